@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use agb_core::gameboy::cartridge::camera::{ImageSource, SENSOR_WIDTH, SENSOR_HEIGHT};
+use image;
+
+/// Shows the camera the same picture (loaded from an image file and scaled to the sensor's resolution) every capture.
+pub struct ImageFileSource {
+	pixels: Box<[u8]>
+}
+
+impl ImageFileSource {
+	pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<ImageFileSource> {
+		let gray = image::open(path)?.to_luma();
+		let scaled = image::imageops::resize(&gray, SENSOR_WIDTH as u32, SENSOR_HEIGHT as u32, image::FilterType::Triangle);
+		Ok(ImageFileSource {
+			pixels: scaled.into_raw().into_boxed_slice()
+		})
+	}
+}
+
+impl ImageSource for ImageFileSource {
+	fn capture(&mut self) -> Box<[u8]> {
+		self.pixels.clone()
+	}
+}
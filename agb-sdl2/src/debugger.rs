@@ -30,6 +30,7 @@ pub fn debug(input: String, gameboy: &mut Gameboy, paused: &mut bool, state: &mu
 			"assembly" => assembly(gameboy),
 			"dump_tiles" => dump_tiles(&mut command, gameboy),
 			"dump_bg" => dump_bg(&mut command, gameboy),
+			"dump_photos" => dump_photos(&mut command, gameboy),
 			"reset" => {
 				gameboy.reset();
 			},
@@ -56,6 +57,7 @@ pub fn debug(input: String, gameboy: &mut Gameboy, paused: &mut bool, state: &mu
 				reset                     - reset the gameboy (keeps breakpoints and any rom/ram patches)\n\
 				dump_tiles <filename>     - dumps the tiles in vram as an image named <filename>.png (or tiles.png if no filename is provided)\n\
 				dump_bg <filename>        - dumps the background as an image to <filename>.png (or bg.png if no filename is provided)\n\
+				dump_photos <prefix>      - dumps the photos saved on a game boy camera as images named <prefix>_<n>.png (or photo_<n>.png if no prefix is provided)\n\
 				quit | exit               - terminate the emulator");
 			},
 			_ => { println!("invalid command (try typing 'help')"); }
@@ -266,6 +268,41 @@ pub fn dump_bg(command: &mut SplitWhitespace, gameboy: &mut Gameboy) {
 	};
 }
 
+pub fn dump_photos(command: &mut SplitWhitespace, gameboy: &mut Gameboy) {
+	let prefix = command.next().unwrap_or("photo");
+	let photos = match gameboy.cart.camera_photos() {
+		Ok(photos) => photos,
+		Err(e) => {
+			println!("{}", e);
+			return;
+		}
+	};
+	if photos.is_empty() {
+		println!("there are no saved photos");
+	}
+	for (index, raw) in photos.iter().enumerate() {
+		let path = format!("{}_{}.png", prefix, index + 1);
+		match File::create(&path) {
+			Ok(file) => {
+				//Convert the u32 pixels into rgba structs for the image library
+				let mut buffer: Vec<u8> = Vec::with_capacity(raw.data.len() * 4);
+				for val in raw.data.iter() {
+					buffer.push((val >> 24) as u8);
+					buffer.push((val >> 16) as u8);
+					buffer.push((val >> 8) as u8);
+					buffer.push((val & 0xFF) as u8);
+				}
+				let encoder = image::png::PNGEncoder::new(file);
+				match encoder.encode(buffer.as_slice(), raw.width as u32, raw.height as u32, image::ColorType::RGBA(8)) {
+					Ok(_) => {},
+					Err(_) => println!("failed to save {} to disk", path)
+				};
+			},
+			Err(e) => println!("{}", e),
+		};
+	}
+}
+
 pub fn assembly(gameboy: &mut Gameboy) {
 	use std::cmp::min;
 
@@ -281,7 +318,7 @@ pub fn assembly(gameboy: &mut Gameboy) {
 			0 => { println!("{:04X}: {} <---", (offset + start) as u16, op); },
 			_ => { println!("{:04X}: {}", (offset + start) as u16, op); },
 		};
-		offset += assembly::INSTRUCTION_LENGTH[data[offset] as usize];
+		offset += assembly::instruction_length(data[offset]);
 	}
 }
 
@@ -6,6 +6,7 @@ extern crate image;
 extern crate clap;
 
 mod debugger;
+mod camera;
 
 use std::sync::mpsc::sync_channel;
 use std::thread;
@@ -20,8 +21,12 @@ use std::sync::{Arc, Mutex};
 use std::ops::DerefMut;
 use std::net::{TcpListener, TcpStream, SocketAddr, IpAddr, Ipv4Addr};
 
-use agb_core::gameboy::Gameboy;
+use agb_core::gameboy::{Gameboy, EmulationEvent};
 use agb_core::gameboy::debugger::DebuggerInterface;
+use agb_core::gameboy::cartridge::Cartridge;
+use agb_core::gameboy::cartridge::patch;
+use agb_core::gameboy::cartridge::gbs;
+use agb_core::gameboy::clock::CycleClock;
 
 use sdl2::pixels::Color;
 use sdl2::pixels::PixelFormatEnum;
@@ -32,6 +37,8 @@ use sdl2::keyboard::Keycode;
 use clap::{Arg, App};
 
 const DEFAULT_SCALE: usize = 2;
+/// How often (in frames) the battery save is written, if the game changed it.
+const AUTOSAVE_FRAMES: u32 = 60;
 
 fn main() {
 	/* Create and initialize gameboy */
@@ -42,15 +49,53 @@ fn main() {
 		.author("Achille Heraud <achille@heraud.xyz>")
 		.about("A GameBoy Emulator")
 		.arg(Arg::with_name("rom")
+			.help("cartridge rom, or GBS file to play (page up/down changes the track)")
 			.long("rom")
 			.takes_value(true)
 			.value_name("FILE")
 			.required(true))
+		.arg(Arg::with_name("patch")
+			.help("IPS, UPS or BPS patch applied to the rom when it's loaded")
+			.long("patch")
+			.takes_value(true)
+			.value_name("FILE")
+			.required(false))
 		.arg(Arg::with_name("ram")
+			.help("battery save file, written back when the game saves (created if it doesn't exist)")
 			.long("ram")
 			.takes_value(true)
 			.value_name("FILE")
 			.required(false))
+		.arg(Arg::with_name("boot_rom")
+			.help("boot rom to run before the cartridge (DMG/MGB/SGB or CGB)")
+			.long("boot_rom")
+			.takes_value(true)
+			.value_name("FILE")
+			.required(false))
+		.arg(Arg::with_name("model")
+			.help("hardware model to emulate (picked based on the cartridge if not provided)")
+			.long("model")
+			.takes_value(true)
+			.possible_values(&["dmg0", "dmg", "mgb", "sgb", "sgb2", "cgb", "agb"])
+			.required(false))
+		.arg(Arg::with_name("mbc1m")
+			.help("override the detection of mbc1 multicarts")
+			.long("mbc1m")
+			.takes_value(true)
+			.possible_values(&["on", "off"])
+			.required(false))
+		.arg(Arg::with_name("camera_image")
+			.help("image the game boy camera sees (a test pattern is used if not provided)")
+			.long("camera_image")
+			.takes_value(true)
+			.value_name("FILE")
+			.required(false))
+		.arg(Arg::with_name("rtc_start")
+			.help("start the cartridge's rtc at a fixed date (unix timestamp), it then advances with the emulated time")
+			.long("rtc_start")
+			.takes_value(true)
+			.value_name("TIMESTAMP")
+			.required(false))
 		.arg(Arg::with_name("paused")
 			.long("pause")
 			.short("p")
@@ -75,17 +120,59 @@ fn main() {
 			.required(false))
 		.get_matches();
 
-	let rom = read_file(matches.value_of("rom").unwrap()).expect("Could not open rom file.");
-	let ram: Option<Box<[u8]>> = if let Some(ram_path) = matches.value_of("ram") {
+	let mut rom = read_file(matches.value_of("rom").unwrap()).expect("Could not open rom file.");
+	if let Some(patch_path) = matches.value_of("patch") {
+		let patch = read_file(patch_path).expect("Could not open patch file.");
+		rom = match patch::apply_patch(&rom, &patch) {
+			Ok(patched) => patched,
+			Err(e) => {
+				println!("Failed to apply the patch: {}", e);
+				return;
+			}
+		};
+	}
+	let ram_path: Option<&str> = matches.value_of("ram");
+	let ram: Option<Box<[u8]>> = if let Some(ram_path) = ram_path.filter(|path| Path::new(path).exists()) {
 		Some(read_file(ram_path).expect("failed to read ram file"))
 	}
 	else {
 		None
 	};
+	let boot_rom: Option<Box<[u8]>> = if let Some(boot_rom_path) = matches.value_of("boot_rom") {
+		Some(read_file(boot_rom_path).expect("failed to read boot rom file"))
+	}
+	else {
+		None
+	};
 
 	let start_paused: bool = matches.occurrences_of("paused") > 0;
 
-	let mut gameboy = Gameboy::new(rom, ram).expect("Failed to initialize gameboy");
+	let gameboy = if gbs::is_gbs(&rom) {
+		Gameboy::with_gbs(&rom)
+	}
+	else {
+		match matches.value_of("model") {
+			Some(model) => Gameboy::with_model(model.parse().unwrap(), rom, ram, boot_rom),
+			None => Gameboy::new(rom, ram, boot_rom)
+		}
+	};
+	let mut gameboy = gameboy.expect("Failed to initialize gameboy");
+	if let Some(header) = gameboy.gbs_header() {
+		println!("{} - {} ({}), {} tracks", header.title, header.author, header.copyright, header.songs);
+	}
+	if let Some(multicart) = matches.value_of("mbc1m") {
+		gameboy.cart.set_mbc1_multicart(multicart == "on").expect("Failed to override the mbc1 multicart detection");
+	}
+	if let Some(path) = matches.value_of("camera_image") {
+		let source = camera::ImageFileSource::open(path).expect("Could not open camera image");
+		gameboy.cart.set_camera_image_source(Box::new(source)).expect("Failed to set the camera image");
+	}
+	if let Some(start) = matches.value_of("rtc_start") {
+		let start: i64 = start.parse().expect("Invalid rtc start timestamp");
+		gameboy.set_clock(Box::new(CycleClock::new(start)));
+	}
+	let mut saved_generation: u64 = gameboy.battery_ram_generation();
+	let mut frames_since_autosave: u32 = 0;
 	let paused: Arc<Mutex<bool>> = Arc::new(Mutex::new(start_paused));
 	gameboy.debugger.enable();
 	{
@@ -225,7 +312,11 @@ fn main() {
 	let width: u32 = (agb_core::WIDTH * DEFAULT_SCALE) as u32;
 	let height: u32 = (agb_core::HEIGHT * DEFAULT_SCALE) as u32;
 
-	let window = video_subsystem.window("agb", width, height)
+	let title = match gameboy.cart.get_cart_info().title.as_str() {
+		"" => String::from("agb"),
+		game => format!("agb - {}", game)
+	};
+	let window = video_subsystem.window(&title, width, height)
 		.position_centered()
 		.opengl()
 		.build()
@@ -281,6 +372,8 @@ fn main() {
 
 		for event in event_pump.poll_iter() {
 			match event {
+				Event::KeyDown { keycode: Some(Keycode::PageDown), .. } => change_gbs_track(&mut gameboy, true),
+				Event::KeyDown { keycode: Some(Keycode::PageUp), .. } => change_gbs_track(&mut gameboy, false),
 				Event::KeyDown { keycode, .. } => {
 					if keycode.is_some() {
 						let key = keymap.get(&keycode.unwrap());
@@ -297,6 +390,11 @@ fn main() {
 						}
 					}
 				},
+				Event::MouseMotion { x, y, .. } => {
+					// moving the mouse away from the center of the window tilts the cartridge (for MBC7 carts)
+					let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+					gameboy.set_tilt((x as f32 - center_x) / center_x, (y as f32 - center_y) / center_y);
+				},
 				Event::Quit {..} => {
 					break 'running;
 				},
@@ -304,13 +402,29 @@ fn main() {
 			};
 		}
 
-		let paused = {
+		let is_paused = {
 				*paused.lock().unwrap()
 		};
-		if !paused {
+		if !is_paused {
 			gameboy.emulate(Duration::from_millis(1000 / 60));
+			while let Some(event) = gameboy.poll_event() {
+				match event {
+					EmulationEvent::IllegalOpcode { pc, opcode, bank } => {
+						println!("the cpu locked up after executing illegal opcode 0x{:02X} at 0x{:04X} (bank {})", opcode, pc, bank);
+						*paused.lock().unwrap() = true;
+					}
+				}
+			}
 			draw(&mut gameboy);
 
+			frames_since_autosave += 1;
+			if frames_since_autosave >= AUTOSAVE_FRAMES {
+				frames_since_autosave = 0;
+				if let Some(path) = ram_path {
+					save_battery_ram(&mut gameboy, path, &mut saved_generation);
+				}
+			}
+
 			//60hz
 			let frame_end: u64 = timer_subsystem.performance_counter();
 			let frame_duration: u64 = frame_end - frame_start;
@@ -321,6 +435,32 @@ fn main() {
 			}
 		}
 	}
+
+	if let Some(path) = ram_path {
+		save_battery_ram(&mut gameboy, path, &mut saved_generation);
+	}
+}
+
+/// Play the next (or previous) track of a GBS file, wrapping around at the first/last track.
+fn change_gbs_track(gameboy: &mut Gameboy, forward: bool) {
+	if let (Some(songs), Some(track)) = (gameboy.gbs_header().map(|header| header.songs as u16), gameboy.gbs_track()) {
+		let track = if forward { (track as u16 + 1) % songs } else { (track as u16 + songs - 1) % songs };
+		gameboy.select_gbs_track(track as u8).expect("Failed to change the track");
+		println!("playing track {}/{}", track + 1, songs);
+	}
+}
+
+/// Write the battery backed ram to path, if the cartridge has a battery and it changed since it was last saved.
+fn save_battery_ram(gameboy: &mut Gameboy, path: &str, saved_generation: &mut u64) {
+	let generation = gameboy.battery_ram_generation();
+	if generation == *saved_generation || !gameboy.cart.get_cart_info().battery {
+		return;
+	}
+	let result = File::create(path).and_then(|mut file| file.write_all(&gameboy.export_battery_ram()));
+	match result {
+		Ok(_) => *saved_generation = generation,
+		Err(e) => println!("failed to write the battery save to {}: {}", path, e)
+	}
 }
 
 pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Box<[u8]>, Error> {
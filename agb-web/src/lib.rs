@@ -13,7 +13,9 @@ use wasm_bindgen::JsValue;
 
 use web_sys::CanvasRenderingContext2d;
 
-use agb_core::gameboy::{Gameboy, Key};
+use agb_core::gameboy::{Gameboy, Key, EmulationEvent};
+use agb_core::gameboy::cartridge::patch;
+use agb_core::gameboy::cartridge::gbs;
 
 pub const KEY_UP: u32 = 0;
 pub const KEY_DOWN: u32 = 1;
@@ -26,7 +28,8 @@ pub const KEY_START: u32 = 7;
 
 enum FrontendEvent {
 	Keydown(Key),
-	Keyup(Key)
+	Keyup(Key),
+	Tilt(f32, f32)
 }
 
 #[wasm_bindgen]
@@ -61,12 +64,18 @@ lazy_static! {
 	};
 }
 
-/// Loads a rom + an optional save file.
+/// Loads a rom + an optional save file, or a GBS file to play.
 /// This creates a new Gameboy object.
 /// This can fail: if the rom has an invalid header an alert will be displayed  and an error message will be printed to the console
 #[wasm_bindgen]
 pub fn load_rom(rom: &[u8]) {
-	match Gameboy::new(Box::from(rom.clone()), None) {
+	let gameboy = if gbs::is_gbs(rom) {
+		Gameboy::with_gbs(rom)
+	}
+	else {
+		Gameboy::new(Box::from(rom.clone()), None, None)
+	};
+	match gameboy {
 		Ok(gameboy) => {
 			let mut opt_gameboy = GAMEBOY.lock().unwrap();
 			*opt_gameboy = Some(gameboy);
@@ -79,6 +88,30 @@ pub fn load_rom(rom: &[u8]) {
 	}
 }
 
+/// Loads a rom after applying an IPS, UPS or BPS patch to it.
+/// If the patch can't be applied an alert will be displayed and an error message will be printed to the console
+#[wasm_bindgen]
+pub fn load_patched_rom(rom: &[u8], patch: &[u8]) {
+	match patch::apply_patch(rom, patch) {
+		Ok(patched) => load_rom(&patched),
+		Err(e) => {
+			error(&format!("{}", e));
+			alert(&format!("Failed to apply the patch: {}", e));
+		}
+	}
+}
+
+/// Play another track of the loaded GBS file (counted from 0).
+#[wasm_bindgen]
+pub fn select_track(track: u8) {
+	let mut opt_gameboy = GAMEBOY.lock().unwrap();
+	if let Some(ref mut gameboy) = *opt_gameboy {
+		if let Err(e) = gameboy.select_gbs_track(track) {
+			error(e);
+		}
+	}
+}
+
 #[wasm_bindgen]
 pub fn keydown(keycode: u32) {
 	let sender = FRONTEND_EVENT_CHANNELS.0.lock().unwrap();
@@ -95,6 +128,13 @@ pub fn keyup(keycode: u32) {
 	}
 }
 
+/// Tilt the cartridge (only MBC7 carts have an accelerometer), x and y are the acceleration in g (-1.0 to 1.0).
+#[wasm_bindgen]
+pub fn tilt(x: f32, y: f32) {
+	let sender = FRONTEND_EVENT_CHANNELS.0.lock().unwrap();
+	sender.send(FrontendEvent::Tilt(x, y)).unwrap();
+}
+
 /// Emulate the gameboy for a specific number of milliseconds
 #[wasm_bindgen]
 pub fn emulate(ctx: CanvasRenderingContext2d, ms: u32) {
@@ -112,6 +152,11 @@ pub fn emulate(ctx: CanvasRenderingContext2d, ms: u32) {
 				if let Some(ref mut gameboy) = *opt_gameboy {
 					gameboy.keyup(key);
 				}
+			},
+			FrontendEvent::Tilt(x, y) => {
+				if let Some(ref mut gameboy) = *opt_gameboy {
+					gameboy.set_tilt(x, y);
+				}
 			}
 		}
 	}
@@ -119,6 +164,13 @@ pub fn emulate(ctx: CanvasRenderingContext2d, ms: u32) {
 	if let Some(ref mut gameboy) = *opt_gameboy {
 		let last_frame_counter = gameboy.get_frame_counter();
 		gameboy.emulate(Duration::from_millis(ms as u64));
+		while let Some(event) = gameboy.poll_event() {
+			match event {
+				EmulationEvent::IllegalOpcode { pc, opcode, bank } => {
+					error(&format!("the cpu locked up after executing illegal opcode 0x{:02X} at 0x{:04X} (bank {})", opcode, pc, bank));
+				}
+			}
+		}
 		if gameboy.get_frame_counter() != last_frame_counter {
 			//new frame waiting to be displayed
 			if let Err(e) = draw(ctx, agb_core::WIDTH, agb_core::HEIGHT, gameboy.get_framebuffer_mut()) {
@@ -128,6 +180,15 @@ pub fn emulate(ctx: CanvasRenderingContext2d, ms: u32) {
 	}
 }
 
+/// Is the rumble motor of the cartridge on? (can be polled after each call to emulate)
+#[wasm_bindgen]
+pub fn rumble() -> bool {
+	match *GAMEBOY.lock().unwrap() {
+		Some(ref gameboy) => gameboy.rumble(),
+		None => false
+	}
+}
+
 fn draw(ctx: CanvasRenderingContext2d, width: usize, height: usize, pixels: &mut [u32]) -> Result<(), JsValue> {
 	use wasm_bindgen::Clamped;
 	use web_sys::ImageData;
@@ -26,20 +26,50 @@ agb.then(agb => {
 	let canvas = document.getElementById("agb-canvas");
 
 	let romInput = document.getElementById("rom");
+	let patchInput = document.getElementById("patch");
+	let rom = null;
+	let patch = null;
 	romInput.addEventListener("change", onRomUpload, false);
-	function onRomUpload() {
-		let files = romInput.files;
+	patchInput.addEventListener("change", onPatchUpload, false);
+
+	function readFile(input, callback) {
+		let files = input.files;
 		if(files.length > 0) {
-			let rom = files[0];
 			let fileReader = new FileReader();
 			fileReader.onload = function() {
-				let data = fileReader.result;
-				let array = new Uint8Array(data);
-				agb.load_rom(array);
-				canvas.focus();
+				callback(new Uint8Array(fileReader.result));
 			}
-			fileReader.readAsArrayBuffer(rom);
+			fileReader.readAsArrayBuffer(files[0]);
+		}
+	}
+
+	// the patch (if one was selected) is applied to the rom when it's loaded
+	function loadRom() {
+		if(rom === null) {
+			return;
+		}
+		if(patch === null) {
+			agb.load_rom(rom);
+		}
+		else {
+			agb.load_patched_rom(rom, patch);
 		}
+		canvas.focus();
+	}
+
+	function onRomUpload() {
+		readFile(romInput, function(data) {
+			rom = data;
+			loadRom();
+		});
+	}
+
+	// selecting a patch reloads the current rom with it
+	function onPatchUpload() {
+		readFile(patchInput, function(data) {
+			patch = data;
+			loadRom();
+		});
 	}
 
 	canvas.addEventListener("keydown", function(event) {
@@ -55,6 +85,14 @@ agb.then(agb => {
 		}
 	});
 
+	// moving the mouse away from the center of the canvas tilts the cartridge (for MBC7 carts)
+	canvas.addEventListener("mousemove", function(event) {
+		let rect = canvas.getBoundingClientRect();
+		let x = (event.clientX - rect.left) / rect.width * 2 - 1;
+		let y = (event.clientY - rect.top) / rect.height * 2 - 1;
+		agb.tilt(x, y);
+	});
+
 	function emulateFrame() {
 		let milliseconds = Math.trunc(1000 / FRAMERATE);
 		let start = new Date().getTime();
@@ -0,0 +1,68 @@
+/// Size of the DMG/MGB/SGB boot roms (mapped to 0x0000 - 0x00FF).
+pub const DMG_BOOT_ROM_SIZE: usize = 0x100;
+
+/// Size of the CGB boot rom (mapped to 0x0000 - 0x00FF and 0x0200 - 0x08FF).
+/// The region 0x0100 - 0x01FF of the dump is never visible, the cartridge header is mapped there instead.
+pub const CGB_BOOT_ROM_SIZE: usize = 0x900;
+
+/// The boot rom that is executed when the gameboy is powered on.
+///
+/// The boot rom is mapped over the beginning of the cartridge rom until the program writes to $FF50,
+/// after that it is unmapped and can't be mapped back in until the next power cycle.
+#[derive(Serialize, Deserialize)]
+pub struct BootRom {
+	#[serde(skip)] // don't serialize the boot rom, it needs to already be loaded when a save state is loaded (same as the cartridge rom)
+	pub rom: Box<[u8]>,
+
+	/// Whether or not the boot rom is currently mapped over the cartridge rom.
+	mapped: bool,
+}
+
+impl BootRom {
+	pub fn new(rom: Box<[u8]>) -> Result<BootRom, &'static str> {
+		match rom.len() {
+			DMG_BOOT_ROM_SIZE | CGB_BOOT_ROM_SIZE => Ok(BootRom {
+				rom: rom,
+				mapped: true
+			}),
+			_ => Err("Invalid boot rom size (must be 256 bytes for a DMG/MGB/SGB boot rom, or 2304 bytes for a CGB boot rom)")
+		}
+	}
+
+	/// Is this a CGB boot rom (as opposed to a DMG/MGB/SGB boot rom)?
+	pub fn is_cgb(&self) -> bool {
+		self.rom.len() == CGB_BOOT_ROM_SIZE
+	}
+
+	/// Map the boot rom back over the cartridge rom (on reset).
+	pub fn map(&mut self) {
+		self.mapped = true;
+	}
+
+	/// Is the boot rom currently mapped?
+	pub fn mapped(&self) -> bool {
+		self.mapped
+	}
+
+	/// Is the boot rom currently mapped at this address?
+	pub fn is_mapped(&self, address: u16) -> bool {
+		let address = address as usize;
+		self.mapped && address < self.rom.len() && (address < 0x100 || address >= 0x200)
+	}
+
+	pub fn read_byte(&self, address: u16) -> u8 {
+		self.rom[address as usize]
+	}
+
+	/// $FF50 isn't readable.
+	pub fn read_ff50(&self) -> u8 {
+		0xFF
+	}
+
+	/// Writing a non-zero value to $FF50 unmaps the boot rom.
+	pub fn write_ff50(&mut self, value: u8) {
+		if value != 0 {
+			self.mapped = false;
+		}
+	}
+}
@@ -0,0 +1,265 @@
+use gameboy::{Gameboy, Mode, Model, IO_SIZE};
+use gameboy::cpu::{HRAM_SIZE, ZERO_FLAG_MASK, HALF_CARRY_FLAG_MASK, CARRY_FLAG_MASK};
+use gameboy::cpu::registers::Registers;
+use gameboy::ppu::{PPU, PpuIoRegister, PALETTE_RAM_SIZE};
+use gameboy::timer::TimerRegister;
+use gameboy::cartridge::CartInfo;
+
+/// IO registers (0xFF00 - 0xFF7F) at the end of the dmg boot rom.
+const DMG_IO: [u8; IO_SIZE] = [
+	0xCF, 0x00, 0x7E, 0xFF, 0x19, 0x00, 0x00, 0xF8, //ff00
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE1, //ff08
+	0x80, 0xBF, 0xF3, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, //ff10
+	0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF, //ff18
+	0xFF, 0x00, 0x00, 0xBF, 0x77, 0xF3, 0xF1, 0xFF, //ff20
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff28
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff30
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff38
+	0x91, 0x83, 0x00, 0x00, 0x01, 0x00, 0xFF, 0xFC, //ff40
+	0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, //ff48
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff50
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff58
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff60
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff68
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff70
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff78
+];
+
+/// IO registers (0xFF00 - 0xFF7F) at the end of the cgb boot rom when a cgb game is inserted.
+/// The palette registers (0xFF68 - 0xFF6B) are set up separately, see `PostBootState::bg_palettes`.
+const CGB_IO: [u8; IO_SIZE] = [
+	0xCF, 0x00, 0x7F, 0xFF, 0x00, 0x00, 0x00, 0xF8, //ff00
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE1, //ff08
+	0x80, 0xBF, 0xF3, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, //ff10
+	0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF, //ff18
+	0xFF, 0x00, 0x00, 0xBF, 0x77, 0xF3, 0xF1, 0xFF, //ff20
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff28
+	0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, //ff30
+	0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, //ff38
+	0x91, 0x85, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFC, //ff40
+	0xFF, 0xFF, 0x00, 0x00, 0xFF, 0x7E, 0xFF, 0xFE, //ff48
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x3E, 0xFF, //ff50
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff58
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff60
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff68
+	0xF8, 0xFF, 0x00, 0x00, 0x00, 0x8F, 0x00, 0x00, //ff70
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff78
+];
+
+/// IO registers (0xFF00 - 0xFF7F) at the end of the cgb boot rom when a dmg game is inserted.
+/// The boot rom locks the cgb only registers before it jumps to the cartridge, so they read as 0xFF.
+const CGB_DMG_COMPAT_IO: [u8; IO_SIZE] = [
+	0xCF, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8, //ff00
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE1, //ff08
+	0x80, 0xBF, 0xF3, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, //ff10
+	0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF, //ff18
+	0xFF, 0x00, 0x00, 0xBF, 0x77, 0xF3, 0xF1, 0xFF, //ff20
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff28
+	0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, //ff30
+	0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, //ff38
+	0x91, 0x85, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFC, //ff40
+	0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, //ff48
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff50
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff58
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff60
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff68
+	0xFF, 0xFF, 0x00, 0x00, 0xFF, 0x8F, 0x00, 0x00, //ff70
+	0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff78
+];
+
+/// The colors the cgb boot rom loads into the palettes of dmg games that it doesn't recognize.
+/// Games with a title that is in the boot rom's lookup table get a different palette on real hardware,
+/// that table isn't included here, so every dmg game gets the default one.
+const CGB_DMG_COMPAT_BG_PALETTE: [u16; 4] = [ 0x7FFF, 0x1BEF, 0x6180, 0x0000 ];
+const CGB_DMG_COMPAT_OBJ_PALETTE: [u16; 4] = [ 0x7FFF, 0x421F, 0x1CF2, 0x0000 ];
+
+/// The cgb boot rom initializes all of the background palettes to white in cgb mode.
+const WHITE: u16 = 0x7FFF;
+
+/// The ppu registers that are copied from the io table when the post boot state is applied.
+const PPU_REGISTERS: [PpuIoRegister; 10] = [
+	PpuIoRegister::Lcdc, PpuIoRegister::Stat, PpuIoRegister::Scy, PpuIoRegister::Scx, PpuIoRegister::Lyc,
+	PpuIoRegister::Bgp, PpuIoRegister::Obp0, PpuIoRegister::Obp1, PpuIoRegister::Wy, PpuIoRegister::Wx
+];
+
+/// The state the boot rom leaves the hardware in when it jumps to the cartridge entry point at 0x0100.
+/// This is used to start the gameboy when no boot rom is loaded.
+pub struct PostBootState {
+	pub registers: Registers,
+	pub io: [u8; IO_SIZE],
+	pub hram: [u8; HRAM_SIZE],
+	/// Contents of the cgb background palette ram (little endian 15-bit colors).
+	pub bg_palettes: [u8; PALETTE_RAM_SIZE],
+	/// Contents of the cgb object palette ram (little endian 15-bit colors).
+	pub obj_palettes: [u8; PALETTE_RAM_SIZE],
+}
+
+impl PostBootState {
+	/// Select the post boot state for the hardware model, and the mode the game is running in
+	/// (a dmg game on a cgb runs in dmg mode, with a different state than on a dmg).
+	pub fn new(model: Model, mode: Mode, cart_info: &CartInfo, rom: &[u8]) -> PostBootState {
+		match (model.is_cgb(), mode) {
+			(true, Mode::CGB) => PostBootState::cgb(model),
+			(true, Mode::DMG) => PostBootState::cgb_dmg_compat(model, cart_info, rom),
+			(false, _) => PostBootState::dmg(model, cart_info),
+		}
+	}
+
+	/// State at the end of the dmg0/dmg/mgb/sgb/sgb2 boot roms.
+	pub fn dmg(model: Model, cart_info: &CartInfo) -> PostBootState {
+		let mut registers: Registers = Default::default();
+		registers.init_dmg();
+		let mut io = DMG_IO;
+
+		match model {
+			Model::DMG0 => {
+				registers.f = 0x00;
+				registers.b = 0xFF;
+				registers.e = 0xC1;
+				registers.h = 0x84;
+				registers.l = 0x03;
+			},
+			Model::SGB | Model::SGB2 => {
+				registers.f = 0x00;
+				registers.c = 0x14;
+				registers.e = 0x00;
+				registers.h = 0xC0;
+				registers.l = 0x60;
+				// the sgb boot rom doesn't play the startup sound, so NR52 doesn't have channel 1 active
+				io[0x26] = 0xF0;
+			},
+			_ => {
+				// the half carry and carry flags are only set if the header checksum is non-zero
+				if cart_info.header_checksum == 0 {
+					registers.f = 0x80;
+				}
+			}
+		}
+
+		// the mgb/sgb2 can be detected by the value of a
+		if model == Model::MGB || model == Model::SGB2 {
+			registers.a = 0xFF;
+		}
+
+		PostBootState {
+			registers: registers,
+			io: io,
+			hram: [0; HRAM_SIZE],
+			bg_palettes: [0; PALETTE_RAM_SIZE],
+			obj_palettes: [0; PALETTE_RAM_SIZE],
+		}
+	}
+
+	/// State at the end of the cgb/agb boot rom, with a cgb game inserted.
+	pub fn cgb(model: Model) -> PostBootState {
+		let mut registers: Registers = Default::default();
+		registers.init_cgb();
+		if model == Model::AGB {
+			agb_inc_b(&mut registers);
+		}
+
+		PostBootState {
+			registers: registers,
+			io: CGB_IO,
+			hram: [0; HRAM_SIZE],
+			bg_palettes: palette_ram(&[WHITE; 4], &[WHITE; 4]),
+			obj_palettes: [0; PALETTE_RAM_SIZE],
+		}
+	}
+
+	/// State at the end of the cgb/agb boot rom, with a dmg game inserted.
+	pub fn cgb_dmg_compat(model: Model, cart_info: &CartInfo, rom: &[u8]) -> PostBootState {
+		let mut registers: Registers = Default::default();
+		registers.init_cgb();
+
+		// b holds the title checksum that the boot rom uses to look up the palette, but only for games published by nintendo
+		registers.b = if cart_info.licensee.is_nintendo() {
+			title_checksum(rom)
+		}
+		else {
+			0
+		};
+		registers.d = 0x00;
+		registers.e = 0x08;
+		if registers.b == 0x43 || registers.b == 0x58 {
+			registers.h = 0x99;
+			registers.l = 0x1A;
+		}
+		else {
+			registers.h = 0x00;
+			registers.l = 0x7C;
+		}
+		if model == Model::AGB {
+			agb_inc_b(&mut registers);
+		}
+
+		PostBootState {
+			registers: registers,
+			io: CGB_DMG_COMPAT_IO,
+			hram: [0; HRAM_SIZE],
+			bg_palettes: palette_ram(&CGB_DMG_COMPAT_BG_PALETTE, &[WHITE; 4]),
+			obj_palettes: palette_ram(&CGB_DMG_COMPAT_OBJ_PALETTE, &CGB_DMG_COMPAT_OBJ_PALETTE),
+		}
+	}
+
+	/// Put the gameboy into this state.
+	/// The divider and dma registers aren't written to, because writing to them has side effects,
+	/// their components start in the right state already.
+	pub fn apply(&self, gameboy: &mut Gameboy) {
+		gameboy.cpu.registers = self.registers;
+		gameboy.cpu.interrupt_flag.write(self.io[0x0F]);
+		gameboy.cpu.hram.copy_from_slice(&self.hram);
+		gameboy.io.copy_from_slice(&self.io);
+
+		gameboy.joypad.write_joyp(self.io[0x00]);
+		gameboy.serial.write_sb(self.io[0x01]);
+		gameboy.serial.write_sc(self.io[0x02]);
+
+		for register in [TimerRegister::Tima, TimerRegister::Tma, TimerRegister::Tac].iter() {
+			gameboy.timer.write_io(*register, self.io[(register.address() - 0xFF00) as usize]);
+		}
+
+		for register in PPU_REGISTERS.iter() {
+			gameboy.ppu.write_io(*register, self.io[(register.address() - 0xFF00) as usize]);
+		}
+
+		// load the palette ram through the palette index/data registers, starting at index 0 with auto increment
+		gameboy.ppu.write_io(PpuIoRegister::Bgpi, 0x80);
+		for value in self.bg_palettes.iter() {
+			gameboy.ppu.write_io(PpuIoRegister::Bgpd, *value);
+		}
+		gameboy.ppu.write_io(PpuIoRegister::Obpi, 0x80);
+		for value in self.obj_palettes.iter() {
+			gameboy.ppu.write_io(PpuIoRegister::Obpd, *value);
+		}
+	}
+}
+
+/// The agb boot rom is the same as the cgb boot rom, except it executes an `INC B` right before jumping to the cartridge,
+/// games use this to detect that they are running on an agb.
+fn agb_inc_b(registers: &mut Registers) {
+	registers.b = registers.b.wrapping_add(1);
+	let carry = registers.f & CARRY_FLAG_MASK;
+	let zero = if registers.b == 0 { ZERO_FLAG_MASK } else { 0 };
+	let half_carry = if registers.b & 0x0F == 0 { HALF_CARRY_FLAG_MASK } else { 0 };
+	registers.f = zero | half_carry | carry;
+}
+
+/// Sum of the 16 bytes of the title field in the cartridge header.
+fn title_checksum(rom: &[u8]) -> u8 {
+	rom[0x0134..0x0144].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Build the contents of palette ram, with `first` in palette 0 and `rest` in palettes 1-7.
+fn palette_ram(first: &[u16; 4], rest: &[u16; 4]) -> [u8; PALETTE_RAM_SIZE] {
+	let mut ram = [0; PALETTE_RAM_SIZE];
+	for palette in 0..8 {
+		let colors = if palette == 0 { first } else { rest };
+		for (i, color) in colors.iter().enumerate() {
+			let index = (palette * 4 + i) * 2;
+			ram[index] = *color as u8;
+			ram[index + 1] = (*color >> 8) as u8;
+		}
+	}
+	ram
+}
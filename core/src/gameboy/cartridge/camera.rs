@@ -0,0 +1,291 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use gameboy::ppu::Bitmap;
+use super::MemoryBankController;
+
+/// The resolution of the images the sensor captures (16x14 tiles).
+pub const SENSOR_WIDTH: usize = 128;
+pub const SENSOR_HEIGHT: usize = 112;
+
+/// 16x14 2bpp tiles
+pub const PHOTO_SIZE: usize = SENSOR_WIDTH * SENSOR_HEIGHT / 4;
+
+/// Captured images are written to this offset in ram bank 0.
+const CAPTURE_ADDRESS: usize = 0x0100;
+
+/* The album: 30 photo slots (2 per ram bank starting at bank 1), and the state vector in bank 0
+ * that contains the position of each slot in the album (0xFF if the slot is empty) */
+pub const PHOTO_SLOTS: usize = 30;
+const PHOTO_SLOT_ADDRESS: usize = 0x2000;
+const PHOTO_SLOT_SIZE: usize = 0x1000;
+const PHOTO_STATE_VECTOR: usize = 0x11B2;
+
+/* Bit 4 of the value written to 0x4000 - 0x5FFF maps the camera registers to 0xA000 - 0xBFFF instead of the ram */
+const REGISTER_SELECT: u8 = 0x10;
+
+/* Registers (mirrored every 0x80 bytes) */
+const REG_CONTROL: usize = 0x00;           /* bit 0: start capture / busy */
+const REG_GAIN: usize = 0x01;              /* bits 5-7: edge mode (0b111 for 2d enhancement), bits 0-4: gain */
+const REG_EXPOSURE_HIGH: usize = 0x02;
+const REG_EXPOSURE_LOW: usize = 0x03;
+const REG_EDGE: usize = 0x04;              /* bits 4-6: edge enhancement ratio, bit 3: invert output */
+const REG_DITHER: usize = 0x06;            /* 0x06 - 0x35: 4x4 matrix, 3 thresholds per pixel */
+const NUM_REGISTERS: usize = 0x36;
+
+/* Exposure time that leaves the sensor values unchanged */
+const EXPOSURE_NOMINAL: f32 = 0x1000 as f32;
+
+const EDGE_RATIOS: [f32; 8] = [0.5, 0.75, 1.0, 1.25, 2.0, 3.0, 4.0, 5.0];
+
+/* RGBA shades used when decoding photos */
+const PHOTO_SHADES: [u32; 4] = [0xFFFFFFFF, 0xAAAAAAFF, 0x555555FF, 0x000000FF];
+
+/// Supplies the frames the camera's sensor sees.
+pub trait ImageSource: Send {
+	/// Return a SENSOR_WIDTH x SENSOR_HEIGHT grayscale frame (row major, 0 is black and 255 is white).
+	fn capture(&mut self) -> Box<[u8]>;
+}
+
+/// The image source used until the frontend sets one: a diagonal gradient that scrolls a bit every capture,
+/// so the camera works (and games can see that the picture is changing) without a webcam.
+pub struct TestPattern {
+	frame: usize
+}
+
+impl TestPattern {
+	pub fn new() -> TestPattern {
+		TestPattern {
+			frame: 0
+		}
+	}
+}
+
+impl ImageSource for TestPattern {
+	fn capture(&mut self) -> Box<[u8]> {
+		let mut buf = Vec::with_capacity(SENSOR_WIDTH * SENSOR_HEIGHT);
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let value = (x + y + self.frame) % (SENSOR_WIDTH + SENSOR_HEIGHT);
+				buf.push((value * 255 / (SENSOR_WIDTH + SENSOR_HEIGHT - 1)) as u8);
+			}
+		}
+		self.frame = self.frame.wrapping_add(1);
+		buf.into_boxed_slice()
+	}
+}
+
+fn default_image_source() -> Box<ImageSource> {
+	Box::new(TestPattern::new())
+}
+
+/// The Game Boy Camera (POCKET CAMERA) has 128KiB of ram and an image sensor, whose registers are mapped to
+/// 0xA000 - 0xBFFF instead of the ram when bit 4 of the ram bank register is set.
+/// Writing 1 to bit 0 of 0xA000 captures an image: the sensor values are scaled by the gain and exposure time,
+/// optionally edge enhanced, then turned into 4 shades with the dithering matrix, and written as 2bpp tiles to ram
+/// bank 0 at 0xA100. Captures finish immediately, so the busy bit always reads 0.
+#[derive(Serialize, Deserialize)]
+pub struct Camera {
+	rom_bank: u8,         /* current rom bank (6 bits, bank 0 can be selected) */
+	ram_bank: u8,         /* current ram bank (4 bits) */
+	registers_mapped: bool,
+	ram_enable: bool,     /* ram is always readable, but it can only be written to when enabled */
+	registers: Box<[u8]>,
+	#[serde(skip, default = "default_image_source")]
+	image_source: Box<ImageSource>
+}
+
+impl Camera {
+	pub fn new() -> Camera {
+		Camera {
+			rom_bank: 1,
+			ram_bank: 0,
+			registers_mapped: false,
+			ram_enable: false,
+			registers: vec![0; NUM_REGISTERS].into_boxed_slice(),
+			image_source: default_image_source()
+		}
+	}
+
+	pub fn set_image_source(&mut self, source: Box<ImageSource>) {
+		self.image_source = source;
+	}
+
+	pub fn swap_image_source(&mut self, other: &mut Camera) {
+		use std::mem::swap;
+		swap(&mut self.image_source, &mut other.image_source);
+	}
+
+	/// Capture an image and write it to ram as 2bpp tiles.
+	fn capture(&mut self, ram: &mut [u8]) {
+		let frame = self.image_source.capture();
+		if frame.len() < SENSOR_WIDTH * SENSOR_HEIGHT || ram.len() < CAPTURE_ADDRESS + PHOTO_SIZE {
+			return;
+		}
+
+		let gain = 0.88 + (self.registers[REG_GAIN] & 0x1F) as f32 * 0.026; // roughly 0.88x - 1.69x
+		let exposure = ((self.registers[REG_EXPOSURE_HIGH] as u16) << 8 | self.registers[REG_EXPOSURE_LOW] as u16) as f32;
+		let scale = gain * exposure / EXPOSURE_NOMINAL;
+		let sensor = |x: isize, y: isize| -> f32 {
+			let x = x.max(0).min(SENSOR_WIDTH as isize - 1) as usize;
+			let y = y.max(0).min(SENSOR_HEIGHT as isize - 1) as usize;
+			frame[y * SENSOR_WIDTH + x] as f32 * scale
+		};
+
+		let edge_enhance = self.registers[REG_GAIN] & 0xE0 == 0xE0;
+		let edge_ratio = EDGE_RATIOS[((self.registers[REG_EDGE] >> 4) & 0x07) as usize];
+		let invert = self.registers[REG_EDGE] & 0x08 != 0;
+
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let (sx, sy) = (x as isize, y as isize);
+				let mut value = sensor(sx, sy);
+				if edge_enhance {
+					let neighbours = sensor(sx - 1, sy) + sensor(sx + 1, sy) + sensor(sx, sy - 1) + sensor(sx, sy + 1);
+					value += (value * 4.0 - neighbours) * edge_ratio;
+				}
+				let mut value = value.max(0.0).min(255.0) as u8;
+				if invert {
+					value = 255 - value;
+				}
+
+				let thresholds = REG_DITHER + ((y % 4) * 4 + (x % 4)) * 3;
+				let shade: u8 = if value < self.registers[thresholds] { 3 }
+					else if value < self.registers[thresholds + 1] { 2 }
+					else if value < self.registers[thresholds + 2] { 1 }
+					else { 0 };
+
+				let tile = (y / 8) * (SENSOR_WIDTH / 8) + (x / 8);
+				let address = CAPTURE_ADDRESS + tile * 16 + (y % 8) * 2;
+				let bit = 7 - (x % 8);
+				ram[address] = (ram[address] & !(1 << bit)) | ((shade & 1) << bit);
+				ram[address + 1] = (ram[address + 1] & !(1 << bit)) | (((shade >> 1) & 1) << bit);
+			}
+		}
+	}
+}
+
+impl MemoryBankController for Camera {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	/// Only the control register can be read, the others read as 0.
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		if self.registers_mapped {
+			if offset as usize & 0x7F == REG_CONTROL {
+				self.registers[REG_CONTROL] & 0x06 // never busy
+			}
+			else {
+				0x00
+			}
+		}
+		else {
+			let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+			if address < ram_size {
+				ram[address]
+			}
+			else {
+				0xFF
+			}
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x1FFF - RAM write enable (0x0A)
+		//0x2000...0x3FFF - ROM Bank number (6 bits)
+		//0x4000...0x5FFF - RAM Bank number (4 bits), or 0x10 to map the camera registers
+		//0x6000...0x7FFF - nothing
+		match address {
+			0x0000...0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+			0x2000...0x3FFF => self.rom_bank = value & 0x3F,
+			0x4000...0x5FFF => {
+				self.registers_mapped = value & REGISTER_SELECT != 0;
+				if !self.registers_mapped {
+					self.ram_bank = value & 0x0F;
+				}
+			},
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		if self.registers_mapped {
+			let register = offset as usize & 0x7F;
+			if register < NUM_REGISTERS {
+				self.registers[register] = value;
+				if register == REG_CONTROL && value & 1 == 1 {
+					// the photo is written to the (battery backed) ram
+					let size = ram_size.min(ram.len());
+					self.capture(&mut ram[..size]);
+					self.registers[REG_CONTROL] &= !1;
+					return true;
+				}
+			}
+			false
+		}
+		else if self.ram_enable {
+			let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+			address < ram_size && write_ram(ram, address, value)
+		}
+		else {
+			false
+		}
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_bank as usize
+	}
+}
+
+/// Decode PHOTO_SIZE bytes of 2bpp tile data (16x14 tiles) into an RGBA image.
+pub fn decode_photo(data: &[u8]) -> Bitmap<u32> {
+	let mut pixels = vec![PHOTO_SHADES[0]; SENSOR_WIDTH * SENSOR_HEIGHT];
+	for y in 0..SENSOR_HEIGHT {
+		for x in 0..SENSOR_WIDTH {
+			let tile = (y / 8) * (SENSOR_WIDTH / 8) + (x / 8);
+			let address = tile * 16 + (y % 8) * 2;
+			if address + 1 < data.len() {
+				let bit = 7 - (x % 8);
+				let shade = ((data[address] >> bit) & 1) | (((data[address + 1] >> bit) & 1) << 1);
+				pixels[y * SENSOR_WIDTH + x] = PHOTO_SHADES[shade as usize];
+			}
+		}
+	}
+	Bitmap {
+		width: SENSOR_WIDTH,
+		height: SENSOR_HEIGHT,
+		data: pixels.into_boxed_slice()
+	}
+}
+
+/// Decode the photos saved in the album, in the order they appear in it.
+pub fn saved_photos(ram: &[u8]) -> Vec<Bitmap<u32>> {
+	if ram.len() < PHOTO_SLOT_ADDRESS + PHOTO_SLOTS * PHOTO_SLOT_SIZE {
+		return Vec::new();
+	}
+	let mut slots: Vec<(u8, usize)> = (0..PHOTO_SLOTS)
+		.map(|slot| (ram[PHOTO_STATE_VECTOR + slot], slot))
+		.filter(|&(position, _)| position != 0xFF)
+		.collect();
+	slots.sort();
+	slots.iter()
+		.map(|&(_, slot)| {
+			let address = PHOTO_SLOT_ADDRESS + slot * PHOTO_SLOT_SIZE;
+			decode_photo(&ram[address..address + PHOTO_SIZE])
+		})
+		.collect()
+}
@@ -0,0 +1,234 @@
+use gameboy::cartridge::{CartInfo, ROM_BANK_SIZE, NINTENDO_LOGO};
+
+/// GBS files start with a 0x70 byte header, followed by the music driver's code and data.
+pub const GBS_HEADER_SIZE: usize = 0x70;
+const GBS_MAGIC: &[u8] = b"GBS";
+
+/* The driver is placed right after the cartridge header, the rst handlers and interrupt vectors are below it */
+const DRIVER_ADDRESS: usize = 0x0150;
+const PLAY_HANDLER_ADDRESS: usize = 0x0068;
+const VBLANK_VECTOR: usize = 0x0040;
+const TIMER_VECTOR: usize = 0x0050;
+
+/* The pseudo cartridge is an MBC5 with 8KiB of ram, GBS drivers switch banks by writing to 0x2000 */
+const CART_TYPE_MBC5_RAM: u8 = 0x1A;
+const RAM_SIZE_8KB: u8 = 0x02;
+
+/* Opcodes used by the driver */
+const OP_NOP: u8 = 0x00;
+const OP_JP: u8 = 0xC3;
+const OP_CALL: u8 = 0xCD;
+const OP_RETI: u8 = 0xD9;
+
+/// The header of a GBS file, the addresses are where the driver's routines are once it's loaded.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct GbsHeader {
+	pub version: u8,
+	/// The number of songs (tracks are counted from 0 to songs - 1).
+	pub songs: u8,
+	/// The song to play first (counted from 1, like in the file).
+	pub first_song: u8,
+	/// Where the data after the header is loaded (0x0400 - 0x7FFF).
+	pub load_address: u16,
+	/// Called with the track number in a, before the song plays.
+	pub init_address: u16,
+	/// Called once per v-blank, or on every timer interrupt if the timer is used.
+	pub play_address: u16,
+	pub stack_pointer: u16,
+	/// TMA and TAC, play is driven by the timer if bit 2 of TAC is set, bit 7 selects cgb double speed.
+	pub timer_modulo: u8,
+	pub timer_control: u8,
+	pub title: String,
+	pub author: String,
+	pub copyright: String
+}
+
+/// A GBS file being played, the track is kept here (and in save states) rather than in the pseudo cartridge's rom.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Gbs {
+	pub header: GbsHeader,
+	/// The track being played (counted from 0), the driver gets it in e when the gameboy is reset.
+	pub track: u8
+}
+
+impl Gbs {
+	pub fn new(header: GbsHeader) -> Gbs {
+		let track = header.first_track();
+		Gbs {
+			header: header,
+			track: track
+		}
+	}
+}
+
+impl GbsHeader {
+	pub fn new(gbs: &[u8]) -> Result<GbsHeader, &'static str> {
+		if !is_gbs(gbs) {
+			return Err("Not a GBS file");
+		}
+		if gbs.len() < GBS_HEADER_SIZE {
+			return Err("GBS file is too small to contain a header");
+		}
+		let word = |offset: usize| gbs[offset] as u16 | (gbs[offset + 1] as u16) << 8;
+		let header = GbsHeader {
+			version: gbs[0x03],
+			songs: gbs[0x04],
+			first_song: gbs[0x05],
+			load_address: word(0x06),
+			init_address: word(0x08),
+			play_address: word(0x0A),
+			stack_pointer: word(0x0C),
+			timer_modulo: gbs[0x0E],
+			timer_control: gbs[0x0F],
+			title: GbsHeader::get_string(&gbs[0x10..0x30]),
+			author: GbsHeader::get_string(&gbs[0x30..0x50]),
+			copyright: GbsHeader::get_string(&gbs[0x50..0x70])
+		};
+		if header.version != 1 {
+			return Err("Unsupported GBS version");
+		}
+		if header.songs == 0 {
+			return Err("GBS file doesn't have any songs");
+		}
+		if (header.load_address as usize) < 0x0400 || header.load_address >= 0x8000 {
+			return Err("GBS load address is outside of 0x0400 - 0x7FFF");
+		}
+		Ok(header)
+	}
+
+	/// Does the timer interrupt call play (instead of v-blank)?
+	pub fn uses_timer(&self) -> bool {
+		self.timer_control & 0x04 != 0
+	}
+
+	/// Does the driver expect the cpu to run in cgb double speed mode?
+	pub fn double_speed(&self) -> bool {
+		self.timer_control & 0x80 != 0
+	}
+
+	/// The track played first (counted from 0).
+	pub fn first_track(&self) -> u8 {
+		match self.first_song {
+			0 => 0,
+			song if song > self.songs => 0,
+			song => song - 1
+		}
+	}
+
+	fn get_string(field: &[u8]) -> String {
+		let end = field.iter().position(|c| *c == 0).unwrap_or(field.len());
+		String::from_utf8_lossy(&field[..end]).trim_end().to_string()
+	}
+}
+
+pub fn is_gbs(data: &[u8]) -> bool {
+	data.starts_with(GBS_MAGIC)
+}
+
+/// Build the rom of a pseudo cartridge that plays a GBS file. The file's data is loaded at its load address,
+/// and bank 0 gets a cartridge header and a minimal driver:
+/// 0x0000 - 0x0038: rst handlers, they jump to the same offset from the load address (like GBS players do)
+/// 0x0040 / 0x0050: the v-blank or timer interrupt calls play (the unused one returns immediately)
+/// 0x0100: jumps to the driver, which clears the ram, sets up the stack and timer, and calls init with the track number,
+///         then halts until the next interrupt forever.
+/// The driver passes e to init as the track number, it isn't used before that, so it's set when the gameboy is reset.
+pub fn gbs_rom(gbs: &[u8], header: &GbsHeader) -> Result<Box<[u8]>, &'static str> {
+	let data = &gbs[GBS_HEADER_SIZE..];
+	let load_address = header.load_address as usize;
+	let banks = ((load_address + data.len() + ROM_BANK_SIZE - 1) / ROM_BANK_SIZE).next_power_of_two().max(2);
+	if banks > 512 {
+		return Err("GBS file is too big (more than 8MiB)");
+	}
+	let mut rom = vec![0xFF; banks * ROM_BANK_SIZE];
+	rom[load_address..load_address + data.len()].copy_from_slice(data);
+
+	// rst handlers
+	for rst in 0..8 {
+		jump(&mut rom, rst * 8, load_address + rst * 8);
+	}
+
+	// interrupt vectors (v-blank, lcd stat, timer, serial, joypad)
+	for vector in 0..5 {
+		rom[VBLANK_VECTOR + vector * 8] = OP_RETI;
+	}
+	let play_vector = if header.uses_timer() { TIMER_VECTOR } else { VBLANK_VECTOR };
+	jump(&mut rom, play_vector, PLAY_HANDLER_ADDRESS);
+	let play = header.play_address;
+	rom[PLAY_HANDLER_ADDRESS..PLAY_HANDLER_ADDRESS + 4].copy_from_slice(&[OP_CALL, play as u8, (play >> 8) as u8, OP_RETI]);
+
+	// entry point
+	rom[0x0100] = OP_NOP;
+	jump(&mut rom, 0x0101, DRIVER_ADDRESS);
+
+	let driver = driver(header);
+	if DRIVER_ADDRESS + driver.len() > load_address {
+		return Err("GBS load address is too low to fit the driver");
+	}
+	rom[DRIVER_ADDRESS..DRIVER_ADDRESS + driver.len()].copy_from_slice(&driver);
+
+	write_cartridge_header(&mut rom, header, banks);
+	Ok(rom.into_boxed_slice())
+}
+
+fn jump(rom: &mut [u8], at: usize, to: usize) {
+	rom[at..at + 3].copy_from_slice(&[OP_JP, to as u8, (to >> 8) as u8]);
+}
+
+/// The driver's code, it only uses a, hl and sp before calling init.
+fn driver(header: &GbsHeader) -> Vec<u8> {
+	let mut code: Vec<u8> = Vec::new();
+	code.push(0xF3);                                      // di
+	if header.double_speed() {
+		// only switch speed on a cgb (KEY1 reads 0xFF on a dmg)
+		code.extend_from_slice(&[0xF0, 0x4D]);            // ldh a, [KEY1]
+		code.push(0x3C);                                  // inc a
+		code.extend_from_slice(&[0x28, 0x06]);            // jr z, +6
+		code.extend_from_slice(&[0x3E, 0x01, 0xE0, 0x4D]); // ld a, 1; ldh [KEY1], a
+		code.extend_from_slice(&[0x10, 0x00]);            // stop
+	}
+	code.extend_from_slice(&[0x3E, 0x0A, 0xEA, 0x00, 0x00]); // ld a, 0x0A; ld [0x0000], a (enable the ram)
+	code.extend_from_slice(&[0x3E, 0x01, 0xEA, 0x00, 0x20]); // ld a, 1; ld [0x2000], a (rom bank 1)
+
+	// clear the cartridge ram and wram (0xA000 - 0xDFFF)
+	code.extend_from_slice(&[0x21, 0x00, 0xA0]);          // ld hl, 0xA000
+	code.push(0xAF);                                      // xor a
+	code.extend_from_slice(&[0x22; 8]);                   // ld [hl+], a (8 times, so the track starts sooner)
+	code.push(0x7C);                                      // ld a, h
+	code.extend_from_slice(&[0xFE, 0xE0]);                // cp 0xE0
+	code.extend_from_slice(&[0x20, 0xF2]);                // jr nz, -14 (xor a)
+
+	let sp = header.stack_pointer;
+	let interrupt = if header.uses_timer() { 0x04 } else { 0x01 };
+	code.extend_from_slice(&[0x31, sp as u8, (sp >> 8) as u8]);        // ld sp, stack pointer
+	code.extend_from_slice(&[0x3E, header.timer_modulo, 0xE0, 0x06]); // ld a, tma; ldh [TMA], a
+	code.extend_from_slice(&[0x3E, header.timer_control & 0x07, 0xE0, 0x07]); // ld a, tac; ldh [TAC], a
+	code.extend_from_slice(&[0x3E, interrupt, 0xE0, 0xFF]);           // ld a, interrupt; ldh [IE], a
+	code.extend_from_slice(&[0xAF, 0xE0, 0x0F]);                      // xor a; ldh [IF], a
+
+	code.push(0x7B);                                      // ld a, e (the track)
+	let init = header.init_address;
+	code.extend_from_slice(&[OP_CALL, init as u8, (init >> 8) as u8]); // call init
+	code.push(0xFB);                                      // ei
+	code.push(0x76);                                      // halt
+	code.extend_from_slice(&[0x18, 0xFD]);                // jr -3 (halt)
+	code
+}
+
+fn write_cartridge_header(rom: &mut [u8], header: &GbsHeader, banks: usize) {
+	rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+	for byte in rom[0x0134..0x0150].iter_mut() {
+		*byte = 0;
+	}
+	let title: Vec<u8> = header.title.bytes().filter(|c| c.is_ascii_graphic() || *c == b' ').take(15).collect();
+	rom[0x0134..0x0134 + title.len()].copy_from_slice(&title);
+	rom[0x0143] = if header.double_speed() { 0x80 } else { 0x00 };
+	rom[0x0147] = CART_TYPE_MBC5_RAM;
+	rom[0x0148] = (banks.trailing_zeros() - 1) as u8; // 32KiB << n
+	rom[0x0149] = RAM_SIZE_8KB;
+	rom[0x014D] = CartInfo::calculate_header_checksum(rom);
+	let global_checksum = CartInfo::calculate_global_checksum(rom, 0x014E);
+	rom[0x014E] = (global_checksum >> 8) as u8;
+	rom[0x014F] = global_checksum as u8;
+}
@@ -0,0 +1,95 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use super::MemoryBankController;
+
+/* Value written to 0x0000 - 0x1FFF to map the infrared port to 0xA000 - 0xBFFF instead of the ram */
+const IR_MODE: u8 = 0x0E;
+
+#[derive(Serialize, Deserialize)]
+pub struct HuC1 {
+	rom_bank: u8,      /* current rom bank (6 bits, bank 0 can be selected) */
+	ram_bank: u8,      /* current ram bank (2 bits) */
+	ir_mode: bool,     /* is the infrared port mapped instead of the ram */
+	ir_led: bool
+}
+
+impl HuC1 {
+	pub fn new() -> HuC1 {
+		HuC1 {
+			rom_bank: 1,
+			ram_bank: 0,
+			ir_mode: false,
+			ir_led: false
+		}
+	}
+}
+
+impl MemoryBankController for HuC1 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	/// In ir mode bit 0 is set when the ir receiver sees light,
+	/// there's nothing on the other end, so it never does.
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		if self.ir_mode {
+			0xC0
+		}
+		else {
+			let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+			if address < ram_size {
+				ram[address]
+			}
+			else {
+				0xFF
+			}
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x1FFF - IR select (0x0E maps the ir port, anything else maps the ram)
+		//0x2000...0x3FFF - ROM Bank number (6 bits)
+		//0x4000...0x5FFF - RAM Bank number (2 bits)
+		//0x6000...0x7FFF - nothing
+		match address {
+			0x0000...0x1FFF => self.ir_mode = value == IR_MODE,
+			0x2000...0x3FFF => self.rom_bank = value & 0x3F,
+			0x4000...0x5FFF => self.ram_bank = value & 0x03,
+			_ => {}
+		}
+		false
+	}
+
+	/// In ir mode bit 0 turns the ir led on/off.
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		if self.ir_mode {
+			self.ir_led = value & 1 == 1;
+			false
+		}
+		else {
+			let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+			address < ram_size && write_ram(ram, address, value)
+		}
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_bank as usize
+	}
+
+	fn ir_led(&self) -> bool {
+		self.ir_led
+	}
+}
@@ -0,0 +1,278 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use gameboy::clock::RtcTimestamp;
+use super::MemoryBankController;
+
+/* Which device is mapped to 0xA000 - 0xBFFF, selected by writing to 0x0000 - 0x1FFF */
+const MODE_RAM_READ: u8 = 0x00;
+const MODE_RAM_READ_WRITE: u8 = 0x0A;
+const MODE_RTC_COMMAND: u8 = 0x0B;
+const MODE_RTC_RESPONSE: u8 = 0x0C;
+const MODE_RTC_SEMAPHORE: u8 = 0x0D;
+const MODE_IR: u8 = 0x0E;
+
+/* Commands written to 0xA000 in MODE_RTC_COMMAND (bits 4-6), the argument is in bits 0-3 */
+const CMD_READ: u8 = 0x1;
+const CMD_WRITE: u8 = 0x3;
+const CMD_ADDRESS_LOW: u8 = 0x4;
+const CMD_ADDRESS_HIGH: u8 = 0x5;
+const CMD_EXTENDED: u8 = 0x6;
+
+/* Arguments of CMD_EXTENDED */
+const EXT_READ_TIME: u8 = 0x0;
+const EXT_WRITE_TIME: u8 = 0x1;
+const EXT_STATUS: u8 = 0x2;
+const EXT_TONE: u8 = 0xE;
+
+/* The time is copied to/from the first 7 nibbles of the rtc memory:
+ * 0x00 - 0x02: minute of the day (12 bits), 0x03 - 0x06: day counter (16 bits) */
+const TIME_MINUTES: usize = 0x00;
+const TIME_DAYS: usize = 0x03;
+
+/* Nibble in rtc memory that selects the tone played by the tone generator */
+const TONE_SELECT: usize = 0x27;
+
+const RTC_MEMORY_SIZE: usize = 0x100;
+
+/* Battery save footer: the rtc memory (two nibbles per byte, low nibble first), the seconds counted since day 0,
+ * and the unix timestamp of the save (both 64 bits, little endian) */
+const FOOTER_SIZE: usize = RTC_MEMORY_SIZE / 2 + 16;
+
+/// The HuC3's rtc is a separate microcontroller the cpu talks to through a command/response interface.
+/// It has 256 nibbles of memory, the current time can be copied into it (and set from it) with extended commands.
+#[derive(Serialize, Deserialize)]
+pub struct HuC3Rtc {
+	last: RtcTimestamp, /* the last time the clock was updated */
+	seconds: u64,       /* seconds counted since day 0 as of the last update */
+	memory: Box<[u8]>,  /* 256 nibbles */
+	address: u8,        /* the nibble accessed by CMD_READ/CMD_WRITE */
+	command: u8,        /* the last command that was executed */
+	response: u8,       /* the result of the last command (4 bits) */
+	tone: Option<u8>    /* the tone selected the last time the tone generator was started */
+}
+
+impl HuC3Rtc {
+	pub fn new() -> HuC3Rtc {
+		HuC3Rtc {
+			last: RtcTimestamp::new(),
+			seconds: 0,
+			memory: vec![0; RTC_MEMORY_SIZE].into_boxed_slice(),
+			address: 0,
+			command: 0,
+			response: 0,
+			tone: None
+		}
+	}
+
+	/// Count the seconds that have passed since the last update (now is a unix timestamp from the cartridge's clock).
+	fn update(&mut self, now: i64) {
+		self.seconds += self.last.update(now);
+	}
+
+	/// Execute a command written to 0xA000 in MODE_RTC_COMMAND.
+	/// Returns true if the command changed the memory or the time (which are kept in battery saves).
+	fn command(&mut self, value: u8) -> bool {
+		let command = (value >> 4) & 0x07;
+		let argument = value & 0x0F;
+		self.command = command;
+		match command {
+			CMD_READ => {
+				self.response = self.memory[self.address as usize];
+				self.address = self.address.wrapping_add(1);
+				false
+			},
+			CMD_WRITE => {
+				let changed = self.memory[self.address as usize] != argument;
+				self.memory[self.address as usize] = argument;
+				self.address = self.address.wrapping_add(1);
+				changed
+			},
+			CMD_ADDRESS_LOW => {
+				self.address = (self.address & 0xF0) | argument;
+				false
+			},
+			CMD_ADDRESS_HIGH => {
+				self.address = (self.address & 0x0F) | (argument << 4);
+				false
+			},
+			CMD_EXTENDED => self.extended_command(argument),
+			_ => false
+		}
+	}
+
+	fn extended_command(&mut self, argument: u8) -> bool {
+		match argument {
+			EXT_READ_TIME => {
+				let minutes = (self.seconds / 60) % 1440;
+				let days = (self.seconds / 86400) & 0xFFFF;
+				for i in 0..3 {
+					self.memory[TIME_MINUTES + i] = ((minutes >> (i * 4)) & 0xF) as u8;
+				}
+				for i in 0..4 {
+					self.memory[TIME_DAYS + i] = ((days >> (i * 4)) & 0xF) as u8;
+				}
+				true
+			},
+			EXT_WRITE_TIME => {
+				let mut minutes: u64 = 0;
+				let mut days: u64 = 0;
+				for i in 0..3 {
+					minutes |= (self.memory[TIME_MINUTES + i] as u64) << (i * 4);
+				}
+				for i in 0..4 {
+					days |= (self.memory[TIME_DAYS + i] as u64) << (i * 4);
+				}
+				self.seconds = days * 86400 + (minutes % 1440) * 60;
+				true
+			},
+			EXT_STATUS => {
+				self.response = 1; // always ready
+				false
+			},
+			EXT_TONE => {
+				self.tone = Some(self.memory[TONE_SELECT]); // there's no speaker emulation, so it's only recorded
+				false
+			},
+			_ => false
+		}
+	}
+
+	/// The value read from 0xA000 in MODE_RTC_RESPONSE:
+	/// bits 0-3 are the result of the last command, bits 4-6 are the last command.
+	fn read_response(&self) -> u8 {
+		(self.command << 4) | self.response
+	}
+
+	fn save(&self) -> Vec<u8> {
+		let mut footer: Vec<u8> = self.memory.chunks(2).map(|pair| pair[0] | (pair[1] << 4)).collect();
+		for i in 0..8 {
+			footer.push((self.seconds >> (i * 8)) as u8);
+		}
+		footer.extend_from_slice(&self.last.save());
+		footer
+	}
+
+	/// Restore the clock from a battery save footer, the time that has passed since the save is applied on the next update.
+	fn load(&mut self, footer: &[u8]) {
+		if footer.len() < FOOTER_SIZE {
+			return;
+		}
+		for (i, byte) in footer[..RTC_MEMORY_SIZE / 2].iter().enumerate() {
+			self.memory[i * 2] = byte & 0x0F;
+			self.memory[i * 2 + 1] = byte >> 4;
+		}
+		let mut seconds: u64 = 0;
+		for i in 0..8 {
+			seconds |= (footer[RTC_MEMORY_SIZE / 2 + i] as u64) << (i * 8);
+		}
+		self.seconds = seconds;
+		self.last.load(&footer[RTC_MEMORY_SIZE / 2 + 8..FOOTER_SIZE]);
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HuC3 {
+	rom_bank: u8,      /* current rom bank (7 bits, bank 0 can be selected) */
+	ram_bank: u8,      /* current ram bank (4 bits) */
+	mode: u8,          /* the device mapped to 0xA000 - 0xBFFF */
+	rtc: HuC3Rtc,
+	ir_led: bool
+}
+
+impl HuC3 {
+	pub fn new() -> HuC3 {
+		HuC3 {
+			rom_bank: 1,
+			ram_bank: 0,
+			mode: MODE_RAM_READ,
+			rtc: HuC3Rtc::new(),
+			ir_led: false
+		}
+	}
+}
+
+impl MemoryBankController for HuC3 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		match self.mode {
+			MODE_RAM_READ | MODE_RAM_READ_WRITE => {
+				let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+				if address < ram_size {
+					ram[address]
+				}
+				else {
+					0xFF
+				}
+			},
+			MODE_RTC_RESPONSE => self.rtc.read_response(),
+			MODE_RTC_SEMAPHORE => 0x01, // commands are executed immediately, so the rtc is always ready
+			MODE_IR => 0xC0, // bit 0 would be set if the ir receiver saw light, there's nothing on the other end
+			_ => 0xFF
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x1FFF - Mode select (ram, rtc command/response/semaphore, ir)
+		//0x2000...0x3FFF - ROM Bank number (7 bits)
+		//0x4000...0x5FFF - RAM Bank number (4 bits)
+		//0x6000...0x7FFF - nothing
+		match address {
+			0x0000...0x1FFF => self.mode = value & 0x0F,
+			0x2000...0x3FFF => self.rom_bank = value & 0x7F,
+			0x4000...0x5FFF => self.ram_bank = value & 0x0F,
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		match self.mode {
+			MODE_RAM_READ_WRITE => {
+				let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+				address < ram_size && write_ram(ram, address, value)
+			},
+			MODE_RTC_COMMAND => self.rtc.command(value),
+			MODE_IR => {
+				self.ir_led = value & 1 == 1;
+				false
+			},
+			_ => false
+		}
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_bank as usize
+	}
+
+	fn set_time(&mut self, now: i64) {
+		self.rtc.update(now);
+	}
+
+	fn battery_footer(&self) -> Vec<u8> {
+		self.rtc.save()
+	}
+
+	fn load_battery_footer(&mut self, footer: &[u8]) {
+		self.rtc.load(footer);
+	}
+
+	fn ir_led(&self) -> bool {
+		self.ir_led
+	}
+}
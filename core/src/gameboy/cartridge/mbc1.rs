@@ -1,3 +1,4 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
 use super::MemoryBankController;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -8,37 +9,68 @@ enum ModeSelect {
 
 #[derive(Serialize, Deserialize)]
 pub struct MBC1 {
-	ram_bank: u8,
-	rom_bank: u8,
+	ram_bank: u8,      /* 2 bits, used as the upper bits of the rom bank number, or as the ram bank number in ram mode */
+	rom_bank: u8,      /* 5 bits, writing 0 selects bank 1 */
 	mode: ModeSelect,
 	ram_enable: bool,
+	/// MBC1M multicarts (1MiB roms with 4 games on them) don't wire the highest bit of the rom bank register,
+	/// so the upper bits (from the ram bank register) are shifted by 4 instead of 5.
+	multicart: bool,
 }
 
 impl MBC1 {
-	pub fn new() -> MBC1 {
+	pub fn new(multicart: bool) -> MBC1 {
 		MBC1 {
 			ram_bank: 0,
 			rom_bank: 1,
 			mode: ModeSelect::Rom,
 			ram_enable: false,
+			multicart: multicart,
 		}
 	}
+
+	pub fn set_multicart(&mut self, multicart: bool) {
+		self.multicart = multicart;
+	}
+
+	fn upper_bank_shift(&self) -> u8 {
+		if self.multicart { 4 } else { 5 }
+	}
+
+	/// The bank mapped to 0x0000 - 0x3FFF: in ram mode the upper bits of the rom bank number apply here too,
+	/// which is how the games after the first one on a multicart map their bank 0.
+	fn rom_bank_low(&self) -> usize {
+		match self.mode {
+			ModeSelect::Rom => 0,
+			ModeSelect::Ram => (self.ram_bank << self.upper_bank_shift()) as usize
+		}
+	}
+
+	/// The bank mapped to 0x4000 - 0x7FFF.
+	fn rom_bank_high(&self) -> usize {
+		let lower_bits = if self.multicart { self.rom_bank & 0x0F } else { self.rom_bank };
+		(lower_bits | (self.ram_bank << self.upper_bank_shift())) as usize
+	}
+
+	fn ram_address(&self, offset: u16) -> usize {
+		let ram_bank = match self.mode {
+			ModeSelect::Rom => 0,
+			ModeSelect::Ram => self.ram_bank
+		};
+		offset as usize + (RAM_BANK_SIZE * ram_bank as usize)
+	}
 }
 
 impl MemoryBankController for MBC1 {
+	/// The rom size is a power of 2, so the bank numbers wrap around if the rom doesn't use all of the bank bits.
 	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
 		let address: usize = match offset {
-			0x0000...0x3FFF => offset as usize,
-			0x4000...0x7FFF => {
-				let mut rom_bank: u8 = self.rom_bank;
-				if self.mode == ModeSelect::Rom {
-					rom_bank |= self.ram_bank << 5;
-				}
-				(offset - 0x4000) as usize + (0x4000 * rom_bank as usize)
-			},
+			0x0000...0x3FFF => offset as usize + (ROM_BANK_SIZE * self.rom_bank_low()),
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank_high()),
 			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
 		};
-		if address < rom_size {
+		let address = if rom_size > 0 { address % rom_size } else { address };
+		if address < rom.len() {
 			return rom[address];
 		}
 		else {
@@ -47,11 +79,7 @@ impl MemoryBankController for MBC1 {
 	}
 
 	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
-		let mut ram_bank: u8 = 0;
-		if self.mode == ModeSelect::Ram {
-			ram_bank |= self.ram_bank;
-		}
-		let address: usize = offset as usize + (0x2000 * ram_bank as usize);
+		let address: usize = self.ram_address(offset);
 		if address < ram_size {
 			return ram[address];
 		}
@@ -60,48 +88,39 @@ impl MemoryBankController for MBC1 {
 		}
 	}
 
-	#[allow(unused_variables)]
-	fn write_byte_rom(&mut self, address: u16, value: u8) {
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
 		//0x0000...0x1FFF - RAM enable
-		//0x2000...0x3FFF - ROM Bank number (5-bits)
+		//0x2000...0x3FFF - ROM Bank number (5-bits, 0 is treated as 1, only the lower 4 bits are wired on multicarts)
 		//0x4000...0x5FFF - RAM Bank number (2-bits)
 		//0x6000...0x7FFF - ROM/RAM Mode Select (0=Rom, 1=Ram)
 		match address {
 			0x0000...0x1FFF => self.ram_enable = (value & 0x0A) == 0x0A,
-			0x2000...0x3FFF => self.rom_bank = value & 0x1F,
+			0x2000...0x3FFF => {
+				self.rom_bank = match value & 0x1F {
+					0 => 1,
+					bank => bank
+				};
+			},
 			0x4000...0x5FFF => self.ram_bank = value & 3,
 			0x6000...0x7FFF => {
 				if value & 1 == 0 { self.mode = ModeSelect::Rom; }
 				else { self.mode = ModeSelect::Ram; }
 			},
-			_ => return,
+			_ => {}
 		};
+		false
 	}
 
-	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) {
-		let mut ram_bank: u8 = 0;
-		if self.mode == ModeSelect::Ram {
-			ram_bank |= self.ram_bank;
-		}
-		let address: usize = offset as usize + (0x2000 * ram_bank as usize);
-		if address < ram_size {
-			ram[address] = value;
-		}
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		let address: usize = self.ram_address(offset);
+		address < ram_size && write_ram(ram, address, value)
 	}
 
 	fn rom_bank(&self) -> usize {
-		let mut rom_bank: u8 = self.rom_bank;
-		if self.mode == ModeSelect::Rom {
-			rom_bank |= self.ram_bank << 5;
-		}
-		rom_bank as usize
+		self.rom_bank_high()
 	}
 
 	fn ram_bank(&self) -> usize {
-		let mut ram_bank: u8 = 0;
-		if self.mode == ModeSelect::Ram {
-			ram_bank |= self.ram_bank;
-		}
-		ram_bank as usize
+		self.ram_address(0) / RAM_BANK_SIZE
 	}
 }
@@ -0,0 +1,82 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, write_ram};
+use super::MemoryBankController;
+
+/// The mbc2 has 512 x 4 bits of ram built in, there is no external ram.
+pub const MBC2_RAM_SIZE: usize = 512;
+
+#[derive(Serialize, Deserialize)]
+pub struct MBC2 {
+	rom_bank: u8,      /* current rom bank (4 bits, can't be 0) */
+	ram_enable: bool
+}
+
+impl MBC2 {
+	pub fn new() -> MBC2 {
+		MBC2 {
+			rom_bank: 1,
+			ram_enable: false
+		}
+	}
+}
+
+impl MemoryBankController for MBC2 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	/// Only the lower 9 bits of the address are used, so the 512 bytes of ram are echoed across 0xA000 - 0xBFFF.
+	/// Each byte only stores the lower nibble, the upper nibble always reads as 1s.
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		let address: usize = (offset as usize) & (MBC2_RAM_SIZE - 1);
+		if self.ram_enable && address < ram_size {
+			ram[address] | 0xF0
+		}
+		else {
+			0xFF
+		}
+	}
+
+	/// There is only one register range (0x0000 - 0x3FFF), bit 8 of the address selects the register:
+	/// bit 8 clear - RAMG: ram enable (0x0A in the lower nibble enables the ram)
+	/// bit 8 set   - ROMB: rom bank number (4 bits, 0 selects bank 1)
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		match address {
+			0x0000...0x3FFF => {
+				if address & 0x100 == 0 {
+					self.ram_enable = value & 0x0F == 0x0A;
+				}
+				else {
+					self.rom_bank = match value & 0x0F {
+						0 => 1,
+						bank => bank
+					};
+				}
+			},
+			_ => {/* writes to 0x4000 - 0x7FFF are ignored */}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		let address: usize = (offset as usize) & (MBC2_RAM_SIZE - 1);
+		self.ram_enable && address < ram_size && write_ram(ram, address, value & 0x0F)
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		0 //there's only one bank of built in ram
+	}
+}
@@ -1,7 +1,7 @@
 use std::cell::Cell;
-use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE};
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use gameboy::clock::RtcTimestamp;
 use super::MemoryBankController;
-use time;
 
 /* What happens if you play at a different speed, the rtc should reflect that... */
 /* Can you write to the rtc registers? If you write while there is an rtc value latched does it change the latched value or the real value? */
@@ -46,10 +46,14 @@ impl Duration {
 	}
 }
 
+/* Size of the rtc footer in battery saves, with a 64-bit or a 32-bit timestamp */
+const RTC_FOOTER_SIZE: usize = 48;
+const RTC_FOOTER_SIZE_32: usize = 44;
+
 #[derive(Clone, Copy, Debug)]
 #[derive(Serialize, Deserialize)]
 pub struct RTC {
-	last: i64, //the last time the timer was updated (unix timestamp)
+	last: RtcTimestamp, //the last time the timer was updated
 	duration: Duration,   //the value of the rtc as of the last time update was called
 	latched: Option<Duration>, //the latched duration (if any)
 	halt: bool,
@@ -59,7 +63,7 @@ pub struct RTC {
 impl RTC {
 	pub fn new() -> RTC {
 		RTC {
-			last: time::now_utc().to_timespec().sec,
+			last: RtcTimestamp::new(),
 			duration: Duration::new(),
 			latched: None,
 			halt: false, //if the rtc is halted, the duration isn't updated in the update method
@@ -67,11 +71,10 @@ impl RTC {
 		}
 	}
 
-	pub fn update(&mut self) {
-		let time = time::now_utc().to_timespec().sec; //current unix timestamp
-		let delta = self.last - time;
-		if delta > 0 { //if now is before (or the same) the last time it was updated then something has gone wrong
-			self.last = time;
+	///Advance the rtc to the current time (unix timestamp, from the cartridge's clock)
+	pub fn update(&mut self, now: i64) {
+		let delta = self.last.update(now);
+		if delta > 0 {
 			if !self.halt {
 				let new_duration = self.duration.add_seconds(delta as usize);
 				if self.duration.days > new_duration.days {
@@ -85,7 +88,6 @@ impl RTC {
 
 	///Latch the current duration
 	pub fn latch(&mut self) {
-		self.update();
 		self.latched = Some(self.duration);
 	}
 
@@ -168,10 +170,52 @@ impl RTC {
 
 	///Write to the RTC_DH register
 	pub fn set_days_high(&mut self, value: u8) {
-		self.duration.days = (self.duration.days & 255) | (((value as usize) >> 8) & 256);
+		self.duration.days = (self.duration.days & 255) | (((value as usize) & 1) << 8);
 		self.halt = value & 64 != 0;
 		self.day_carry = value & 128 != 0;
 	}
+
+	///The 48 byte footer vba/bgb append to battery saves: the live and latched S/M/H/DL/DH registers as 32-bit little endian values,
+	///followed by the unix timestamp of the save (64-bit little endian).
+	pub fn save(&self) -> Vec<u8> {
+		let latched = self.latched.unwrap_or(self.duration);
+		let mut footer: Vec<u8> = Vec::with_capacity(RTC_FOOTER_SIZE);
+		let registers = [
+			self.duration.seconds as u8, self.duration.minutes as u8, self.duration.hours as u8, self.duration.days as u8, self.days_high_of(self.duration),
+			latched.seconds as u8, latched.minutes as u8, latched.hours as u8, latched.days as u8, self.days_high_of(latched)
+		];
+		for register in registers.iter() {
+			footer.extend_from_slice(&[*register, 0, 0, 0]);
+		}
+		footer.extend_from_slice(&self.last.save());
+		footer
+	}
+
+	///Restore the rtc from a vba/bgb footer (the 44 byte variant with a 32-bit timestamp is accepted too),
+	///the time that has passed since the save was made is applied the next time the clock sets the time.
+	pub fn load(&mut self, footer: &[u8]) {
+		if footer.len() < RTC_FOOTER_SIZE_32 {
+			return;
+		}
+		let register = |index: usize| footer[index * 4];
+		let duration = |first: usize| Duration {
+			seconds: register(first) as usize % 60,
+			minutes: register(first + 1) as usize % 60,
+			hours: register(first + 2) as usize % 24,
+			days: register(first + 3) as usize | ((register(first + 4) as usize & 1) << 8)
+		};
+		self.duration = duration(0);
+		self.latched = Some(duration(5));
+		self.halt = register(4) & 64 != 0;
+		self.day_carry = register(4) & 128 != 0;
+
+		let timestamp_size = if footer.len() >= RTC_FOOTER_SIZE { 8 } else { 4 };
+		self.last.load(&footer[40..40 + timestamp_size]);
+	}
+
+	fn days_high_of(&self, duration: Duration) -> u8 {
+		((duration.days >> 8) as u8 & 1) | ((self.halt as u8) << 6) | ((self.day_carry as u8) << 7)
+	}
 }
 
 /* Ram bank numbers used to access the different rtc registers */
@@ -245,9 +289,7 @@ impl MemoryBankController for MBC3 {
 				},
 				0x8...0xC => {
 					if let Some(rtc_cell) = self.rtc.as_ref() {
-						let mut rtc = rtc_cell.get();
-						rtc.update();
-						rtc_cell.set(rtc);
+						let rtc = rtc_cell.get();
 						match self.ram_bank {
 							RTC_S => rtc.seconds(),
 							RTC_M => rtc.minutes(),
@@ -269,7 +311,7 @@ impl MemoryBankController for MBC3 {
 		}
 	}
 
-	fn write_byte_rom(&mut self, address: u16, value: u8) {
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
 		/* Writing to the rom sets internal mbc registers */
 		match address {
 			0...0x1FFF => self.ram_timer_enable = value == 0x0A,
@@ -296,21 +338,24 @@ impl MemoryBankController for MBC3 {
 			},
 			_ => {/* Cartridge memory is only from 0h...7FFFh, this isn't a valid address */}
 		}
+		false // latching only copies the clock, it doesn't change it
 	}
 
-	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) {
-		if self.ram_timer_enable {
-			match self.ram_bank {
-				0...3 => {
-					let bank_offset = RAM_BANK_SIZE * self.ram_bank as usize;
-					if (bank_offset + offset as usize) < ram_size {
-						ram[bank_offset + offset as usize] = value;
-					}
-				},
-				8...0xC => {
-					/* Write to rtc registers */
-					if let Some(rtc_cell) = self.rtc.as_mut() {
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		if !self.ram_timer_enable {
+			return false;
+		}
+		match self.ram_bank {
+			0...3 => {
+				let address = RAM_BANK_SIZE * self.ram_bank as usize + offset as usize;
+				address < ram_size && write_ram(ram, address, value)
+			},
+			8...0xC => {
+				/* Write to rtc registers */
+				match self.rtc.as_mut() {
+					Some(rtc_cell) => {
 						let mut rtc = rtc_cell.get();
+						let before = rtc.save();
 						match self.ram_bank {
 							RTC_S => rtc.set_seconds(value),
 							RTC_M => rtc.set_minutes(value),
@@ -320,10 +365,12 @@ impl MemoryBankController for MBC3 {
 							_ => {}
 						};
 						rtc_cell.set(rtc);
-					}
+						rtc.save() != before
+					},
+					None => false
 				}
-				_ => {}
 			}
+			_ => false
 		}
 	}
 
@@ -334,4 +381,23 @@ impl MemoryBankController for MBC3 {
 	fn ram_bank(&self) -> usize {
 		self.ram_bank as usize
 	}
+
+	fn battery_footer(&self) -> Vec<u8> {
+		match self.rtc.as_ref() {
+			Some(rtc_cell) => rtc_cell.get().save(),
+			None => Vec::new()
+		}
+	}
+
+	fn load_battery_footer(&mut self, footer: &[u8]) {
+		if let Some(rtc_cell) = self.rtc.as_mut() {
+			rtc_cell.get_mut().load(footer);
+		}
+	}
+
+	fn set_time(&mut self, now: i64) {
+		if let Some(rtc_cell) = self.rtc.as_mut() {
+			rtc_cell.get_mut().update(now);
+		}
+	}
 }
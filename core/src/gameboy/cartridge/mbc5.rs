@@ -0,0 +1,89 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use super::MemoryBankController;
+
+#[derive(Serialize, Deserialize)]
+pub struct MBC5 {
+	rom_bank: u16,     /* current rom bank (9 bits, bank 0 can be selected) */
+	ram_bank: u8,      /* current ram bank (4 bits, 3 bits on carts with a rumble motor) */
+	ram_enable: bool,
+	has_rumble: bool,  /* on rumble carts bit 3 of the ram bank register controls the motor */
+	rumble: bool
+}
+
+impl MBC5 {
+	pub fn new(has_rumble: bool) -> MBC5 {
+		MBC5 {
+			rom_bank: 1,
+			ram_bank: 0,
+			ram_enable: false,
+			has_rumble: has_rumble,
+			rumble: false
+		}
+	}
+}
+
+impl MemoryBankController for MBC5 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+		if self.ram_enable && address < ram_size {
+			ram[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x1FFF - RAM enable (only 0x0A enables the ram)
+		//0x2000...0x2FFF - ROM Bank number (low 8 bits)
+		//0x3000...0x3FFF - ROM Bank number (bit 8)
+		//0x4000...0x5FFF - RAM Bank number (4 bits), or RAM Bank number (3 bits) + rumble motor (bit 3)
+		match address {
+			0x0000...0x1FFF => self.ram_enable = value == 0x0A,
+			0x2000...0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+			0x3000...0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 1) << 8),
+			0x4000...0x5FFF => {
+				if self.has_rumble {
+					self.ram_bank = value & 0x07;
+					self.rumble = value & 0x08 != 0;
+				}
+				else {
+					self.ram_bank = value & 0x0F;
+				}
+			},
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		let address: usize = offset as usize + (RAM_BANK_SIZE * self.ram_bank as usize);
+		self.ram_enable && address < ram_size && write_ram(ram, address, value)
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_bank as usize
+	}
+
+	fn rumble(&self) -> bool {
+		self.rumble
+	}
+}
@@ -0,0 +1,310 @@
+use gameboy::cartridge::write_ram;
+use super::MemoryBankController;
+
+/// The MBC6 maps rom/flash and ram in two independently banked windows each.
+const ROM_WINDOW_SIZE: usize = 0x2000;
+const RAM_WINDOW_SIZE: usize = 0x1000;
+
+/// The Macronix MX29F008 flash chip holds 1MiB, divided into 8 sectors.
+pub const MBC6_FLASH_SIZE: usize = 0x100000;
+const FLASH_SECTOR_SIZE: usize = 0x20000;
+const FLASH_SECTORS: usize = MBC6_FLASH_SIZE / FLASH_SECTOR_SIZE;
+
+/// The battery footer is the flash, followed by a byte with a bit for each protected sector.
+const FLASH_FOOTER_SIZE: usize = MBC6_FLASH_SIZE + 1;
+
+/* Values returned in autoselect (id) mode */
+const FLASH_MANUFACTURER_ID: u8 = 0xC2;
+const FLASH_DEVICE_ID: u8 = 0x81;
+const FLASH_SECTOR_PROTECTED: u8 = 0x01;
+
+/* Addresses (in the flash) the command sequences are written to */
+const FLASH_UNLOCK_1: usize = 0x5555;
+const FLASH_UNLOCK_2: usize = 0x2AAA;
+
+/* Value written to the rom/flash select registers to map the flash */
+const SELECT_FLASH: u8 = 0x08;
+
+/// Where the flash is in a command sequence. Every command starts with the unlock sequence:
+/// 0xAA written to 0x5555, then 0x55 written to 0x2AAA. After that 0x5555 gets the command:
+/// 0xA0: program the byte written next (bits can only be cleared)
+/// 0x80: erase, after another unlock sequence 0x10 written to 0x5555 erases the chip, or 0x30 written to a sector erases it
+/// 0x90: autoselect, reads return the manufacturer id (A0 = 0), device id (A0 = 1) or whether the sector is protected
+///       (A1 = 1, A0 = 0) until 0xF0 is written
+/// 0x60: sector protection, after another unlock sequence 0x40 written to a sector protects it, or 0x20 unprotects it
+/// Protected sectors can't be programmed or erased. Writing 0xF0 anywhere resets the flash to read mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+enum FlashCommand {
+	Idle,
+	Unlock1,
+	Unlock2,
+	Program,
+	Erase,
+	EraseUnlock1,
+	EraseUnlock2,
+	Protect,
+	ProtectUnlock1,
+	ProtectUnlock2
+}
+
+#[derive(Serialize, Deserialize)]
+struct Flash {
+	data: Box<[u8]>,
+	command: FlashCommand,
+	id_mode: bool,
+	protected: u8 /* a bit for each sector */
+}
+
+impl Flash {
+	fn new() -> Flash {
+		Flash {
+			data: vec![0xFF; MBC6_FLASH_SIZE].into_boxed_slice(),
+			command: FlashCommand::Idle,
+			id_mode: false,
+			protected: 0
+		}
+	}
+
+	fn sector(address: usize) -> usize {
+		address / FLASH_SECTOR_SIZE
+	}
+
+	fn is_protected(&self, address: usize) -> bool {
+		self.protected & (1 << Flash::sector(address)) != 0
+	}
+
+	/// Erase the sector containing address, returns false if it's protected.
+	fn erase_sector(&mut self, address: usize) -> bool {
+		if self.is_protected(address) {
+			return false;
+		}
+		let start = Flash::sector(address) * FLASH_SECTOR_SIZE;
+		for byte in self.data[start..start + FLASH_SECTOR_SIZE].iter_mut() {
+			*byte = 0xFF;
+		}
+		true
+	}
+
+	fn read(&self, address: usize) -> u8 {
+		let address = address % MBC6_FLASH_SIZE;
+		if self.id_mode {
+			match address & 3 {
+				0 => FLASH_MANUFACTURER_ID,
+				2 => if self.is_protected(address) { FLASH_SECTOR_PROTECTED } else { 0x00 },
+				_ => FLASH_DEVICE_ID
+			}
+		}
+		else {
+			self.data[address]
+		}
+	}
+
+	/// Programming, erasing and changing the sector protection only happen if writable (the flash write enable register
+	/// is set), they complete immediately. Returns true if the flash (or its protection) was changed.
+	fn write(&mut self, address: usize, value: u8, writable: bool) -> bool {
+		let address = address % MBC6_FLASH_SIZE;
+		if value == 0xF0 && self.command != FlashCommand::Program {
+			self.command = FlashCommand::Idle;
+			self.id_mode = false;
+			return false;
+		}
+		let mut written = false;
+		let command_address = address & 0x7FFF;
+		self.command = match self.command {
+			FlashCommand::Idle if command_address == FLASH_UNLOCK_1 && value == 0xAA => FlashCommand::Unlock1,
+			FlashCommand::Unlock1 if command_address == FLASH_UNLOCK_2 && value == 0x55 => FlashCommand::Unlock2,
+			FlashCommand::Unlock2 if command_address == FLASH_UNLOCK_1 => match value {
+				0xA0 => FlashCommand::Program,
+				0x80 => FlashCommand::Erase,
+				0x60 => FlashCommand::Protect,
+				0x90 => {
+					self.id_mode = true;
+					FlashCommand::Idle
+				},
+				_ => FlashCommand::Idle
+			},
+			FlashCommand::Program => {
+				if writable && !self.is_protected(address) {
+					self.data[address] &= value;
+					written = true;
+				}
+				FlashCommand::Idle
+			},
+			FlashCommand::Erase if command_address == FLASH_UNLOCK_1 && value == 0xAA => FlashCommand::EraseUnlock1,
+			FlashCommand::EraseUnlock1 if command_address == FLASH_UNLOCK_2 && value == 0x55 => FlashCommand::EraseUnlock2,
+			FlashCommand::EraseUnlock2 => {
+				if writable {
+					if value == 0x10 && command_address == FLASH_UNLOCK_1 {
+						// only the unprotected sectors are erased
+						for sector in 0..FLASH_SECTORS {
+							written |= self.erase_sector(sector * FLASH_SECTOR_SIZE);
+						}
+					}
+					else if value == 0x30 {
+						written = self.erase_sector(address);
+					}
+				}
+				FlashCommand::Idle
+			},
+			FlashCommand::Protect if command_address == FLASH_UNLOCK_1 && value == 0xAA => FlashCommand::ProtectUnlock1,
+			FlashCommand::ProtectUnlock1 if command_address == FLASH_UNLOCK_2 && value == 0x55 => FlashCommand::ProtectUnlock2,
+			FlashCommand::ProtectUnlock2 => {
+				if writable {
+					let protected = match value {
+						0x40 => self.protected | (1 << Flash::sector(address)),
+						0x20 => self.protected & !(1 << Flash::sector(address)),
+						_ => self.protected
+					};
+					written = protected != self.protected;
+					self.protected = protected;
+				}
+				FlashCommand::Idle
+			},
+			_ => FlashCommand::Idle
+		};
+		written
+	}
+}
+
+/// The MBC6 splits 0x4000 - 0x7FFF into two 8KiB windows (A: 0x4000 - 0x5FFF, B: 0x6000 - 0x7FFF) that can each map
+/// a bank of the rom or of the flash, and 0xA000 - 0xBFFF into two 4KiB ram windows (A: 0xA000 - 0xAFFF, B: 0xB000 - 0xBFFF).
+/// The flash is saved after the ram in battery saves.
+#[derive(Serialize, Deserialize)]
+pub struct MBC6 {
+	rom_bank_a: u8,    /* 8KiB rom/flash bank mapped to 0x4000 - 0x5FFF (7 bits) */
+	rom_bank_b: u8,    /* 8KiB rom/flash bank mapped to 0x6000 - 0x7FFF (7 bits) */
+	flash_a: bool,     /* is the flash mapped to window A instead of the rom */
+	flash_b: bool,
+	ram_bank_a: u8,    /* 4KiB ram bank mapped to 0xA000 - 0xAFFF (3 bits) */
+	ram_bank_b: u8,    /* 4KiB ram bank mapped to 0xB000 - 0xBFFF (3 bits) */
+	ram_enable: bool,
+	flash_enable: bool,
+	flash_write_enable: bool,
+	flash: Flash
+}
+
+impl MBC6 {
+	pub fn new() -> MBC6 {
+		MBC6 {
+			rom_bank_a: 2,
+			rom_bank_b: 3,
+			flash_a: false,
+			flash_b: false,
+			ram_bank_a: 0,
+			ram_bank_b: 1,
+			ram_enable: false,
+			flash_enable: false,
+			flash_write_enable: false,
+			flash: Flash::new()
+		}
+	}
+
+	/// The bank number and whether it's in the flash for the window containing offset (0x4000 - 0x7FFF).
+	fn rom_window(&self, offset: u16) -> (usize, bool) {
+		if offset < 0x6000 {
+			(self.rom_bank_a as usize, self.flash_a)
+		}
+		else {
+			(self.rom_bank_b as usize, self.flash_b)
+		}
+	}
+
+	fn ram_address(&self, offset: u16) -> usize {
+		let bank = if offset < 0x1000 { self.ram_bank_a } else { self.ram_bank_b };
+		(bank as usize * RAM_WINDOW_SIZE) + (offset as usize & 0x0FFF)
+	}
+}
+
+impl MemoryBankController for MBC6 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => {
+				let (bank, flash) = self.rom_window(offset);
+				let address = (bank * ROM_WINDOW_SIZE) + (offset as usize & 0x1FFF);
+				if flash {
+					return self.flash.read(address);
+				}
+				address
+			},
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		let address = self.ram_address(offset);
+		if self.ram_enable && address < ram_size {
+			ram[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x03FF - RAM enable (0x0A)
+		//0x0400...0x07FF - RAM bank A (3 bits)
+		//0x0800...0x0BFF - RAM bank B (3 bits)
+		//0x0C00...0x0FFF - Flash enable (bit 0)
+		//0x1000...0x1FFF - Flash write enable (bit 0)
+		//0x2000...0x27FF - ROM/Flash bank A (7 bits)
+		//0x2800...0x2FFF - ROM/Flash select A (0x08 selects the flash)
+		//0x3000...0x37FF - ROM/Flash bank B (7 bits)
+		//0x3800...0x3FFF - ROM/Flash select B
+		//0x4000...0x7FFF - flash commands (if the flash is mapped)
+		match address {
+			0x0000...0x03FF => self.ram_enable = value & 0x0F == 0x0A,
+			0x0400...0x07FF => self.ram_bank_a = value & 0x07,
+			0x0800...0x0BFF => self.ram_bank_b = value & 0x07,
+			0x0C00...0x0FFF => self.flash_enable = value & 1 == 1,
+			0x1000...0x1FFF => self.flash_write_enable = value & 1 == 1,
+			0x2000...0x27FF => self.rom_bank_a = value & 0x7F,
+			0x2800...0x2FFF => self.flash_a = value == SELECT_FLASH,
+			0x3000...0x37FF => self.rom_bank_b = value & 0x7F,
+			0x3800...0x3FFF => self.flash_b = value == SELECT_FLASH,
+			0x4000...0x7FFF => {
+				let (bank, flash) = self.rom_window(address);
+				if flash && self.flash_enable {
+					let flash_address = (bank * ROM_WINDOW_SIZE) + (address as usize & 0x1FFF);
+					return self.flash.write(flash_address, value, self.flash_write_enable);
+				}
+			},
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		let address = self.ram_address(offset);
+		self.ram_enable && address < ram_size && write_ram(ram, address, value)
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank_a as usize / 2 // in 16KiB banks
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_bank_a as usize / 2 // in 8KiB banks
+	}
+
+	fn battery_footer(&self) -> Vec<u8> {
+		let mut footer = Vec::with_capacity(FLASH_FOOTER_SIZE);
+		footer.extend_from_slice(&self.flash.data);
+		footer.push(self.flash.protected);
+		footer
+	}
+
+	fn load_battery_footer(&mut self, footer: &[u8]) {
+		let size = footer.len().min(MBC6_FLASH_SIZE);
+		self.flash.data[..size].copy_from_slice(&footer[..size]);
+		// saves from before the protection was saved have no sectors protected
+		self.flash.protected = if footer.len() >= FLASH_FOOTER_SIZE { footer[MBC6_FLASH_SIZE] } else { 0 };
+	}
+}
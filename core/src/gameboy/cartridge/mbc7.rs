@@ -0,0 +1,321 @@
+use gameboy::cartridge::ROM_BANK_SIZE;
+use super::MemoryBankController;
+
+/// The 93LC56 eeprom stores 128 16-bit words, it's the only save memory on MBC7 carts.
+pub const MBC7_EEPROM_SIZE: usize = 256;
+
+/* Accelerometer values: 0x81D0 when the cart is level, and roughly 0x70 per g of acceleration */
+const ACCELEROMETER_CENTER: f32 = 0x81D0 as f32;
+const ACCELEROMETER_G: f32 = 0x70 as f32;
+const ACCELEROMETER_ERASED: u16 = 0x8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+enum EepromState {
+	/// Waiting for a start bit (DI high on a rising clock edge).
+	Idle,
+	/// Shifting in the 2 bit opcode and the 8 bit address.
+	Command { value: u16, bits: u8 },
+	/// Shifting out a word, reading continues with the next word as long as chip select stays high.
+	Read { address: u8, word: u16, bits: u8 },
+	/// Shifting in the word to write to address (or to every address for WRAL).
+	Write { address: Option<u8>, value: u16, bits: u8 },
+	/// The command has been executed, nothing happens until chip select goes low.
+	Done
+}
+
+/// The 93LC56 is accessed serially through a single register, bits are shifted in/out on the rising edge of the clock.
+/// Commands (after the start bit):
+/// 10 AAAAAAAA: READ  - read the word at address A
+/// 01 AAAAAAAA: WRITE - write the 16 bits shifted in after the command to address A
+/// 11 AAAAAAAA: ERASE - set the word at address A to 0xFFFF
+/// 00 11xxxxxx: EWEN  - enable erasing/writing
+/// 00 00xxxxxx: EWDS  - disable erasing/writing
+/// 00 10xxxxxx: ERAL  - set every word to 0xFFFF
+/// 00 01xxxxxx: WRAL  - write the 16 bits shifted in after the command to every address
+/// Only the lower 7 address bits are used, there are 128 words. Writes and erases complete immediately.
+#[derive(Serialize, Deserialize)]
+struct Eeprom {
+	chip_select: bool,
+	clock: bool,
+	data_in: bool,
+	data_out: bool,
+	write_enable: bool,
+	state: EepromState
+}
+
+impl Eeprom {
+	fn new() -> Eeprom {
+		Eeprom {
+			chip_select: false,
+			clock: false,
+			data_in: false,
+			data_out: true,
+			write_enable: false,
+			state: EepromState::Idle
+		}
+	}
+
+	/// Bit 7: chip select, bit 6: clock, bit 1: data in, bit 0: data out
+	fn read(&self) -> u8 {
+		((self.chip_select as u8) << 7) | ((self.clock as u8) << 6) | ((self.data_in as u8) << 1) | (self.data_out as u8)
+	}
+
+	/// Returns true if a word was written or erased.
+	fn write(&mut self, eeprom: &mut [u8], value: u8) -> bool {
+		let chip_select = value & 0x80 != 0;
+		let clock = value & 0x40 != 0;
+		self.data_in = value & 0x02 != 0;
+
+		let mut written = false;
+		if !chip_select {
+			self.state = EepromState::Idle;
+			self.data_out = true; // ready
+		}
+		else if clock && !self.clock {
+			written = self.clock_rising_edge(eeprom);
+		}
+
+		self.chip_select = chip_select;
+		self.clock = clock;
+		written
+	}
+
+	fn clock_rising_edge(&mut self, eeprom: &mut [u8]) -> bool {
+		let bit = self.data_in as u16;
+		let mut written = false;
+		self.state = match self.state {
+			EepromState::Idle if self.data_in => EepromState::Command { value: 0, bits: 0 },
+			EepromState::Idle => EepromState::Idle,
+			EepromState::Command { value, bits } => {
+				let value = (value << 1) | bit;
+				if bits + 1 == 10 {
+					let (state, erased) = self.execute(eeprom, value);
+					written = erased;
+					state
+				}
+				else {
+					EepromState::Command { value: value, bits: bits + 1 }
+				}
+			},
+			EepromState::Read { address, word, bits } => {
+				self.data_out = word & 0x8000 != 0;
+				if bits > 1 {
+					EepromState::Read { address: address, word: word << 1, bits: bits - 1 }
+				}
+				else {
+					let address = (address + 1) & 0x7F;
+					EepromState::Read { address: address, word: Eeprom::read_word(eeprom, address), bits: 16 }
+				}
+			},
+			EepromState::Write { address, value, bits } => {
+				let value = (value << 1) | bit;
+				if bits + 1 == 16 {
+					if self.write_enable {
+						match address {
+							Some(address) => Eeprom::write_word(eeprom, address, value),
+							None => for address in 0..0x80 { Eeprom::write_word(eeprom, address, value); }
+						}
+						written = true;
+					}
+					self.data_out = true;
+					EepromState::Done
+				}
+				else {
+					EepromState::Write { address: address, value: value, bits: bits + 1 }
+				}
+			},
+			EepromState::Done => EepromState::Done
+		};
+		written
+	}
+
+	/// Execute a command, returns the state the eeprom is in afterwards, and whether it erased anything.
+	fn execute(&mut self, eeprom: &mut [u8], command: u16) -> (EepromState, bool) {
+		let address = (command & 0x7F) as u8;
+		match (command >> 8) & 3 {
+			0b10 => {
+				self.data_out = false; // a dummy 0 bit comes before the data
+				(EepromState::Read { address: address, word: Eeprom::read_word(eeprom, address), bits: 16 }, false)
+			},
+			0b01 => (EepromState::Write { address: Some(address), value: 0, bits: 0 }, false),
+			0b11 => {
+				if self.write_enable {
+					Eeprom::write_word(eeprom, address, 0xFFFF);
+				}
+				(EepromState::Done, self.write_enable)
+			},
+			_ => match (command >> 6) & 3 {
+				0b11 => {
+					self.write_enable = true;
+					(EepromState::Done, false)
+				},
+				0b00 => {
+					self.write_enable = false;
+					(EepromState::Done, false)
+				},
+				0b10 => {
+					if self.write_enable {
+						for address in 0..0x80 {
+							Eeprom::write_word(eeprom, address, 0xFFFF);
+						}
+					}
+					(EepromState::Done, self.write_enable)
+				},
+				_ => (EepromState::Write { address: None, value: 0, bits: 0 }, false)
+			}
+		}
+	}
+
+	fn read_word(eeprom: &[u8], address: u8) -> u16 {
+		let index = address as usize * 2;
+		if index + 1 < eeprom.len() {
+			eeprom[index] as u16 | ((eeprom[index + 1] as u16) << 8)
+		}
+		else {
+			0xFFFF
+		}
+	}
+
+	fn write_word(eeprom: &mut [u8], address: u8, value: u16) {
+		let index = address as usize * 2;
+		if index + 1 < eeprom.len() {
+			eeprom[index] = value as u8;
+			eeprom[index + 1] = (value >> 8) as u8;
+		}
+	}
+}
+
+/// The MBC7 has an accelerometer and a serial eeprom (instead of ram) mapped to 0xA000 - 0xAFFF.
+/// Both ram enable registers (0x0000 - 0x1FFF: 0x0A, 0x4000 - 0x5FFF: 0x40) have to be set to access them.
+/// Bits 4-7 of the address select the register:
+/// Ax0x: write 0x55 to erase the latched accelerometer values
+/// Ax1x: write 0xAA to latch the accelerometer values (after erasing them)
+/// Ax2x / Ax3x: latched x value (low / high byte)
+/// Ax4x / Ax5x: latched y value (low / high byte)
+/// Ax6x: always 0x00
+/// Ax7x: always 0xFF
+/// Ax8x: eeprom
+#[derive(Serialize, Deserialize)]
+pub struct MBC7 {
+	rom_bank: u8,      /* current rom bank (7 bits) */
+	ram_enable_1: bool,
+	ram_enable_2: bool,
+	tilt: (f32, f32),  /* current acceleration in g */
+	latch: (u16, u16),
+	latch_erased: bool,
+	eeprom: Eeprom
+}
+
+impl MBC7 {
+	pub fn new() -> MBC7 {
+		MBC7 {
+			rom_bank: 1,
+			ram_enable_1: false,
+			ram_enable_2: false,
+			tilt: (0.0, 0.0),
+			latch: (ACCELEROMETER_ERASED, ACCELEROMETER_ERASED),
+			latch_erased: false,
+			eeprom: Eeprom::new()
+		}
+	}
+
+	fn registers_enabled(&self) -> bool {
+		self.ram_enable_1 && self.ram_enable_2
+	}
+
+	fn accelerometer_value(g: f32) -> u16 {
+		(ACCELEROMETER_CENTER + g * ACCELEROMETER_G) as u16
+	}
+
+	/// The accelerometer saturates at 1g in each direction (NaN is treated as level).
+	fn clamp_tilt(g: f32) -> f32 {
+		if g.is_nan() {
+			0.0
+		}
+		else {
+			g.max(-1.0).min(1.0)
+		}
+	}
+}
+
+impl MemoryBankController for MBC7 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank as usize),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	#[allow(unused_variables)]
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		if !self.registers_enabled() || offset >= 0x1000 {
+			return 0xFF;
+		}
+		match (offset >> 4) & 0x0F {
+			0x2 => self.latch.0 as u8,
+			0x3 => (self.latch.0 >> 8) as u8,
+			0x4 => self.latch.1 as u8,
+			0x5 => (self.latch.1 >> 8) as u8,
+			0x6 => 0x00,
+			0x8 => self.eeprom.read(),
+			_ => 0xFF
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		//0x0000...0x1FFF - RAM enable 1 (0x0A)
+		//0x2000...0x3FFF - ROM Bank number (7 bits)
+		//0x4000...0x5FFF - RAM enable 2 (0x40)
+		//0x6000...0x7FFF - nothing
+		match address {
+			0x0000...0x1FFF => self.ram_enable_1 = value == 0x0A,
+			0x2000...0x3FFF => self.rom_bank = value & 0x7F,
+			0x4000...0x5FFF => self.ram_enable_2 = value == 0x40,
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		if !self.registers_enabled() || offset >= 0x1000 {
+			return false;
+		}
+		match (offset >> 4) & 0x0F {
+			0x0 if value == 0x55 => {
+				self.latch = (ACCELEROMETER_ERASED, ACCELEROMETER_ERASED);
+				self.latch_erased = true;
+				false
+			},
+			0x1 if value == 0xAA && self.latch_erased => {
+				self.latch = (MBC7::accelerometer_value(self.tilt.0), MBC7::accelerometer_value(self.tilt.1));
+				self.latch_erased = false;
+				false
+			},
+			0x8 => {
+				let size = ram_size.min(ram.len());
+				self.eeprom.write(&mut ram[..size], value)
+			},
+			_ => false
+		}
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank as usize
+	}
+
+	fn ram_bank(&self) -> usize {
+		0 //the eeprom isn't banked
+	}
+
+	fn set_tilt(&mut self, x: f32, y: f32) {
+		self.tilt = (MBC7::clamp_tilt(x), MBC7::clamp_tilt(y));
+	}
+}
@@ -0,0 +1,156 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, RAM_BANK_SIZE, write_ram};
+use super::MemoryBankController;
+
+/// The MMM01 is used by multicarts: it starts out unmapped, with the last 32KiB of the rom (the menu) mapped to
+/// 0x0000 - 0x7FFF. The menu then sets up the outer bank bits and masks of the game it wants to run, and locks them
+/// by setting the map enable bit. After that the MMM01 behaves like an MBC1 limited to the game's part of the rom/ram.
+///
+/// Registers (bits marked with * can only be written while unmapped):
+/// 0x0000 - 0x1FFF: bit 6*: map enable, bits 4-5*: ram bank write protect, bits 0-3: ram enable (0x0A)
+/// 0x2000 - 0x3FFF: bits 5-6*: rom bank bits 5-6, bits 0-4: rom bank bits 0-4
+/// 0x4000 - 0x5FFF: bit 6*: mode write protect, bits 4-5*: rom bank bits 7-8, bits 2-3*: ram bank bits 2-3, bits 0-1: ram bank bits 0-1
+/// 0x6000 - 0x7FFF: bits 2-5*: rom bank write protect (bits 1-4), bit 0: mbc1 mode
+#[derive(Serialize, Deserialize)]
+pub struct MMM01 {
+	mapped: bool,
+	rom_bank: u16,      /* 9 bits, only bits 0-4 can be changed by the game once mapped */
+	rom_bank_mask: u8,  /* set bits of rom bank bits 0-4 can't be changed once mapped */
+	ram_bank: u8,       /* 4 bits, only bits 0-1 can be changed by the game once mapped */
+	ram_bank_mask: u8,  /* set bits of ram bank bits 0-1 can't be changed once mapped */
+	ram_enable: bool,
+	mbc1_mode: bool,    /* false: rom banking mode, true: ram banking mode (the same as on the MBC1) */
+	mode_locked: bool   /* the mbc1 mode can't be changed once mapped */
+}
+
+impl MMM01 {
+	pub fn new() -> MMM01 {
+		MMM01 {
+			mapped: false,
+			rom_bank: 0,
+			rom_bank_mask: 0,
+			ram_bank: 0,
+			ram_bank_mask: 0,
+			ram_enable: false,
+			mbc1_mode: false,
+			mode_locked: false
+		}
+	}
+
+	/// The bank mapped to 0x0000 - 0x3FFF: the writable low bits of the rom bank number are 0.
+	fn rom_bank_low(&self) -> usize {
+		if self.mapped {
+			(self.rom_bank & !(0x1F & !self.rom_bank_mask as u16)) as usize
+		}
+		else {
+			0x1FE // second to last bank, the menu is always at the end of the rom
+		}
+	}
+
+	/// The bank mapped to 0x4000 - 0x7FFF: like on the MBC1, bank 0 of the game can't be selected.
+	fn rom_bank_high(&self) -> usize {
+		if self.mapped {
+			if self.rom_bank & 0x1F & !(self.rom_bank_mask as u16) == 0 {
+				(self.rom_bank | 1) as usize
+			}
+			else {
+				self.rom_bank as usize
+			}
+		}
+		else {
+			0x1FF // last bank
+		}
+	}
+
+	fn ram_address(&self, offset: u16) -> usize {
+		let ram_bank = if self.mbc1_mode {
+			self.ram_bank
+		}
+		else {
+			self.ram_bank & 0x0C
+		};
+		offset as usize + (RAM_BANK_SIZE * ram_bank as usize)
+	}
+}
+
+impl MemoryBankController for MMM01 {
+	/// The rom size is a power of 2, so the bank numbers wrap around if the rom is smaller than 8MiB.
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize + (ROM_BANK_SIZE * self.rom_bank_low()),
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank_high()),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		let address = if rom_size > 0 { address % rom_size } else { address };
+		if address < rom.len() {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		let address = self.ram_address(offset);
+		if self.ram_enable && address < ram_size {
+			ram[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		match address {
+			0x0000...0x1FFF => {
+				self.ram_enable = value & 0x0F == 0x0A;
+				if !self.mapped {
+					self.ram_bank_mask = (value >> 4) & 0x03;
+					self.mapped = value & 0x40 != 0;
+				}
+			},
+			0x2000...0x3FFF => {
+				let writable: u16 = if self.mapped {
+					0x1F & !(self.rom_bank_mask as u16)
+				}
+				else {
+					0x7F
+				};
+				self.rom_bank = (self.rom_bank & !writable) | (value as u16 & writable);
+			},
+			0x4000...0x5FFF => {
+				if self.mapped {
+					let writable = 0x03 & !self.ram_bank_mask;
+					self.ram_bank = (self.ram_bank & !writable) | (value & writable);
+				}
+				else {
+					self.ram_bank = value & 0x0F;
+					self.rom_bank = (self.rom_bank & 0x7F) | (((value as u16 >> 4) & 0x03) << 7);
+					self.mode_locked = value & 0x40 != 0;
+				}
+			},
+			0x6000...0x7FFF => {
+				if !(self.mapped && self.mode_locked) {
+					self.mbc1_mode = value & 1 == 1;
+				}
+				if !self.mapped {
+					self.rom_bank_mask = (value & 0x3C) >> 1;
+				}
+			},
+			_ => {}
+		}
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		let address = self.ram_address(offset);
+		self.ram_enable && address < ram_size && write_ram(ram, address, value)
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank_high()
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.ram_address(0) / RAM_BANK_SIZE
+	}
+}
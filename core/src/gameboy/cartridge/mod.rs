@@ -1,14 +1,54 @@
 mod nombc;
 mod mbc1;
+mod mbc2;
 mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod huc1;
+mod huc3;
+mod mmm01;
+mod tama5;
+pub mod camera;
+pub mod patch;
+pub mod gbs;
+
+use std::cmp;
 
 use gameboy::cartridge::nombc::NoMBC;
 use gameboy::cartridge::mbc1::MBC1;
+use gameboy::cartridge::mbc2::{MBC2, MBC2_RAM_SIZE};
 use gameboy::cartridge::mbc3::MBC3;
+use gameboy::cartridge::mbc5::MBC5;
+use gameboy::cartridge::mbc6::MBC6;
+use gameboy::cartridge::mbc7::{MBC7, MBC7_EEPROM_SIZE};
+use gameboy::cartridge::huc1::HuC1;
+use gameboy::cartridge::huc3::HuC3;
+use gameboy::cartridge::mmm01::MMM01;
+use gameboy::cartridge::tama5::{TAMA5, TAMA5_RAM_SIZE};
+use gameboy::cartridge::camera::{Camera, ImageSource};
+use gameboy::ppu::Bitmap;
 
 pub const ROM_BANK_SIZE: usize = 0x4000;
 pub const RAM_BANK_SIZE: usize = 0x2000;
 
+/// The logo at 0x104 - 0x133 in the header of every licensed cartridge.
+pub const NINTENDO_LOGO: [u8; 48] = [
+	0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+	0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+	0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+/// Size of each game on an MBC1M multicart.
+const MBC1M_GAME_SIZE: usize = 0x40000;
+
+/// Write a byte of cartridge ram (address must be in the ram), returns true if the byte changed.
+fn write_ram(ram: &mut [u8], address: usize, value: u8) -> bool {
+	let changed = ram[address] != value;
+	ram[address] = value;
+	changed
+}
+
 #[derive(Debug)]
 #[derive(Serialize, Deserialize)]
 pub enum MBCType {
@@ -27,15 +67,71 @@ pub enum MBCType {
 	HUC1
 }
 
+/// How the cartridge supports the cgb (header byte 0x143).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub enum CgbSupport {
+	/// A dmg game, the cgb runs it in dmg compatibility mode.
+	None,
+	/// Runs on any model, but uses the cgb's features when it's available (0x80).
+	Enhanced,
+	/// Only runs on the cgb (0xC0).
+	Only
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub enum Licensee {
+	/// The old licensee code (0x14B).
+	Old(u8),
+	/// The two character new licensee code (0x144 - 0x145), used when the old licensee code is 0x33.
+	New(String)
+}
+
+impl Licensee {
+	/// Is the game published by nintendo (old licensee code 0x01, or new licensee code "01")?
+	pub fn is_nintendo(&self) -> bool {
+		match self {
+			Licensee::Old(code) => *code == 0x01,
+			Licensee::New(code) => code == "01"
+		}
+	}
+}
+
+/// Where the cartridge was sold (0x14A).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub enum Destination {
+	Japan,
+	Overseas
+}
+
 #[derive(Debug)]
 #[derive(Serialize, Deserialize)]
 pub struct CartInfo {
 	pub title: String,
+	/// The 4 character manufacturer code at the end of the title field (0x13F - 0x142), only newer cgb carts have one.
+	pub manufacturer_code: Option<String>,
 	pub sgb: bool,
+	/// Does the cartridge support the cgb? (true for both cgb enhanced and cgb only carts)
 	pub cgb: bool,
+	pub cgb_support: CgbSupport,
+	pub licensee: Licensee,
+	pub destination: Destination,
+	/// The mask rom version number (0x14C).
+	pub version: u8,
+	/// The checksum of 0x134 - 0x14C (0x14D), the boot rom locks up if it's wrong.
+	pub header_checksum: u8,
+	pub header_checksum_valid: bool,
+	/// The sum of every byte in the rom except the checksum itself (0x14E - 0x14F, big endian), nothing checks this.
+	pub global_checksum: u16,
+	pub global_checksum_valid: bool,
 	pub mbc_type: MBCType,
 	pub battery: bool,
 	pub rtc: bool,
+	pub rumble: bool,
+	/// Is this an MBC1M multicart? (detected from the rom contents, the header doesn't say)
+	pub multicart: bool,
 	pub rom_size: usize,
 	pub ram_size: usize,
 }
@@ -57,17 +153,62 @@ pub trait Cartridge {
 
 	fn ram(&self) -> &[u8];
 	fn ram_mut(&mut self) -> &mut[u8];
+
+	/// The rom bank currently mapped to 0x4000 - 0x7FFF
+	fn rom_bank(&self) -> usize;
+
+	/// The ram bank currently mapped to 0xA000 - 0xBFFF
+	fn ram_bank(&self) -> usize;
+
+	/// Is the rumble motor on? (always false for carts without one)
+	fn rumble(&self) -> bool;
+
+	/// Is the infrared led on? (always false for carts without an ir port)
+	fn ir_led(&self) -> bool;
+
+	/// Set the acceleration (in g) measured by the cart's accelerometer (ignored by carts without one).
+	fn set_tilt(&mut self, x: f32, y: f32);
+
+	/// Advance the cart's rtc to the current time (a unix timestamp), ignored by carts without one.
+	fn set_time(&mut self, now: i64);
 }
 
 pub trait MemoryBankController: Send {
 	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8;
 	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8;
 
-	fn write_byte_rom(&mut self, offset: u16, value: u8);
-	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8);
+	/// The writes return true if they changed state that's kept in battery saves (the ram, or the battery footer).
+	fn write_byte_rom(&mut self, offset: u16, value: u8) -> bool;
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool;
 
 	fn rom_bank(&self) -> usize;
 	fn ram_bank(&self) -> usize;
+
+	/// Only carts with a rumble motor need to implement this.
+	fn rumble(&self) -> bool {
+		false
+	}
+
+	/// Only carts with an ir port need to implement this.
+	fn ir_led(&self) -> bool {
+		false
+	}
+
+	/// Only carts with an accelerometer need to implement this.
+	#[allow(unused_variables)]
+	fn set_tilt(&mut self, x: f32, y: f32) {}
+
+	/// Only carts with an rtc need to implement this.
+	#[allow(unused_variables)]
+	fn set_time(&mut self, now: i64) {}
+
+	/// Only carts with state that has to survive without a save state (like an rtc) need to implement these.
+	fn battery_footer(&self) -> Vec<u8> {
+		Vec::new()
+	}
+
+	#[allow(unused_variables)]
+	fn load_battery_footer(&mut self, footer: &[u8]) {}
 }
 
 impl CartInfo {
@@ -76,16 +217,54 @@ impl CartInfo {
 			return Err("Rom is too small to contain a rom header (rom is smaller than 0x150 bytes)");
 		}
 
-		let mbc_type: MBCType = try!(CartInfo::get_type(rom[0x0147]));
-		let rom_size: usize = try!(CartInfo::get_rom_size(rom[0x0148]));
-		let ram_size: usize = try!(CartInfo::get_ram_size(rom[0x0149]));
+		let header: &[u8] = &rom[CartInfo::header_offset(rom)..];
+
+		let mbc_type: MBCType = try!(CartInfo::get_type(header[0x0147]));
+		let rom_size: usize = match mbc_type {
+			// the menu's header doesn't always include the games, but the rom has to be a power of 2
+			MBCType::MMM01 => cmp::max(try!(CartInfo::get_rom_size(header[0x0148])), rom.len().next_power_of_two()),
+			_ => try!(CartInfo::get_rom_size(header[0x0148]))
+		};
+		let ram_size: usize = match mbc_type {
+			MBCType::MBC2 => MBC2_RAM_SIZE, // the ram is built into the mbc, so the header doesn't specify any
+			MBCType::MBC7 => MBC7_EEPROM_SIZE, // the save data is in an eeprom, not ram
+			MBCType::TAMA5 => TAMA5_RAM_SIZE, // the ram is in the TAMA6 microcontroller
+			_ => try!(CartInfo::get_ram_size(header[0x0149]))
+		};
+
+		let manufacturer_code = CartInfo::get_manufacturer_code(header);
+		let title_end = match (&manufacturer_code, header[0x0143] & 0x80 == 0x80) {
+			(&Some(_), _) => 0x013F,
+			(&None, true) => 0x0143, // the last byte of the title is the cgb flag
+			(&None, false) => 0x0144
+		};
+		let global_checksum = (header[0x014E] as u16) << 8 | header[0x014F] as u16;
+		let global_checksum_offset = CartInfo::header_offset(rom) + 0x014E;
 
 		let info = CartInfo {
-			title: String::from(""),	//TODO: Cart title
-			sgb: rom[0x0146] == 0x03,
-			cgb: rom[0x0143] & 0x80 == 0x80,
-			battery: CartInfo::has_battery(rom[0x0147]),
-			rtc: CartInfo::has_rtc(rom[0x0147]),
+			title: CartInfo::get_title(&header[0x0134..title_end]),
+			manufacturer_code: manufacturer_code,
+			sgb: header[0x0146] == 0x03,
+			cgb: header[0x0143] & 0x80 == 0x80,
+			cgb_support: match header[0x0143] {
+				0xC0 => CgbSupport::Only,
+				flag if flag & 0x80 == 0x80 => CgbSupport::Enhanced,
+				_ => CgbSupport::None
+			},
+			licensee: match header[0x014B] {
+				0x33 => Licensee::New(String::from_utf8_lossy(&header[0x0144..0x0146]).into_owned()),
+				code => Licensee::Old(code)
+			},
+			destination: if header[0x014A] == 0 { Destination::Japan } else { Destination::Overseas },
+			version: header[0x014C],
+			header_checksum: header[0x014D],
+			header_checksum_valid: CartInfo::calculate_header_checksum(header) == header[0x014D],
+			global_checksum: global_checksum,
+			global_checksum_valid: CartInfo::calculate_global_checksum(rom, global_checksum_offset) == global_checksum,
+			battery: CartInfo::has_battery(header[0x0147]),
+			rtc: CartInfo::has_rtc(header[0x0147]),
+			rumble: CartInfo::has_rumble(header[0x0147]),
+			multicart: CartInfo::is_mbc1_multicart(&mbc_type, rom),
 			mbc_type: mbc_type,
 			rom_size: rom_size,
 			ram_size: ram_size,
@@ -94,6 +273,76 @@ impl CartInfo {
 		Ok(info)
 	}
 
+	/// Where the header that describes the cartridge hardware is.
+	/// MMM01 multicarts start with the last 32KiB of the rom mapped (the menu), so that's where their header is,
+	/// the header at 0x100 belongs to the first game on the cart.
+	fn header_offset(rom: &Box<[u8]>) -> usize {
+		let menu = rom.len().saturating_sub(0x8000);
+		if menu > 0 && CartInfo::is_mmm01(rom[menu + 0x0147]) && !CartInfo::is_mmm01(rom[0x0147]) {
+			menu
+		}
+		else {
+			0
+		}
+	}
+
+	/// MBC1M multicarts look like regular 1MiB MBC1 carts, but each 256KiB game on them has its own header,
+	/// so if the nintendo logo shows up at the start of more than one of them, it's most likely a multicart.
+	fn is_mbc1_multicart(mbc_type: &MBCType, rom: &Box<[u8]>) -> bool {
+		match *mbc_type {
+			MBCType::MBC1 if rom.len() == 4 * MBC1M_GAME_SIZE => {
+				let logos = (0..4)
+					.map(|game| game * MBC1M_GAME_SIZE + 0x104)
+					.filter(|&start| &rom[start..start + NINTENDO_LOGO.len()] == &NINTENDO_LOGO[..])
+					.count();
+				logos >= 2
+			},
+			_ => false
+		}
+	}
+
+	fn is_mmm01(cart_type: u8) -> bool {
+		match cart_type {
+			0x0B...0x0D => true,
+			_ => false
+		}
+	}
+
+	/// The title is upper case ascii, padded with zeros.
+	fn get_title(title: &[u8]) -> String {
+		title.iter()
+			.take_while(|c| **c != 0)
+			.filter(|c| c.is_ascii_graphic() || **c == b' ')
+			.map(|c| *c as char)
+			.collect::<String>()
+			.trim_end()
+			.to_string()
+	}
+
+	/// Only cgb carts have a manufacturer code, it's 4 upper case letters or digits in the place of the end of the title.
+	fn get_manufacturer_code(header: &[u8]) -> Option<String> {
+		let code = &header[0x013F..0x0143];
+		if header[0x0143] & 0x80 == 0x80 && code.iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+			Some(String::from_utf8_lossy(code).into_owned())
+		}
+		else {
+			None
+		}
+	}
+
+	/// x = 0; for each byte in 0x134 - 0x14C: x = x - byte - 1
+	fn calculate_header_checksum(header: &[u8]) -> u8 {
+		header[0x0134..0x014D].iter().fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1))
+	}
+
+	/// The sum of every byte in the rom except the two bytes of the global checksum (at offset).
+	fn calculate_global_checksum(rom: &[u8], offset: usize) -> u16 {
+		rom.iter()
+			.enumerate()
+			.filter(|&(i, _)| i != offset && i != offset + 1)
+			.fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16))
+	}
+
 	fn has_battery(cart_type: u8) -> bool {
 		match cart_type {
 			0x03 => true,
@@ -105,7 +354,11 @@ impl CartInfo {
 			0x17 => true,
 			0x1B => true,
 			0x1E => true,
+			0x20 => true,
 			0x22 => true,
+			0xFC => true,
+			0xFD => true,
+			0xFE => true,
 			0xFF => true,
 			_ => false,
 		}
@@ -114,6 +367,14 @@ impl CartInfo {
 	fn has_rtc(cart_type: u8) -> bool {
 		match cart_type {
 			0x0F | 0x10 => true,
+			0xFD | 0xFE => true,
+			_ => false
+		}
+	}
+
+	fn has_rumble(cart_type: u8) -> bool {
+		match cart_type {
+			0x1C...0x1E => true,
 			_ => false
 		}
 	}
@@ -140,7 +401,7 @@ impl CartInfo {
 
 	fn get_rom_size(rom_size: u8) -> Result<usize, & 'static str> {
 		match rom_size {
-			0x00...0x07 => Ok(0x8000 << rom_size),
+			0x00...0x08 => Ok(0x8000 << rom_size),
 			0x52 => Ok(0x4000 * 72),
 			0x53 => Ok(0x4000 * 80),
 			0x54 => Ok(0x4000 * 96),
@@ -169,7 +430,16 @@ impl CartInfo {
 enum MBC {
 	NoMBC(Box<NoMBC>),
 	Mbc1(Box<MBC1>),
-	Mbc3(Box<MBC3>)
+	Mbc2(Box<MBC2>),
+	Mbc3(Box<MBC3>),
+	Mbc5(Box<MBC5>),
+	Mbc6(Box<MBC6>),
+	Mbc7(Box<MBC7>),
+	HuC1(Box<HuC1>),
+	HuC3(Box<HuC3>),
+	Mmm01(Box<MMM01>),
+	Tama5(Box<TAMA5>),
+	Camera(Box<Camera>)
 }
 
 impl MemoryBankController for MBC {
@@ -177,7 +447,16 @@ impl MemoryBankController for MBC {
 		match self {
 			MBC::NoMBC(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
 			MBC::Mbc1(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
-			MBC::Mbc3(mbc) => mbc.read_byte_rom(rom, rom_size, offset)
+			MBC::Mbc2(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Mbc3(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Mbc5(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Mbc6(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Mbc7(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::HuC1(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::HuC3(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Mmm01(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Tama5(mbc) => mbc.read_byte_rom(rom, rom_size, offset),
+			MBC::Camera(mbc) => mbc.read_byte_rom(rom, rom_size, offset)
 		}
 	}
 
@@ -185,23 +464,50 @@ impl MemoryBankController for MBC {
 		match self {
 			MBC::NoMBC(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
 			MBC::Mbc1(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
-			MBC::Mbc3(mbc) => mbc.read_byte_ram(ram, ram_size, offset)
+			MBC::Mbc2(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Mbc3(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Mbc5(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Mbc6(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Mbc7(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::HuC1(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::HuC3(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Mmm01(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Tama5(mbc) => mbc.read_byte_ram(ram, ram_size, offset),
+			MBC::Camera(mbc) => mbc.read_byte_ram(ram, ram_size, offset)
 		}
 	}
 
-	fn write_byte_rom(&mut self, offset: u16, value: u8) {
+	fn write_byte_rom(&mut self, offset: u16, value: u8) -> bool {
 		match self {
 			MBC::NoMBC(mbc) => mbc.write_byte_rom(offset, value),
 			MBC::Mbc1(mbc) => mbc.write_byte_rom(offset, value),
-			MBC::Mbc3(mbc) => mbc.write_byte_rom(offset, value)
+			MBC::Mbc2(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Mbc3(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Mbc5(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Mbc6(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Mbc7(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::HuC1(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::HuC3(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Mmm01(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Tama5(mbc) => mbc.write_byte_rom(offset, value),
+			MBC::Camera(mbc) => mbc.write_byte_rom(offset, value)
 		}
 	}
 
-	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) {
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
 		match self {
 			MBC::NoMBC(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
 			MBC::Mbc1(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
-			MBC::Mbc3(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value)
+			MBC::Mbc2(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Mbc3(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Mbc5(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Mbc6(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Mbc7(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::HuC1(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::HuC3(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Mmm01(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Tama5(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value),
+			MBC::Camera(mbc) => mbc.write_byte_ram(ram, ram_size, offset, value)
 		}
 	}
 
@@ -209,7 +515,16 @@ impl MemoryBankController for MBC {
 		match self {
 			MBC::NoMBC(mbc) => mbc.rom_bank(),
 			MBC::Mbc1(mbc) => mbc.rom_bank(),
-			MBC::Mbc3(mbc) => mbc.rom_bank()
+			MBC::Mbc2(mbc) => mbc.rom_bank(),
+			MBC::Mbc3(mbc) => mbc.rom_bank(),
+			MBC::Mbc5(mbc) => mbc.rom_bank(),
+			MBC::Mbc6(mbc) => mbc.rom_bank(),
+			MBC::Mbc7(mbc) => mbc.rom_bank(),
+			MBC::HuC1(mbc) => mbc.rom_bank(),
+			MBC::HuC3(mbc) => mbc.rom_bank(),
+			MBC::Mmm01(mbc) => mbc.rom_bank(),
+			MBC::Tama5(mbc) => mbc.rom_bank(),
+			MBC::Camera(mbc) => mbc.rom_bank()
 		}
 	}
 
@@ -217,7 +532,67 @@ impl MemoryBankController for MBC {
 		match self {
 			MBC::NoMBC(mbc) => mbc.ram_bank(),
 			MBC::Mbc1(mbc) => mbc.ram_bank(),
-			MBC::Mbc3(mbc) => mbc.ram_bank()
+			MBC::Mbc2(mbc) => mbc.ram_bank(),
+			MBC::Mbc3(mbc) => mbc.ram_bank(),
+			MBC::Mbc5(mbc) => mbc.ram_bank(),
+			MBC::Mbc6(mbc) => mbc.ram_bank(),
+			MBC::Mbc7(mbc) => mbc.ram_bank(),
+			MBC::HuC1(mbc) => mbc.ram_bank(),
+			MBC::HuC3(mbc) => mbc.ram_bank(),
+			MBC::Mmm01(mbc) => mbc.ram_bank(),
+			MBC::Tama5(mbc) => mbc.ram_bank(),
+			MBC::Camera(mbc) => mbc.ram_bank()
+		}
+	}
+
+	fn rumble(&self) -> bool {
+		match self {
+			MBC::Mbc5(mbc) => mbc.rumble(),
+			_ => false
+		}
+	}
+
+	fn ir_led(&self) -> bool {
+		match self {
+			MBC::HuC1(mbc) => mbc.ir_led(),
+			MBC::HuC3(mbc) => mbc.ir_led(),
+			_ => false
+		}
+	}
+
+	fn set_tilt(&mut self, x: f32, y: f32) {
+		match self {
+			MBC::Mbc7(mbc) => mbc.set_tilt(x, y),
+			_ => {}
+		}
+	}
+
+	fn set_time(&mut self, now: i64) {
+		match self {
+			MBC::Mbc3(mbc) => mbc.set_time(now),
+			MBC::HuC3(mbc) => mbc.set_time(now),
+			MBC::Tama5(mbc) => mbc.set_time(now),
+			_ => {}
+		}
+	}
+
+	fn battery_footer(&self) -> Vec<u8> {
+		match self {
+			MBC::Mbc3(mbc) => mbc.battery_footer(),
+			MBC::Mbc6(mbc) => mbc.battery_footer(),
+			MBC::HuC3(mbc) => mbc.battery_footer(),
+			MBC::Tama5(mbc) => mbc.battery_footer(),
+			_ => Vec::new()
+		}
+	}
+
+	fn load_battery_footer(&mut self, footer: &[u8]) {
+		match self {
+			MBC::Mbc3(mbc) => mbc.load_battery_footer(footer),
+			MBC::Mbc6(mbc) => mbc.load_battery_footer(footer),
+			MBC::HuC3(mbc) => mbc.load_battery_footer(footer),
+			MBC::Tama5(mbc) => mbc.load_battery_footer(footer),
+			_ => {}
 		}
 	}
 }
@@ -229,53 +604,128 @@ pub struct VirtualCartridge {
 	ram: Box<[u8]>,
 	cart_info: CartInfo,
 	mbc: MBC,
+	battery_generation: u64, // incremented on every write that changes the battery backed ram (or mapper state saved with it)
 }
 
 impl VirtualCartridge {
 	pub fn new(rom: Box<[u8]>, ram: Option<Box<[u8]>>) -> Result<VirtualCartridge, & 'static str> {
 		let cart_info: CartInfo = try!(CartInfo::new(&rom));
 
-		let ram = match ram {
-			Some(ram) => {
-				if ram.len() < cart_info.ram_size {
-					// ram too small, expand it to the proper size
-					// TODO: warning message?
-					let mut vec = Vec::from(ram);
-					vec.resize(cart_info.ram_size, 0);
-					vec.into_boxed_slice()
-				}
-				else {
-					ram
-				}
-			},
-			None => {
-				//No ram supplied, allocate some.
-				let mut vec: Vec<u8> = Vec::with_capacity(cart_info.ram_size);
-				vec.resize(cart_info.ram_size, 0);
-				vec.into_boxed_slice()
-			}
-		};
-
 		let mbc: Result<MBC, & 'static str> = match cart_info.mbc_type {
 			MBCType::NONE => Ok(MBC::NoMBC(Box::new(NoMBC::new()))),
-			MBCType::MBC1 => Ok(MBC::Mbc1(Box::new(MBC1::new()))),
+			MBCType::MBC1 => Ok(MBC::Mbc1(Box::new(MBC1::new(cart_info.multicart)))),
+			MBCType::MBC2 => Ok(MBC::Mbc2(Box::new(MBC2::new()))),
 			MBCType::MBC3 => Ok(MBC::Mbc3(Box::new(MBC3::new(cart_info.rtc)))),
+			MBCType::MBC5 => Ok(MBC::Mbc5(Box::new(MBC5::new(cart_info.rumble)))),
+			MBCType::MBC6 => Ok(MBC::Mbc6(Box::new(MBC6::new()))),
+			MBCType::MBC7 => Ok(MBC::Mbc7(Box::new(MBC7::new()))),
+			MBCType::HUC1 => Ok(MBC::HuC1(Box::new(HuC1::new()))),
+			MBCType::HUC3 => Ok(MBC::HuC3(Box::new(HuC3::new()))),
+			MBCType::MMM01 => Ok(MBC::Mmm01(Box::new(MMM01::new()))),
+			MBCType::CAMERA => Ok(MBC::Camera(Box::new(Camera::new()))),
+			MBCType::TAMA5 => Ok(MBC::Tama5(Box::new(TAMA5::new()))),
 			_ => {
 				Err("Unimplemented MBC")	//TODO: more helpful error message
 			},
 		};
 
-		let mbc = try!(mbc);
+		let mut vec: Vec<u8> = Vec::with_capacity(cart_info.ram_size);
+		vec.resize(cart_info.ram_size, 0);
 
-		let cart = VirtualCartridge {
+		let mut cart = VirtualCartridge {
 			rom: rom,
-			ram: ram,
-			mbc: mbc,
+			ram: vec.into_boxed_slice(),
+			mbc: try!(mbc),
 			cart_info: cart_info,
+			battery_generation: 0,
 		};
 
+		if let Some(ram) = ram {
+			cart.import_battery_ram(&ram);
+		}
+
 		Ok(cart)
 	}
+
+	/// Override the MBC1M multicart detection, for multicarts the heuristic misses (or regular carts it mistakes for one).
+	pub fn set_mbc1_multicart(&mut self, multicart: bool) -> Result<(), & 'static str> {
+		match self.mbc {
+			MBC::Mbc1(ref mut mbc) => {
+				mbc.set_multicart(multicart);
+				self.cart_info.multicart = multicart;
+				Ok(())
+			},
+			_ => Err("Not an MBC1 cartridge")
+		}
+	}
+
+	/// The contents of the battery backed ram, followed by any mapper state that has to be saved with it (like the MBC3's rtc, or the MBC6's flash).
+	/// This is the format the ram passed to VirtualCartridge::new is expected to be in.
+	pub fn export_battery_ram(&self) -> Vec<u8> {
+		let mut buf = Vec::from(&self.ram[..]);
+		buf.extend(self.mbc.battery_footer());
+		buf
+	}
+
+	/// Replace the battery backed ram (and the mapper state saved after it), in the format returned by export_battery_ram.
+	pub fn import_battery_ram(&mut self, ram: &[u8]) {
+		let ram_size = self.ram.len();
+		if ram.len() < ram_size {
+			// ram too small, the rest is cleared
+			// TODO: warning message?
+			self.ram[..ram.len()].copy_from_slice(ram);
+			for byte in self.ram[ram.len()..].iter_mut() {
+				*byte = 0;
+			}
+		}
+		else {
+			self.ram.copy_from_slice(&ram[..ram_size]);
+		}
+
+		// anything after the ram is the mapper's footer
+		if ram.len() > ram_size {
+			self.mbc.load_battery_footer(&ram[ram_size..]);
+		}
+		self.battery_generation += 1;
+	}
+
+	/// Changes every time the battery backed ram (or the mapper state saved with it) changes,
+	/// frontends can compare it to its value when they last saved to only save when something changed.
+	pub fn battery_generation(&self) -> u64 {
+		self.battery_generation
+	}
+
+	/// Loading a save state replaces the battery backed ram, the generation has to move on from the previous cart's
+	/// (and not go back to the one in the state) so frontends notice the change.
+	pub fn continue_battery_generation(&mut self, previous: &VirtualCartridge) {
+		self.battery_generation = self.battery_generation.max(previous.battery_generation) + 1;
+	}
+
+	/// Set where the Game Boy Camera's sensor gets its images from (a generated test pattern by default).
+	pub fn set_camera_image_source(&mut self, source: Box<ImageSource>) -> Result<(), & 'static str> {
+		match self.mbc {
+			MBC::Camera(ref mut camera) => {
+				camera.set_image_source(source);
+				Ok(())
+			},
+			_ => Err("Not a Game Boy Camera cartridge")
+		}
+	}
+
+	/// The image source isn't part of the save state, this is used to keep it when a state is loaded.
+	pub fn swap_camera_image_source(&mut self, other: &mut VirtualCartridge) {
+		if let (MBC::Camera(ref mut a), MBC::Camera(ref mut b)) = (&mut self.mbc, &mut other.mbc) {
+			a.swap_image_source(b);
+		}
+	}
+
+	/// Decode the photos saved in the Game Boy Camera's album.
+	pub fn camera_photos(&self) -> Result<Vec<Bitmap<u32>>, & 'static str> {
+		match self.mbc {
+			MBC::Camera(_) => Ok(camera::saved_photos(&self.ram)),
+			_ => Err("Not a Game Boy Camera cartridge")
+		}
+	}
 }
 
 impl Cartridge for VirtualCartridge {
@@ -288,11 +738,16 @@ impl Cartridge for VirtualCartridge {
 	}
 
 	fn write_byte_rom(&mut self, offset: u16, value: u8) {
-		self.mbc.write_byte_rom(offset, value);
+		// only the MBC6's flash can be changed through the rom
+		if self.mbc.write_byte_rom(offset, value) && self.cart_info.battery {
+			self.battery_generation += 1;
+		}
 	}
 
 	fn write_byte_ram(&mut self, offset: u16, value: u8) {
-		self.mbc.write_byte_ram(&mut self.ram, self.cart_info.ram_size, offset, value);
+		if self.mbc.write_byte_ram(&mut self.ram, self.cart_info.ram_size, offset, value) && self.cart_info.battery {
+			self.battery_generation += 1;
+		}
 	}
 
 	fn get_cart_info(&self) -> &CartInfo {
@@ -376,4 +831,28 @@ impl Cartridge for VirtualCartridge {
 			}
 		}
 	}
+
+	fn rom_bank(&self) -> usize {
+		self.mbc.rom_bank()
+	}
+
+	fn ram_bank(&self) -> usize {
+		self.mbc.ram_bank()
+	}
+
+	fn rumble(&self) -> bool {
+		self.mbc.rumble()
+	}
+
+	fn ir_led(&self) -> bool {
+		self.mbc.ir_led()
+	}
+
+	fn set_tilt(&mut self, x: f32, y: f32) {
+		self.mbc.set_tilt(x, y);
+	}
+
+	fn set_time(&mut self, now: i64) {
+		self.mbc.set_time(now);
+	}
 }
@@ -1,3 +1,4 @@
+use gameboy::cartridge::write_ram;
 use super::MemoryBankController;
 
 #[derive(Serialize, Deserialize)]
@@ -33,15 +34,14 @@ impl<'de> MemoryBankController for NoMBC {
 	}
 
 	#[allow(unused_variables)]
-	fn write_byte_rom(&mut self, offset: u16, value: u8) {
+	fn write_byte_rom(&mut self, offset: u16, value: u8) -> bool {
 		//This isn't a real mbc, so this doesn't do anything
+		false
 	}
 
-	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) {
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
 		let offset: usize = offset as usize;
-		if offset < ram_size {
-			ram[offset] = value;
-		}
+		offset < ram_size && write_ram(ram, offset, value)
 	}
 
 	fn rom_bank(&self) -> usize {
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::error::Error;
+
+use flate2::Crc;
+
+/* Magic numbers at the start of each patch format */
+const IPS_MAGIC: &[u8] = b"PATCH";
+const UPS_MAGIC: &[u8] = b"UPS1";
+const BPS_MAGIC: &[u8] = b"BPS1";
+
+/* An IPS record at this offset ("EOF") ends the patch */
+const IPS_EOF: usize = 0x454F46;
+
+/* UPS and BPS patches end with the crc32 of the source, the target and the rest of the patch */
+const FOOTER_SIZE: usize = 12;
+
+/* The biggest patched rom a UPS or BPS patch can make, game boy roms are at most 8MiB (512 banks) */
+const MAX_TARGET_SIZE: usize = 8 * 1024 * 1024;
+
+/* BPS actions, in the low 2 bits of each action's number */
+const BPS_SOURCE_READ: usize = 0;
+const BPS_TARGET_READ: usize = 1;
+const BPS_SOURCE_COPY: usize = 2;
+const BPS_TARGET_COPY: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+	Ips,
+	Ups,
+	Bps
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+	/// The patch doesn't start with the IPS, UPS or BPS magic number.
+	UnknownFormat,
+	/// The patch ends in the middle of a record.
+	Truncated(PatchFormat),
+	/// The patch reads or writes outside of the source/target (the patch is corrupt).
+	OutOfBounds(PatchFormat),
+	/// The rom isn't the size the patch was made for.
+	SourceSize { expected: usize, actual: usize },
+	/// The rom isn't the one the patch was made for.
+	SourceChecksum { expected: u32, actual: u32 },
+	/// The patched rom isn't what the patch should produce.
+	TargetChecksum { expected: u32, actual: u32 },
+	/// The patch file is corrupt.
+	PatchChecksum { expected: u32, actual: u32 }
+}
+
+impl fmt::Display for PatchError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PatchError::UnknownFormat => write!(f, "Not an IPS, UPS or BPS patch"),
+			PatchError::Truncated(format) => write!(f, "The {:?} patch is truncated", format),
+			PatchError::OutOfBounds(format) => write!(f, "The {:?} patch accesses data outside of the rom", format),
+			PatchError::SourceSize { expected, actual } => write!(f, "The patch is for a rom of {} bytes, the rom is {} bytes", expected, actual),
+			PatchError::SourceChecksum { expected, actual } => write!(f, "The patch is for a different rom (crc32 0x{:08X}, the rom's is 0x{:08X})", expected, actual),
+			PatchError::TargetChecksum { expected, actual } => write!(f, "The patched rom is wrong (crc32 0x{:08X} instead of 0x{:08X})", actual, expected),
+			PatchError::PatchChecksum { expected, actual } => write!(f, "The patch is corrupt (crc32 0x{:08X} instead of 0x{:08X})", actual, expected)
+		}
+	}
+}
+
+impl Error for PatchError {}
+
+/// Apply an IPS, UPS or BPS patch (detected from its magic number) to a rom, returns the patched rom.
+/// This has to be done before the rom is passed to VirtualCartridge::new (or Gameboy::new), since the patch can change the header.
+pub fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Box<[u8]>, PatchError> {
+	if patch.starts_with(IPS_MAGIC) {
+		apply_ips(rom, patch)
+	}
+	else if patch.starts_with(UPS_MAGIC) {
+		apply_ups(rom, patch)
+	}
+	else if patch.starts_with(BPS_MAGIC) {
+		apply_bps(rom, patch)
+	}
+	else {
+		Err(PatchError::UnknownFormat)
+	}
+}
+
+/// IPS patches are a list of records: a 24 bit offset, a 16 bit size and the data written at the offset.
+/// A record with a size of 0 is a run (RLE extension): a 16 bit length and the byte that is repeated.
+/// Records can write past the end of the rom to make it bigger, the list ends with "EOF",
+/// which can be followed by the 24 bit size the rom is truncated to (truncate extension).
+/// All values are big endian.
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Box<[u8]>, PatchError> {
+	let mut reader = PatchReader::new(PatchFormat::Ips, patch, IPS_MAGIC.len());
+	let mut target = Vec::from(rom);
+	loop {
+		let offset = reader.read_be(3)?;
+		if offset == IPS_EOF {
+			break;
+		}
+		let size = reader.read_be(2)?;
+		if size == 0 {
+			let length = reader.read_be(2)?;
+			let value = reader.read_byte()?;
+			ips_write(&mut target, offset, &vec![value; length]);
+		}
+		else {
+			let data = reader.read_slice(size)?;
+			ips_write(&mut target, offset, data);
+		}
+	}
+	if !reader.finished() {
+		let size = reader.read_be(3)?;
+		target.truncate(size);
+	}
+	Ok(target.into_boxed_slice())
+}
+
+fn ips_write(target: &mut Vec<u8>, offset: usize, data: &[u8]) {
+	let end = offset + data.len();
+	if end > target.len() {
+		target.resize(end, 0);
+	}
+	target[offset..end].copy_from_slice(data);
+}
+
+/// UPS patches store the difference between the rom and the patched rom as hunks of xored bytes:
+/// the number of unchanged bytes since the previous hunk, then bytes that are xored with the rom, ending with a 0.
+pub fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Box<[u8]>, PatchError> {
+	let (source_crc, target_crc) = check_footer(PatchFormat::Ups, patch)?;
+	let mut reader = PatchReader::new(PatchFormat::Ups, &patch[..patch.len() - FOOTER_SIZE], UPS_MAGIC.len());
+	let source_size = reader.read_number()?;
+	let target_size = read_target_size(&mut reader)?;
+	check_source(rom, source_size, source_crc)?;
+
+	let out_of_bounds = PatchError::OutOfBounds(PatchFormat::Ups);
+	let mut target = Vec::from(rom);
+	target.resize(target_size, 0);
+	let mut position: usize = 0;
+	while !reader.finished() {
+		position = position.checked_add(reader.read_number()?).ok_or(out_of_bounds.clone())?;
+		loop {
+			let value = reader.read_byte()?;
+			if value == 0 {
+				position = position.checked_add(1).ok_or(out_of_bounds.clone())?;
+				break;
+			}
+			match target.get_mut(position) {
+				Some(byte) => *byte ^= value,
+				None => return Err(out_of_bounds)
+			}
+			position += 1;
+		}
+	}
+	check_target(&target, target_crc)?;
+	Ok(target.into_boxed_slice())
+}
+
+/// BPS patches build the patched rom from actions that copy data from the rom, the patch, or the patched rom itself.
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Box<[u8]>, PatchError> {
+	let (source_crc, target_crc) = check_footer(PatchFormat::Bps, patch)?;
+	let mut reader = PatchReader::new(PatchFormat::Bps, &patch[..patch.len() - FOOTER_SIZE], BPS_MAGIC.len());
+	let source_size = reader.read_number()?;
+	let target_size = read_target_size(&mut reader)?;
+	let metadata_size = reader.read_number()?;
+	reader.read_slice(metadata_size)?;
+	check_source(rom, source_size, source_crc)?;
+
+	let out_of_bounds = PatchError::OutOfBounds(PatchFormat::Bps);
+	let mut target: Vec<u8> = Vec::new();
+	let mut source_offset: usize = 0;
+	let mut target_offset: usize = 0;
+	while !reader.finished() {
+		let action = reader.read_number()?;
+		let length = (action >> 2) + 1;
+		if length > target_size - target.len() {
+			return Err(out_of_bounds);
+		}
+		match action & 3 {
+			BPS_SOURCE_READ => {
+				let start = target.len();
+				let data = rom.get(start..start + length).ok_or(out_of_bounds.clone())?;
+				target.extend_from_slice(data);
+			},
+			BPS_TARGET_READ => target.extend_from_slice(reader.read_slice(length)?),
+			BPS_SOURCE_COPY => {
+				source_offset = relative_offset(source_offset, reader.read_number()?).ok_or(out_of_bounds.clone())?;
+				let data = rom.get(source_offset..source_offset.saturating_add(length)).ok_or(out_of_bounds.clone())?;
+				target.extend_from_slice(data);
+				source_offset += length;
+			},
+			BPS_TARGET_COPY => {
+				target_offset = relative_offset(target_offset, reader.read_number()?).ok_or(out_of_bounds.clone())?;
+				if target_offset >= target.len() {
+					return Err(out_of_bounds);
+				}
+				// the copy can overlap the bytes it writes (to repeat a pattern), so it's done a byte at a time
+				for _ in 0..length {
+					let value = target[target_offset];
+					target.push(value);
+					target_offset += 1;
+				}
+			},
+			_ => unreachable!()
+		}
+	}
+	if target.len() != target_size {
+		return Err(PatchError::Truncated(PatchFormat::Bps));
+	}
+	check_target(&target, target_crc)?;
+	Ok(target.into_boxed_slice())
+}
+
+/// Read the size of the patched rom from a UPS/BPS header, sizes that no rom can have are rejected before anything is allocated.
+fn read_target_size(reader: &mut PatchReader) -> Result<usize, PatchError> {
+	let size = reader.read_number()?;
+	if size > MAX_TARGET_SIZE {
+		return Err(PatchError::OutOfBounds(reader.format));
+	}
+	Ok(size)
+}
+
+/// BPS copy offsets are relative to the end of the last copy, bit 0 is the sign.
+fn relative_offset(offset: usize, value: usize) -> Option<usize> {
+	if value & 1 == 0 {
+		offset.checked_add(value >> 1)
+	}
+	else {
+		offset.checked_sub(value >> 1)
+	}
+}
+
+fn crc32(data: &[u8]) -> u32 {
+	let mut crc = Crc::new();
+	crc.update(data);
+	crc.sum()
+}
+
+/// Check the patch's own checksum, returns the source and target checksums from the footer.
+fn check_footer(format: PatchFormat, patch: &[u8]) -> Result<(u32, u32), PatchError> {
+	if patch.len() < UPS_MAGIC.len() + FOOTER_SIZE {
+		return Err(PatchError::Truncated(format));
+	}
+	let footer = patch.len() - FOOTER_SIZE;
+	let read_u32 = |offset: usize| (0..4).fold(0u32, |value, i| value | ((patch[offset + i] as u32) << (i * 8)));
+	let (source_crc, target_crc, patch_crc) = (read_u32(footer), read_u32(footer + 4), read_u32(footer + 8));
+
+	let actual = crc32(&patch[..footer + 8]);
+	if actual != patch_crc {
+		return Err(PatchError::PatchChecksum { expected: patch_crc, actual: actual });
+	}
+	Ok((source_crc, target_crc))
+}
+
+fn check_source(rom: &[u8], size: usize, crc: u32) -> Result<(), PatchError> {
+	if rom.len() != size {
+		return Err(PatchError::SourceSize { expected: size, actual: rom.len() });
+	}
+	let actual = crc32(rom);
+	if actual != crc {
+		return Err(PatchError::SourceChecksum { expected: crc, actual: actual });
+	}
+	Ok(())
+}
+
+fn check_target(target: &[u8], crc: u32) -> Result<(), PatchError> {
+	let actual = crc32(target);
+	if actual != crc {
+		return Err(PatchError::TargetChecksum { expected: crc, actual: actual });
+	}
+	Ok(())
+}
+
+/// Reads values from a patch, running out of data is an error.
+struct PatchReader<'a> {
+	format: PatchFormat,
+	data: &'a [u8],
+	position: usize
+}
+
+impl<'a> PatchReader<'a> {
+	fn new(format: PatchFormat, data: &'a [u8], position: usize) -> PatchReader<'a> {
+		PatchReader {
+			format: format,
+			data: data,
+			position: position
+		}
+	}
+
+	fn finished(&self) -> bool {
+		self.position >= self.data.len()
+	}
+
+	fn read_slice(&mut self, length: usize) -> Result<&'a [u8], PatchError> {
+		if length > self.data.len() - self.position {
+			return Err(PatchError::Truncated(self.format));
+		}
+		let slice = &self.data[self.position..self.position + length];
+		self.position += length;
+		Ok(slice)
+	}
+
+	fn read_byte(&mut self) -> Result<u8, PatchError> {
+		self.read_slice(1).map(|slice| slice[0])
+	}
+
+	/// A big endian number (IPS)
+	fn read_be(&mut self, bytes: usize) -> Result<usize, PatchError> {
+		let slice = self.read_slice(bytes)?;
+		Ok(slice.iter().fold(0, |value, &byte| (value << 8) | byte as usize))
+	}
+
+	/// A variable length number (UPS/BPS): 7 bits per byte, least significant first, the last byte has bit 7 set.
+	/// Each byte after the first also adds 1 << (7 * n), so every number has a single encoding.
+	fn read_number(&mut self) -> Result<usize, PatchError> {
+		let mut value: usize = 0;
+		let mut shift: usize = 1;
+		loop {
+			let byte = self.read_byte()?;
+			let bits = ((byte & 0x7F) as usize).checked_mul(shift);
+			value = bits.and_then(|bits| value.checked_add(bits)).ok_or(PatchError::OutOfBounds(self.format))?;
+			if byte & 0x80 != 0 {
+				return Ok(value);
+			}
+			shift = shift.checked_mul(0x80).ok_or(PatchError::OutOfBounds(self.format))?;
+			value = value.checked_add(shift).ok_or(PatchError::OutOfBounds(self.format))?;
+		}
+	}
+}
@@ -0,0 +1,434 @@
+use gameboy::cartridge::{ROM_BANK_SIZE, write_ram};
+use gameboy::clock::RtcTimestamp;
+use super::MemoryBankController;
+
+/// The TAMA6 microcontroller has 32 bytes of battery backed ram.
+pub const TAMA5_RAM_SIZE: usize = 32;
+
+/* Registers, selected by writing to 0xA001, and written (4 bits at a time) through 0xA000 */
+const REG_ROM_BANK_LOW: u8 = 0x0;
+const REG_ROM_BANK_HIGH: u8 = 0x1;
+const REG_DATA_LOW: u8 = 0x4;
+const REG_DATA_HIGH: u8 = 0x5;
+const REG_COMMAND: u8 = 0x6;      /* bits 1-3: command, bit 0: address bit 4 */
+const REG_ADDRESS_LOW: u8 = 0x7;  /* writing the low address bits executes the command */
+const REG_READY: u8 = 0xA;
+const REG_RESULT_LOW: u8 = 0xC;
+const REG_RESULT_HIGH: u8 = 0xD;
+
+/* Commands (bits 1-3 of REG_COMMAND) */
+const CMD_RAM_WRITE: u8 = 0x0;
+const CMD_RAM_READ: u8 = 0x1;
+const CMD_RTC: u8 = 0x2;
+const CMD_RTC_PAGE: u8 = 0x4;     /* access a nibble of the timer/alarm page (see TAMA5) */
+
+/* Arguments of CMD_RTC (the address) */
+const RTC_DISABLE_TIMER: u8 = 0x00;
+const RTC_ENABLE_TIMER: u8 = 0x01;
+const RTC_MINUTE_WRITE: u8 = 0x04;
+const RTC_HOUR_WRITE: u8 = 0x05;
+const RTC_MINUTE_READ: u8 = 0x06;
+const RTC_HOUR_READ: u8 = 0x07;
+const RTC_DISABLE_ALARM: u8 = 0x10;
+const RTC_ENABLE_ALARM: u8 = 0x11;
+
+/* Nibbles of the timer page (BCD), the alarm page only uses the minutes and hours */
+const PAGE_SECOND_1: u8 = 0x0;
+const PAGE_SECOND_10: u8 = 0x1;
+const PAGE_MINUTE_1: u8 = 0x2;
+const PAGE_MINUTE_10: u8 = 0x3;
+const PAGE_HOUR_1: u8 = 0x4;
+const PAGE_HOUR_10: u8 = 0x5;
+const PAGE_WEEK: u8 = 0x6;
+const PAGE_DAY_1: u8 = 0x7;
+const PAGE_DAY_10: u8 = 0x8;
+const PAGE_MONTH_1: u8 = 0x9;
+const PAGE_MONTH_10: u8 = 0xA;
+const PAGE_YEAR_1: u8 = 0xB;
+const PAGE_YEAR_10: u8 = 0xC;
+const PAGE_CONTROL: u8 = 0xD;     /* bit 3: timer enabled, bit 2: alarm enabled, bit 0: the alarm went off */
+
+/* Battery save footer: second, minute, hour, week, day, month, year, alarm minute, alarm hour, control,
+ * and the unix timestamp of the save (64 bits, little endian) */
+const FOOTER_SIZE: usize = 18;
+
+/// The calendar kept by the TAMA6's rtc (years are 00 - 99, every 4th one is a leap year).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+struct DateTime {
+	second: u8,
+	minute: u8,
+	hour: u8,
+	week: u8,   /* day of the week (0 - 6) */
+	day: u8,    /* 1 - 31 */
+	month: u8,  /* 1 - 12 */
+	year: u8    /* 0 - 99 */
+}
+
+impl DateTime {
+	fn new() -> DateTime {
+		DateTime {
+			second: 0,
+			minute: 0,
+			hour: 0,
+			week: 0,
+			day: 1,
+			month: 1,
+			year: 0
+		}
+	}
+
+	fn days_in_month(&self) -> u8 {
+		match self.month {
+			2 if self.year % 4 == 0 => 29,
+			2 => 28,
+			4 | 6 | 9 | 11 => 30,
+			_ => 31
+		}
+	}
+
+	fn advance_day(&mut self) {
+		self.week = (self.week + 1) % 7;
+		self.day += 1;
+		if self.day > self.days_in_month() {
+			self.day = 1;
+			self.month += 1;
+			if self.month > 12 {
+				self.month = 1;
+				self.year = (self.year + 1) % 100;
+			}
+		}
+	}
+
+	fn advance(&mut self, seconds: u64) {
+		let seconds = seconds + self.second as u64 + (self.minute as u64 * 60) + (self.hour as u64 * 3600);
+		for _ in 0..(seconds / 86400) {
+			self.advance_day();
+		}
+		let seconds = seconds % 86400;
+		self.hour = (seconds / 3600) as u8;
+		self.minute = ((seconds / 60) % 60) as u8;
+		self.second = (seconds % 60) as u8;
+	}
+
+	/// Get one of the BCD digits of the timer page.
+	fn nibble(&self, index: u8) -> u8 {
+		match index {
+			PAGE_SECOND_1 => self.second % 10,
+			PAGE_SECOND_10 => self.second / 10,
+			PAGE_MINUTE_1 => self.minute % 10,
+			PAGE_MINUTE_10 => self.minute / 10,
+			PAGE_HOUR_1 => self.hour % 10,
+			PAGE_HOUR_10 => self.hour / 10,
+			PAGE_WEEK => self.week,
+			PAGE_DAY_1 => self.day % 10,
+			PAGE_DAY_10 => self.day / 10,
+			PAGE_MONTH_1 => self.month % 10,
+			PAGE_MONTH_10 => self.month / 10,
+			PAGE_YEAR_1 => self.year % 10,
+			PAGE_YEAR_10 => self.year / 10,
+			_ => 0
+		}
+	}
+
+	/// Set one of the BCD digits of the timer page.
+	fn set_nibble(&mut self, index: u8, value: u8) {
+		fn ones(field: &mut u8, value: u8) { *field = (*field / 10) * 10 + (value % 10); }
+		fn tens(field: &mut u8, value: u8) { *field = (value % 10) * 10 + (*field % 10); }
+		match index {
+			PAGE_SECOND_1 => ones(&mut self.second, value),
+			PAGE_SECOND_10 => tens(&mut self.second, value),
+			PAGE_MINUTE_1 => ones(&mut self.minute, value),
+			PAGE_MINUTE_10 => tens(&mut self.minute, value),
+			PAGE_HOUR_1 => ones(&mut self.hour, value),
+			PAGE_HOUR_10 => tens(&mut self.hour, value),
+			PAGE_WEEK => self.week = value % 7,
+			PAGE_DAY_1 => ones(&mut self.day, value),
+			PAGE_DAY_10 => tens(&mut self.day, value),
+			PAGE_MONTH_1 => ones(&mut self.month, value),
+			PAGE_MONTH_10 => tens(&mut self.month, value),
+			PAGE_YEAR_1 => ones(&mut self.year, value),
+			PAGE_YEAR_10 => tens(&mut self.year, value),
+			_ => {}
+		}
+	}
+}
+
+fn to_bcd(value: u8) -> u8 {
+	((value / 10) << 4) | (value % 10)
+}
+
+fn from_bcd(value: u8) -> u8 {
+	(value >> 4) * 10 + (value & 0x0F)
+}
+
+/// The TAMA6's real time clock, with an alarm that goes off once a day at a set hour and minute.
+#[derive(Serialize, Deserialize)]
+pub struct Tama6Rtc {
+	last: RtcTimestamp, /* the last time the clock was updated */
+	time: DateTime,
+	alarm: (u8, u8),    /* hour, minute */
+	timer_enable: bool,
+	alarm_enable: bool,
+	alarm_fired: bool
+}
+
+impl Tama6Rtc {
+	pub fn new() -> Tama6Rtc {
+		Tama6Rtc {
+			last: RtcTimestamp::new(),
+			time: DateTime::new(),
+			alarm: (0, 0),
+			timer_enable: true,
+			alarm_enable: false,
+			alarm_fired: false
+		}
+	}
+
+	/// Advance the clock by the time that has passed since the last update (now is a unix timestamp from the cartridge's clock),
+	/// and check if the alarm went off.
+	fn update(&mut self, now: i64) {
+		let delta = self.last.update(now);
+		if delta == 0 || !self.timer_enable {
+			return;
+		}
+
+		let before = self.time.hour as u64 * 3600 + self.time.minute as u64 * 60 + self.time.second as u64;
+		self.time.advance(delta);
+		if self.alarm_enable {
+			// the alarm went off if the time of day it's set to was passed (possibly on a later day)
+			let alarm = self.alarm.0 as u64 * 3600 + self.alarm.1 as u64 * 60;
+			let next_alarm = if alarm > before { alarm } else { alarm + 86400 };
+			if before + delta >= next_alarm {
+				self.alarm_fired = true;
+			}
+		}
+	}
+
+	fn control(&self) -> u8 {
+		((self.timer_enable as u8) << 3) | ((self.alarm_enable as u8) << 2) | (self.alarm_fired as u8)
+	}
+
+	fn set_control(&mut self, value: u8) {
+		self.timer_enable = value & 0x08 != 0;
+		self.alarm_enable = value & 0x04 != 0;
+		self.alarm_fired = value & 0x01 != 0;
+	}
+
+	/// Execute a CMD_RTC command, returns the value read by the *_READ commands.
+	fn command(&mut self, command: u8, data: u8) -> Option<u8> {
+		match command {
+			RTC_DISABLE_TIMER => self.timer_enable = false,
+			RTC_ENABLE_TIMER => {
+				self.timer_enable = true;
+				self.time.second = 0;
+			},
+			RTC_MINUTE_WRITE => self.time.minute = from_bcd(data) % 60,
+			RTC_HOUR_WRITE => self.time.hour = from_bcd(data) % 24,
+			RTC_MINUTE_READ => return Some(to_bcd(self.time.minute)),
+			RTC_HOUR_READ => return Some(to_bcd(self.time.hour)),
+			RTC_DISABLE_ALARM => {
+				self.alarm_enable = false;
+				self.alarm_fired = false;
+			},
+			RTC_ENABLE_ALARM => self.alarm_enable = true,
+			_ => {}
+		}
+		None
+	}
+
+	/// Read a nibble from the timer page, or the alarm page if bit 1 of page is set.
+	fn read_page(&self, page: u8, index: u8) -> u8 {
+		if index == PAGE_CONTROL {
+			self.control()
+		}
+		else if page & 0x02 == 0 {
+			self.time.nibble(index)
+		}
+		else {
+			match index {
+				PAGE_MINUTE_1 => self.alarm.1 % 10,
+				PAGE_MINUTE_10 => self.alarm.1 / 10,
+				PAGE_HOUR_1 => self.alarm.0 % 10,
+				PAGE_HOUR_10 => self.alarm.0 / 10,
+				_ => 0
+			}
+		}
+	}
+
+	/// Write a nibble to the timer page, or the alarm page if bit 1 of page is set.
+	fn write_page(&mut self, page: u8, index: u8, value: u8) {
+		if index == PAGE_CONTROL {
+			self.set_control(value);
+		}
+		else if page & 0x02 == 0 {
+			self.time.set_nibble(index, value);
+		}
+		else {
+			match index {
+				PAGE_MINUTE_1 => self.alarm.1 = (self.alarm.1 / 10) * 10 + (value % 10),
+				PAGE_MINUTE_10 => self.alarm.1 = (value % 10) * 10 + (self.alarm.1 % 10),
+				PAGE_HOUR_1 => self.alarm.0 = (self.alarm.0 / 10) * 10 + (value % 10),
+				PAGE_HOUR_10 => self.alarm.0 = (value % 10) * 10 + (self.alarm.0 % 10),
+				_ => {}
+			}
+		}
+	}
+
+	fn save(&self) -> Vec<u8> {
+		let t = &self.time;
+		let mut footer = vec![t.second, t.minute, t.hour, t.week, t.day, t.month, t.year, self.alarm.1, self.alarm.0, self.control()];
+		footer.extend_from_slice(&self.last.save());
+		footer
+	}
+
+	/// Restore the clock from a battery save footer, the time that has passed since the save is applied on the next update.
+	fn load(&mut self, footer: &[u8]) {
+		if footer.len() < FOOTER_SIZE {
+			return;
+		}
+		self.time = DateTime {
+			second: footer[0] % 60,
+			minute: footer[1] % 60,
+			hour: footer[2] % 24,
+			week: footer[3] % 7,
+			day: footer[4].max(1).min(31),
+			month: footer[5].max(1).min(12),
+			year: footer[6] % 100
+		};
+		self.alarm = (footer[8] % 24, footer[7] % 60);
+		self.set_control(footer[9]);
+		self.last.load(&footer[10..FOOTER_SIZE]);
+	}
+}
+
+/// The TAMA5 is only accessed through 0xA000 (data) and 0xA001 (register select), writes to 0x0000 - 0x7FFF are ignored.
+/// Each register holds 4 bits, the ram and rtc live in the TAMA6 microcontroller, which executes a command
+/// when the low address register is written:
+/// command 0: write the data registers to ram (5 bit address)
+/// command 1: read ram (the result registers return the byte at the address)
+/// command 2: rtc commands (the address selects the command, the hour/minute writes use the data registers as BCD)
+/// command 4: access a nibble of the rtc's timer/alarm pages (data low: index, data high: value written,
+///            address low: bit 1 selects the alarm page, bit 0 reads the nibble instead of writing it)
+#[derive(Serialize, Deserialize)]
+pub struct TAMA5 {
+	registers: [u8; 16],
+	selected: u8,
+	result: u8,
+	rtc: Tama6Rtc
+}
+
+impl TAMA5 {
+	pub fn new() -> TAMA5 {
+		TAMA5 {
+			registers: [0; 16],
+			selected: 0,
+			result: 0,
+			rtc: Tama6Rtc::new()
+		}
+	}
+
+	fn rom_bank_number(&self) -> usize {
+		(self.registers[REG_ROM_BANK_LOW as usize] | ((self.registers[REG_ROM_BANK_HIGH as usize] & 1) << 4)) as usize
+	}
+
+	/// Returns true if the command changed the ram or the rtc (which are kept in battery saves).
+	fn execute(&mut self, ram: &mut [u8]) -> bool {
+		let command = self.registers[REG_COMMAND as usize] >> 1;
+		let address = ((self.registers[REG_COMMAND as usize] & 1) << 4) | self.registers[REG_ADDRESS_LOW as usize];
+		let data = (self.registers[REG_DATA_HIGH as usize] << 4) | self.registers[REG_DATA_LOW as usize];
+		let rtc = self.rtc.save();
+		match command {
+			CMD_RAM_WRITE => return (address as usize) < ram.len() && write_ram(ram, address as usize, data),
+			CMD_RAM_READ => {
+				self.result = if (address as usize) < ram.len() { ram[address as usize] } else { 0xFF };
+			},
+			CMD_RTC => {
+				if let Some(value) = self.rtc.command(address, data) {
+					self.result = value;
+				}
+			},
+			CMD_RTC_PAGE => {
+				let page = self.registers[REG_ADDRESS_LOW as usize];
+				let index = self.registers[REG_DATA_LOW as usize];
+				match page & 0x01 {
+					0 => self.rtc.write_page(page, index, self.registers[REG_DATA_HIGH as usize]),
+					_ => self.result = self.rtc.read_page(page, index)
+				}
+			},
+			_ => {}
+		}
+		self.rtc.save() != rtc
+	}
+}
+
+impl MemoryBankController for TAMA5 {
+	fn read_byte_rom(&self, rom: &Box<[u8]>, rom_size: usize, offset: u16) -> u8 {
+		let address: usize = match offset {
+			0x0000...0x3FFF => offset as usize,
+			0x4000...0x7FFF => (offset - 0x4000) as usize + (ROM_BANK_SIZE * self.rom_bank_number()),
+			_ => panic!("Invalid parameters for read_byte_rom: address must be in the range 0x0000...0x7FFF"),
+		};
+		if address < rom_size {
+			rom[address]
+		}
+		else {
+			0xFF
+		}
+	}
+
+	#[allow(unused_variables)]
+	fn read_byte_ram(&self, ram: &Box<[u8]>, ram_size: usize, offset: u16) -> u8 {
+		if offset & 1 == 1 {
+			return 0xFF;
+		}
+		match self.selected {
+			REG_READY => 0xF1,
+			REG_RESULT_LOW => 0xF0 | (self.result & 0x0F),
+			REG_RESULT_HIGH => 0xF0 | (self.result >> 4),
+			_ => 0xF1
+		}
+	}
+
+	#[allow(unused_variables)]
+	fn write_byte_rom(&mut self, address: u16, value: u8) -> bool {
+		false
+	}
+
+	fn write_byte_ram(&mut self, ram: &mut Box<[u8]>, ram_size: usize, offset: u16, value: u8) -> bool {
+		if offset & 1 == 1 {
+			self.selected = value & 0x0F;
+			false
+		}
+		else {
+			self.registers[self.selected as usize] = value & 0x0F;
+			if self.selected == REG_ADDRESS_LOW {
+				let size = ram_size.min(ram.len());
+				self.execute(&mut ram[..size])
+			}
+			else {
+				false
+			}
+		}
+	}
+
+	fn rom_bank(&self) -> usize {
+		self.rom_bank_number()
+	}
+
+	fn ram_bank(&self) -> usize {
+		0 //the ram isn't banked
+	}
+
+	fn battery_footer(&self) -> Vec<u8> {
+		self.rtc.save()
+	}
+
+	fn load_battery_footer(&mut self, footer: &[u8]) {
+		self.rtc.load(footer);
+	}
+
+	fn set_time(&mut self, now: i64) {
+		self.rtc.update(now);
+	}
+}
@@ -0,0 +1,96 @@
+use time;
+
+/// The number of t cycles the cpu executes per second (in normal speed mode).
+const CYCLES_PER_SECOND: usize = 4_194_304;
+
+/// Saved in place of the timestamp of an rtc that was never updated, it's before any time a clock can return.
+const NEVER_UPDATED: i64 = ::std::i64::MIN;
+
+/// Where cartridge rtcs (MBC3, HuC3, TAMA5) get the current time from.
+/// The gameboy passes the time to the cartridge before it's accessed, so the rtc only advances when the clock does.
+pub trait Clock: Send {
+	/// The current time as a unix timestamp (in seconds), cycle_counter is the number of cycles the cpu has executed,
+	/// counted at the normal speed rate (cycles executed in cgb double speed mode count as half a cycle).
+	/// i64::MIN is reserved, it marks rtcs that were never updated in battery saves.
+	fn now(&self, cycle_counter: usize) -> i64;
+}
+
+/// The real time, the rtc keeps running while the emulator is closed or paused (like a real cartridge).
+pub struct WallClock;
+
+impl Clock for WallClock {
+	#[allow(unused_variables)]
+	fn now(&self, cycle_counter: usize) -> i64 {
+		time::now_utc().to_timespec().sec
+	}
+}
+
+/// Time that advances with the emulated cycles, starting at a fixed date. The rtc stops while emulation is paused,
+/// runs faster when fast forwarding, and is the same every time a game is replayed.
+/// It keeps the same pace in cgb double speed mode, since the cycle counter it gets runs at the normal speed rate.
+pub struct CycleClock {
+	start: i64
+}
+
+impl CycleClock {
+	/// start is the unix timestamp the clock reads when the cycle counter is 0.
+	pub fn new(start: i64) -> CycleClock {
+		CycleClock {
+			start: start.max(NEVER_UPDATED + 1)
+		}
+	}
+}
+
+impl Clock for CycleClock {
+	fn now(&self, cycle_counter: usize) -> i64 {
+		self.start + (cycle_counter / CYCLES_PER_SECOND) as i64
+	}
+}
+
+/// The clock used if none is set (and after deserializing a save state).
+pub fn default_clock() -> Box<Clock> {
+	Box::new(WallClock)
+}
+
+/// When a cartridge rtc was last updated, the rtc counts the seconds that pass between updates.
+/// It's kept in battery saves, so the rtc catches up with the time that passed while the game wasn't running.
+#[derive(Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize)]
+pub struct RtcTimestamp {
+	last: Option<i64> /* unix timestamp, None until the cartridge's clock sets it */
+}
+
+impl RtcTimestamp {
+	pub fn new() -> RtcTimestamp {
+		RtcTimestamp {
+			last: None
+		}
+	}
+
+	/// Move to now (a unix timestamp from the cartridge's clock), and return the seconds that passed since the last update.
+	/// No time passes on the first update, or if the clock went backwards (or was replaced): the rtc continues from the new time.
+	pub fn update(&mut self, now: i64) -> u64 {
+		let delta = now.saturating_sub(self.last.unwrap_or(now));
+		self.last = Some(now);
+		if delta > 0 { delta as u64 } else { 0 }
+	}
+
+	/// The timestamp as 8 little endian bytes, for battery saves.
+	pub fn save(&self) -> [u8; 8] {
+		let last = self.last.unwrap_or(NEVER_UPDATED);
+		let mut bytes = [0; 8];
+		for (i, byte) in bytes.iter_mut().enumerate() {
+			*byte = (last >> (i * 8)) as u8;
+		}
+		bytes
+	}
+
+	/// Restore the timestamp from the bytes returned by save (the 4 byte timestamps some emulators save are accepted too).
+	pub fn load(&mut self, bytes: &[u8]) {
+		let mut last: i64 = 0;
+		for (i, byte) in bytes.iter().take(8).enumerate() {
+			last |= (*byte as i64) << (i * 8);
+		}
+		self.last = if last == NEVER_UPDATED { None } else { Some(last) };
+	}
+}
@@ -107,25 +107,19 @@ impl InterruptEnable {
 
 /// Passed to components that need to request interrupts, but don't have access to the global emulator state.
 pub struct InterruptLine<'a> {
-	interrupt_flag: &'a mut InterruptFlag,
-	halt: &'a mut bool,
-	stop: &'a mut bool
+	interrupt_flag: &'a mut InterruptFlag
 }
 
 impl<'a> InterruptLine<'a> {
-	pub fn new(interrupt_flag: &'a mut InterruptFlag, halt: &'a mut bool, stop: &'a mut bool) -> InterruptLine<'a> {
+	pub fn new(interrupt_flag: &'a mut InterruptFlag) -> InterruptLine<'a> {
 		InterruptLine {
-			interrupt_flag: interrupt_flag,
-			halt: halt,
-			stop: stop
+			interrupt_flag: interrupt_flag
 		}
 	}
 
 	/// Request an interrupt
-	/// Interrupts will wake the cpu if it is stopped or halted.
+	/// If the cpu is halted, it will wake up on the next interrupt check if the interrupt is also enabled in IE.
 	pub fn request_interrupt(&mut self, int: Interrupt) {
 		self.interrupt_flag.request_interrupt(int);
-		*self.halt = false;
-		*self.stop = false;
 	}
 }
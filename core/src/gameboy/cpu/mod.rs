@@ -12,7 +12,7 @@ pub const SUBTRACTION_FLAG_MASK: u8 = 1 << 6;
 pub const HALF_CARRY_FLAG_MASK: u8 = 1 << 5;
 pub const CARRY_FLAG_MASK: u8 = 1 << 4;
 
-const HRAM_SIZE: usize = 127;
+pub const HRAM_SIZE: usize = 127;
 
 ///On the DMG/CGB the EI instruction, the value of ime isn't changed until after the next instruction,
 ///I assume this is because of instruction pipelining, and the next instruction has been fetched before
@@ -27,9 +27,18 @@ pub struct CPU {
 	pub interrupt_enable: InterruptEnable, //Interrupt Enable Register - $FFFF
 	pub stop: bool,
 	pub halt: bool,
+	/// Set when HALT triggers the halt bug, the next opcode fetch doesn't increment pc.
+	pub halt_bug: bool,
+	/// Set when the cpu executes an illegal opcode, it stops executing instructions and ignores interrupts until it is reset.
+	pub locked: bool,
 	pub hram: Box<[u8]>,
 	pub double_speed_mode: bool,
+	/// KEY1 bit 0: when set, the next STOP instruction switches between normal and double speed (cgb only)
+	pub prepare_speed_switch: bool,
 	pub cycle_counter: usize,
+	/// Counts time in cycles of the double speed clock (8MiHz) in both speed modes,
+	/// unlike cycle_counter, which runs twice as fast in double speed mode.
+	pub time_counter: usize,
 }
 
 impl CPU {
@@ -42,9 +51,13 @@ impl CPU {
 			interrupt_enable: InterruptEnable::new(),
 			stop: false,
 			halt: false,
+			halt_bug: false,
+			locked: false,
 			hram: Box::new([0; HRAM_SIZE]),
 			double_speed_mode: false,
-			cycle_counter: 0
+			prepare_speed_switch: false,
+			cycle_counter: 0,
+			time_counter: 0
 		}
 	}
 
@@ -59,7 +72,49 @@ impl CPU {
 		self.interrupt_enable.reset();
 		self.stop = false;
 		self.halt = false;
+		self.halt_bug = false;
+		self.locked = false;
 		self.double_speed_mode = false;
+		self.prepare_speed_switch = false;
+	}
+
+	/// Reset the cpu to the state it is in when the gameboy is powered on,
+	/// before the boot rom has been executed.
+	pub fn power_on(&mut self) {
+		self.registers.init_power_on();
+		self.ime = false;
+		self.next_ime_state = false;
+		self.interrupt_flag.write(0);
+		self.interrupt_enable.reset();
+		self.stop = false;
+		self.halt = false;
+		self.halt_bug = false;
+		self.locked = false;
+		self.double_speed_mode = false;
+		self.prepare_speed_switch = false;
+	}
+
+	/// Count the t cycles the cpu has executed.
+	pub fn count_cycles(&mut self, t_cycles: usize) {
+		self.cycle_counter += t_cycles;
+		self.time_counter += if self.double_speed_mode { t_cycles } else { t_cycles * 2 };
+	}
+
+	/// The number of cycles that would have been executed in the same amount of time in normal speed mode.
+	pub fn normal_speed_cycles(&self) -> usize {
+		self.time_counter / 2
+	}
+
+	/// Read from the KEY1 register ($FF4D), cgb only.
+	/// Bit 7 is the current speed (1 = double speed), bit 0 is the prepare speed switch flag, the other bits read as 1.
+	pub fn read_key1(&self) -> u8 {
+		0x7E | ((self.double_speed_mode as u8) << 7) | (self.prepare_speed_switch as u8)
+	}
+
+	/// Write to the KEY1 register ($FF4D), cgb only.
+	/// Only bit 0 (prepare speed switch) is writable.
+	pub fn write_key1(&mut self, value: u8) {
+		self.prepare_speed_switch = value & 1 == 1;
 	}
 
 	pub fn read_byte_hram(&self, offset: u16) -> u8 {
@@ -53,6 +53,12 @@ impl Registers {
 		regs
 	}
 
+	///Sets the values of the registers to what they are when the gameboy is powered on
+	///(before the boot rom is executed)
+	pub fn init_power_on(&mut self) {
+		*self = Default::default();
+	}
+
 	///Sets the values of the registers to what they would be
 	///at the end of the dmg bootrom
 	#[allow(dead_code)]
@@ -72,7 +78,6 @@ impl Registers {
 	///Sets the values of the registers to what they would be
 	///at the end of the cgb bootrom
 	pub fn init_cgb(&mut self) {
-		//TODO: double check to actual values at the end of cgb bootrom
 		self.a = 0x11;
 		self.f = 0x80;
 		self.b = 0x00;
@@ -4,9 +4,10 @@ use gameboy::Gameboy;
 use gameboy::cpu::registers::{ Registers, Register, RegisterPair };
 use gameboy::mmu::Mmu;
 use gameboy::assembly;
+use gameboy::instructions::opcodes::{self, OpcodeInfo, Operand, MemoryAccess, Mnemonic};
 use gameboy::ppu::Bitmap;
-use gameboy::cartridge::Cartridge;
 use gameboy::ppu::PPU;
+use gameboy::scheduler::HardwareScheduler;
 
 type BreakpointCallback = FnMut(Breakpoint) + Send;
 
@@ -150,6 +151,7 @@ impl DebuggerInterface for Gameboy {
 		self.interrupt_service_routine();
 		let result = self.breakpoint_lookahead();
 		self.execute();
+		self.sync_hardware();
 		result
 	}
 
@@ -188,9 +190,11 @@ impl DebuggerInterface for Gameboy {
 			//     starting at pc= 0x1000.  If there is a breakpoint at 0x1001, which
 			//     is in the middle of the instruction, should it be hit when the
 			//     instruction hits?
-			let next_opcode = self.read_byte(self.cpu.registers.pc);
+			let pc = self.cpu.registers.pc;
+			let instruction = [self.read_byte(pc), self.read_byte(pc.wrapping_add(1))];
+			let info = opcodes::lookup(&instruction).unwrap();
 
-			let execute = Breakpoint::new(self.cpu.registers.pc, AccessType::Execute);
+			let execute = Breakpoint::new(pc, AccessType::Execute);
 			if let Ok(index) = self.debugger.breakpoints.binary_search(&execute) {
 				//there is a breakpoint on the next instruction
 				return Some(self.debugger.breakpoints[index]);
@@ -198,178 +202,27 @@ impl DebuggerInterface for Gameboy {
 
 			//jump: check if the next instruction is JR, JP, CALL, RET, or RST,
 			//then look at it's destination (for RET look at stack)
-			const JR:   [u8;5] = [0x18, 0x20, 0x28, 0x30, 0x38];
-			const JP:   [u8;6] = [0xC2, 0xC3, 0xCA, 0xD2, 0xDA, 0xE9];
-			const CALL: [u8;5] = [0xC4, 0xCC, 0xCD, 0xD4, 0xDC];
-			const RET:  [u8;6] = [0xC0, 0xC8, 0xC9, 0xD0, 0xD8, 0xD9];
-			const RST:  [u8;8] = [0xC7, 0xCF, 0xD7, 0xDF, 0xE7, 0xEF, 0xF7, 0xFF];
-			const JUMPS:[u8;30] = [0x18, 0x20, 0x28, 0x30, 0x38, 0xC0, 0xC2, 0xC3,
-			                       0xC4, 0xC7, 0xC8, 0xC9, 0xCA, 0xCC, 0xCD, 0xCF,
-			                       0xD0, 0xD2, 0xD4, 0xD7, 0xD8, 0xD9, 0xDA, 0xDC,
-			                       0xDF, 0xE7, 0xE9, 0xEF, 0xF7, 0xFF];
-			if let Ok(_) = JUMPS.binary_search(&next_opcode) {
-				//the next instruction is some sort of jump, look at where it points.
-				let address =
-				if JR.contains(&next_opcode) {
-					//next instruction is jr, so the address is relative (signed byte offset)
-					let pc = self.cpu.registers.pc;
-					let offset = self.read_byte(pc + 1);
-					((pc as i32) + ((offset as i8) as i32)) as u16
-				}
-				else if JP.contains(&next_opcode) || CALL.contains(&next_opcode) {
-					//next instruction is jp or call (1 byte opcode followed by 2 byte address)
-					let pc = self.cpu.registers.pc;
-					let high = self.read_byte(pc + 2) as u16;
-					let low = self.read_byte(pc + 1) as u16;
-					(high << 8) | low
-				}
-				else if RET.contains(&next_opcode) {
-					//next instruction is ret
-					//the return address is at the top of the stack
-					let sp = self.cpu.registers.sp;
-					let high = self.read_byte(sp + 1) as u16;
-					let low = self.read_byte(sp) as u16;
-					(high << 8) | low
-				}
-				else if RST.contains(&next_opcode) {
-					//next instrcution is rst
-					//look up the address
-					match next_opcode {
-						0xC7 => 0,
-						0xCF => 0x8,
-						0xD7 => 0x10,
-						0xDF => 0x18,
-						0xE7 => 0x20,
-						0xEF => 0x2F,
-						0xF7 => 0x30,
-						0xFF => 0x48,
-						_ => panic!()
-					}
-				}
-				else {
-					panic!("oops");
-				};
-
+			if let Some(address) = jump_target(self, info) {
 				let breakpoint = Breakpoint::new(address, AccessType::Jump);
 				if let Ok(index) = self.debugger.breakpoints.binary_search(&breakpoint) {
-					//there is a breakpoint on the next instruction
 					return Some(self.debugger.breakpoints[index]);
 				}
 			}
 
-			const READ_AT_BC: u8 = 0x0A;
-			const READ_AT_DE: u8 = 0x1A;
-			const READ_IO_A8: u8 = 0xF0;
-			const READ_IO_C: u8 = 0xF2; //read from 0xFF00 + C
-			const READ_A16: u8 = 0xFA;
-			const READ_AT_HL: [u8;20] =
-			[0x2A, 0x34, 0x35, 0x3A, 0x46, 0x4E, 0x56, 0x5E, 0x66, 0x6E, 0x7E, 0x86, 0x8E, 0x96, 0x9E,
-			0xA6, 0xAE, 0xB6, 0xBE, 0xE9];
-			const READ_AT_HL_EXTENDED: [u8;16] =
-			[0x06, 0x0E, 0x16, 0x1E, 0x26, 0x2E, 0x36, 0x3E, 0x46, 0x4E, 0x56, 0x5E, 0x66, 0x6E, 0x76, 0x7E];
-
-			const READS: [u8;25] = [READ_AT_BC, READ_AT_DE, 0x2A, 0x34, 0x35, 0x3A, 0x46, 0x4E, 0x56, 0x5E, 0x66, 0x6E, 0x7E, 0x86, 0x8E, 0x96, 0x9E,
-			0xA6, 0xAE, 0xB6, 0xBE, 0xE9, READ_IO_A8, READ_IO_C, READ_A16];
-
-			let mut address: Option<u16> = None;
-			if let Ok(_) = READS.binary_search(&next_opcode) {
-				if next_opcode == READ_AT_BC {
-					let b = self.cpu.registers.b as u16;
-					let c = self.cpu.registers.c as u16;
-					address = Some((b << 8) | c);
-				}
-				else if next_opcode == READ_AT_DE {
-					let d = self.cpu.registers.d as u16;
-					let e = self.cpu.registers.e as u16;
-					address = Some((d << 8) | e);
-				}
-				else if next_opcode == READ_IO_A8 {
-					address = Some(0xFF00 + (self.read_byte(self.cpu.registers.pc + 1) as u16));
-				}
-				else if next_opcode == READ_IO_C {
-					address = Some(0xFF00 + (self.cpu.registers.c as u16));
-				}
-				else if next_opcode == READ_A16 {
-					let pc = self.cpu.registers.pc + 1;
-					address = Some((self.read_byte(pc) as u16) | ((self.read_byte(pc + 1) as u16) << 8));
-				}
-				else if let Ok(_) = READ_AT_HL.binary_search(&next_opcode) {
-					let h = self.cpu.registers.h as u16;
-					let l = self.cpu.registers.l as u16;
-					address = Some((h << 8) | l);
-				}
-				else {
-					panic!("oops");
-				}
-			}
-			else if next_opcode == 0xCB {
-				let next_opcode = self.read_byte(self.cpu.registers.pc + 1);
-				if let Ok(_) = READ_AT_HL_EXTENDED.binary_search(&next_opcode) {
-					let h = self.cpu.registers.h as u16;
-					let l = self.cpu.registers.l as u16;
-					address = Some((h << 8) | l);
-				}
-			}
-			if let Some(addr) = address {
-				let breakpoint = Breakpoint::new(addr, AccessType::Read);
-				if let Ok(index) = self.debugger.breakpoints.binary_search(&breakpoint) {
-					return Some(self.debugger.breakpoints[index]);
+			//read/write: look at the address of the memory operand (if there is one)
+			if let Some(address) = memory_operand_address(self, info) {
+				if info.memory == MemoryAccess::Read || info.memory == MemoryAccess::ReadWrite {
+					let breakpoint = Breakpoint::new(address, AccessType::Read);
+					if let Ok(index) = self.debugger.breakpoints.binary_search(&breakpoint) {
+						return Some(self.debugger.breakpoints[index]);
+					}
 				}
-			}
 
-			const WRITE_AT_BC: u8 = 0x02;
-			const WRITE_AT_DE: u8 = 0x12;
-			const WRITE_A16: [u8;2] = [0x08, 0xEA];
-			const WRITE_IO_A8: u8 = 0xE0;
-			const WRITE_IO_C: u8 = 0xE2;
-			const WRITE_AT_HL: [u8;12] = [0x22, 0x32, 0x34, 0x35, 0x36, 0x70, 0x71, 0x72, 0x73,
-			                              0x74, 0x75, 0x77];
-			const WRITE_AT_HL_EXTENDED: [u8;24] = [0x06, 0x0E, 0x16, 0x1E, 0x26, 0x2E, 0x36, 0x3E,
-			                                       0x86, 0x8E, 0x96, 0x9E, 0xA6, 0xAE, 0xB6, 0xBE,
-			                                       0xC6, 0xCE, 0xD6, 0xDE, 0xE6, 0xEE, 0xF6, 0xFE];
-			const WRITES: [u8;18] = [0x02, 0x08, 0x12, 0x22, 0x32, 0x34, 0x35, 0x36, 0x70, 0x71,
-			                         0x72, 0x73, 0x74, 0x75, 0x77, 0xE0, 0xE2, 0xEA];
-
-			let mut address: Option<u16> = None;
-			if let Ok(_) = WRITES.binary_search(&next_opcode) {
-				if let Ok(_) = WRITE_AT_HL.binary_search(&next_opcode) {
-					let h = self.cpu.registers.h as u16;
-					let l = self.cpu.registers.l as u16;
-					address = Some((h << 8) | l);
-				}
-				else if let Ok(_) = WRITE_A16.binary_search(&next_opcode) {
-					let pc = self.cpu.registers.pc + 1;
-					address = Some((self.read_byte(pc) as u16) | ((self.read_byte(pc + 1) as u16) << 8));
-				}
-				else if next_opcode == WRITE_IO_A8 {
-					address = Some((self.read_byte(self.cpu.registers.pc + 1) as u16) + 0xFF00);
-				}
-				else if next_opcode == WRITE_AT_BC {
-					let b = self.cpu.registers.b as u16;
-					let c = self.cpu.registers.c as u16;
-					address = Some((b << 8) | c);
-				}
-				else if next_opcode == WRITE_AT_DE {
-					let d = self.cpu.registers.d as u16;
-					let e = self.cpu.registers.e as u16;
-					address = Some((d << 8) | e);
-				}
-				else if next_opcode == WRITE_IO_C {
-					address = Some(0xFF00 + (self.cpu.registers.c as u16));
-				}
-			}
-			else if next_opcode == 0xCB {
-				let next_opcode = self.read_byte(self.cpu.registers.pc + 1);
-				if let Ok(_) = WRITE_AT_HL_EXTENDED.binary_search(&next_opcode) {
-					let h = self.cpu.registers.h as u16;
-					let l = self.cpu.registers.l as u16;
-					address = Some((h << 8) | l);
-				}
-			}
-			if let Some(address) = address {
-				let breakpoint = Breakpoint::new(address, AccessType::Write);
-				if let Ok(index) = self.debugger.breakpoints.binary_search(&breakpoint) {
-					return Some(self.debugger.breakpoints[index]);
+				if info.memory == MemoryAccess::Write || info.memory == MemoryAccess::ReadWrite {
+					let breakpoint = Breakpoint::new(address, AccessType::Write);
+					if let Ok(index) = self.debugger.breakpoints.binary_search(&breakpoint) {
+						return Some(self.debugger.breakpoints[index]);
+					}
 				}
 			}
 		}
@@ -400,6 +253,7 @@ impl DebuggerInterface for Gameboy {
 			0xFF04 => self.timer.div = (value as u16) << 8,
 			_ => { self.write_byte(address, value); },
 		}
+		self.scheduler.invalidate();
 	}
 
 	fn read_range(&self, address_start: u16, address_end: u16) -> Result<Box<[u8]>, ()> {
@@ -423,6 +277,7 @@ impl DebuggerInterface for Gameboy {
 		for (index, value) in values.iter().enumerate() {
 			self.write_byte((index as u16) + address, *value);
 		}
+		self.scheduler.invalidate();
 	}
 
 	fn get_assembly(&self, ins: &[u8]) -> Vec<String> {
@@ -430,15 +285,18 @@ impl DebuggerInterface for Gameboy {
 	}
 
 	fn reset(&mut self) {
-		use gameboy::Mode;
-		let mode: Mode = match self.cart.get_cart_info().cgb {
-			true => Mode::CGB,
-			false => Mode::DMG,
-		};
+		let mode = self.mode;
 		self.cpu.reset(mode);
 		self.timer.reset();
 		self.ppu.reset();
 		self.oam_dma_state.reset();
+
+		// if there is a boot rom, map it back in so it runs again
+		if let Some(ref mut boot_rom) = self.boot_rom {
+			boot_rom.map();
+		}
+		self.init_hardware();
+		self.scheduler.reset(self.cpu.cycle_counter);
 	}
 
 	fn dump_tiles(&self) -> Bitmap<u32> {
@@ -477,5 +335,60 @@ impl DebuggerInterface for Gameboy {
 
 	fn set_div(&mut self, value: u16) {
 		self.timer.div = value;
+		self.scheduler.invalidate();
+	}
+}
+
+/// Read the little endian word at an address.
+fn read_word(gameboy: &Gameboy, address: u16) -> u16 {
+	(gameboy.read_byte(address) as u16) | ((gameboy.read_byte(address.wrapping_add(1)) as u16) << 8)
+}
+
+/// The address of each operand of the next instruction (where its immediate value is, if it has one).
+fn operand_addresses(gameboy: &Gameboy, info: &OpcodeInfo) -> Vec<u16> {
+	let pc = gameboy.cpu.registers.pc;
+	// the operands start after the opcode (and the 0xCB prefix)
+	let mut address = pc.wrapping_add(if gameboy.read_byte(pc) == 0xCB { 2 } else { 1 });
+	let mut addresses = Vec::with_capacity(info.operands.len());
+	for operand in info.operands {
+		addresses.push(address);
+		address = address.wrapping_add(operand.length() as u16);
+	}
+	addresses
+}
+
+/// Where the next instruction jumps to, if it is JR, JP, CALL, RET, RETI or RST (ignoring the condition).
+fn jump_target(gameboy: &Gameboy, info: &OpcodeInfo) -> Option<u16> {
+	let registers = &gameboy.cpu.registers;
+	for (operand, address) in info.operands.iter().zip(operand_addresses(gameboy, info)) {
+		match (info.mnemonic, *operand) {
+			// the offset is relative to the address of the next instruction
+			(Mnemonic::JR, Operand::S8) => return Some(address.wrapping_add(1).wrapping_add(gameboy.read_byte(address) as i8 as u16)),
+			(Mnemonic::JP, Operand::A16) | (Mnemonic::CALL, Operand::A16) => return Some(read_word(gameboy, address)),
+			(Mnemonic::JP, Operand::R16(pair)) => return Some(registers.get_register_pair(pair)),
+			(Mnemonic::RST, Operand::Vector(vector)) => return Some(vector),
+			_ => {}
+		}
+	}
+	match info.mnemonic {
+		// the return address is at the top of the stack
+		Mnemonic::RET | Mnemonic::RETI => Some(read_word(gameboy, registers.sp)),
+		_ => None
+	}
+}
+
+/// The address the next instruction reads from or writes to, if it has a memory operand.
+fn memory_operand_address(gameboy: &Gameboy, info: &OpcodeInfo) -> Option<u16> {
+	let registers = &gameboy.cpu.registers;
+	for (operand, address) in info.operands.iter().zip(operand_addresses(gameboy, info)) {
+		match *operand {
+			Operand::R8(Register::AT_HL) | Operand::AtHlPlus | Operand::AtHlMinus => return Some(registers.get_register_pair(RegisterPair::HL)),
+			Operand::AtR16(pair) => return Some(registers.get_register_pair(pair)),
+			Operand::AtFF00PlusC => return Some(0xFF00 + registers.c as u16),
+			Operand::AtFF00PlusA8 => return Some(0xFF00 + gameboy.read_byte(address) as u16),
+			Operand::AtA16 => return Some(read_word(gameboy, address)),
+			_ => {}
+		}
 	}
+	None
 }
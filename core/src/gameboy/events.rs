@@ -0,0 +1,9 @@
+/// Events that happen during emulation that a frontend or debugger might want to react to.
+/// They are queued up by the core, and can be retrieved with Gameboy::poll_event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulationEvent {
+	/// The cpu executed one of the opcodes that don't exist (0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD),
+	/// which locks it up until the gameboy is reset.
+	/// bank is the rom bank if pc is in switchable rom (0x4000 - 0x7FFF), or the ram bank if it is in cartridge ram.
+	IllegalOpcode { pc: u16, opcode: u8, bank: usize }
+}
@@ -1,10 +1,18 @@
 use super::Gameboy;
 use super::Register;
 use gameboy::mmu::Mmu;
+use gameboy::scheduler::HardwareScheduler;
 use gameboy::cpu;
 use gameboy::cpu::{ZERO_FLAG_MASK, CARRY_FLAG_MASK};
 use gameboy::cpu::registers::RegisterPair;
 use gameboy::util::{wrapping_add, wrapping_sub};
+use gameboy::Mode;
+use self::opcodes::{OPCODES, CB_OPCODES};
+
+pub mod opcodes;
+
+/// Number of clock cycles the cpu is paused for during a speed switch (2050 M-Cycles).
+const SPEED_SWITCH_CYCLES: usize = 2050 * 4;
 
 
 #[derive(Copy, Clone)]
@@ -12,181 +20,25 @@ pub enum Conditional {
 	Z, NZ, C, NC
 }
 
-fn map_register(reg: u8) -> Register {
-	match reg {
-		0 => Register::B,
-		1 => Register::C,
-		2 => Register::D,
-		3 => Register::E,
-		4 => Register::H,
-		5 => Register::L,
-		6 => Register::AT_HL,
-		7 => Register::A,
-		_ => panic!("reg must be in the range 0...7"),
-	}
-}
-
 impl Gameboy {
 	pub fn execute(&mut self) {
-		if self.cpu.halt {
+		if self.cpu.halt || self.cpu.stop || self.cpu.locked {
 			self.emulate_hardware(4);
 		}
 		else {
 			self.cpu.ime = self.cpu.next_ime_state;
 
 			let opcode: u8 = self.read_byte_cpu(self.cpu.registers.pc);
-			self.cpu.registers.pc = self.cpu.registers.pc.wrapping_add(1);
+			if self.cpu.halt_bug {
+				// pc fails to increment after the opcode fetch following a HALT that triggered the halt bug
+				self.cpu.halt_bug = false;
+			}
+			else {
+				self.cpu.registers.pc = self.cpu.registers.pc.wrapping_add(1);
+			}
 			self.emulate_hardware(4);
 
-			match opcode {
-				0x00 => self.nop(),
-				0x01 => self.ld_bc_d16(),
-				0x02 => self.ld_at_bc_a(),
-				0x03 => self.inc_r16(RegisterPair::BC),
-				0x04 => self.inc_r8(Register::B),
-				0x05 => self.dec_r8(Register::B),
-				0x06 => self.ld_r8_d8(Register::B),
-				0x07 => self.rlca(),
-				0x08 => self.ld_at_a16_sp(),
-				0x09 => self.add_hl_r16(RegisterPair::BC),
-				0x0A => self.ld_a_at_bc(),
-				0x0B => self.dec_r16(RegisterPair::BC),
-				0x0C => self.inc_r8(Register::C),
-				0x0D => self.dec_r8(Register::C),
-				0x0E => self.ld_r8_d8(Register::C),
-				0x0F => self.rrca(),
-				0x10 => self.stop(),
-				0x11 => self.ld_de_d16(),
-				0x12 => self.ld_at_de_a(),
-				0x13 => self.inc_r16(RegisterPair::DE),
-				0x14 => self.inc_r8(Register::D),
-				0x15 => self.dec_r8(Register::D),
-				0x16 => self.ld_r8_d8(Register::D),
-				0x17 => self.rla(),
-				0x18 => self.jr_r8(),
-				0x19 => self.add_hl_r16(RegisterPair::DE),
-				0x1A => self.ld_a_at_de(),
-				0x1B => self.dec_r16(RegisterPair::DE),
-				0x1C => self.inc_r8(Register::E),
-				0x1D => self.dec_r8(Register::E),
-				0x1E => self.ld_r8_d8(Register::E),
-				0x1F => self.rra(),
-				0x20 => self.jr_nz_r8(),
-				0x21 => self.ld_hl_d16(),
-				0x22 => self.ldi_at_hl_a(),
-				0x23 => self.inc_r16(RegisterPair::HL),
-				0x24 => self.inc_r8(Register::H),
-				0x25 => self.dec_r8(Register::H),
-				0x26 => self.ld_r8_d8(Register::H),
-				0x27 => self.daa(),
-				0x28 => self.jr_z_r8(),
-				0x29 => self.add_hl_r16(RegisterPair::HL),
-				0x2A => self.ldi_a_at_hl(),
-				0x2B => self.dec_r16(RegisterPair::HL),
-				0x2C => self.inc_r8(Register::L),
-				0x2D => self.dec_r8(Register::L),
-				0x2E => self.ld_r8_d8(Register::L),
-				0x2F => self.cpl(),
-				0x30 => self.jr_nc_r8(),
-				0x31 => self.ld_sp_d16(),
-				0x32 => self.ldd_at_hl_a(),
-				0x33 => self.inc_r16(RegisterPair::SP),
-				0x34 => self.inc_r8(Register::AT_HL),
-				0x35 => self.dec_r8(Register::AT_HL),
-				0x36 => self.ld_r8_d8(Register::AT_HL),
-				0x37 => self.scf(),
-				0x38 => self.jr_c_r8(),
-				0x39 => self.add_hl_r16(RegisterPair::SP),
-				0x3A => self.ldd_a_at_hl(),
-				0x3B => self.dec_r16(RegisterPair::SP),
-				0x3C => self.inc_r8(Register::A),
-				0x3D => self.dec_r8(Register::A),
-				0x3E => self.ld_r8_d8(Register::A),
-				0x3F => self.ccf(),
-				0x40...0x47 => self.ld_r_r(Register::B, opcode),
-				0x48...0x4F => self.ld_r_r(Register::C, opcode),
-				0x50...0x57 => self.ld_r_r(Register::D, opcode),
-				0x58...0x5F => self.ld_r_r(Register::E, opcode),
-				0x60...0x67 => self.ld_r_r(Register::H, opcode),
-				0x68...0x6F => self.ld_r_r(Register::L, opcode),
-				//TODO: collapse LD (HL), R into a single fn
-				0x70 => self.ld_at_hl_r8(Register::B),
-				0x71 => self.ld_at_hl_r8(Register::C),
-				0x72 => self.ld_at_hl_r8(Register::D),
-				0x73 => self.ld_at_hl_r8(Register::E),
-				0x74 => self.ld_at_hl_r8(Register::H),
-				0x75 => self.ld_at_hl_r8(Register::L),
-				0x76 => self.halt(),
-				0x77 => self.ld_at_hl_r8(Register::A),
-
-				//TODO: refactor these to take a register, not an opcode
-				0x78...0x7F => self.ld_r_r(Register::A, opcode),
-				0x80...0x87 => self.add_a_r8(opcode),
-				0x88...0x8F => self.adc_a_r8(opcode),
-				0x90...0x97 => self.sub_a_r8(opcode),
-				0x98...0x9F => self.sbc_a_r8(opcode),
-				0xA0...0xA7 => self.and(opcode),
-				0xA8...0xAF => self.xor(opcode),
-				0xB0...0xB7 => self.or_r8(opcode),
-				0xB8...0xBF => self.cp_r8(opcode),
-				0xC0 => self.ret_nz(),
-				0xC1 => self.pop_r16(RegisterPair::BC),
-				0xC2 => self.jp_conditional(Conditional::NZ),
-				0xC3 => self.jp_a16(),
-				0xC4 => self.call_conditional(Conditional::NZ),
-				0xC5 => self.push_r16(RegisterPair::BC),
-				0xC6 => self.add_d8(),
-				0xC7 => self.rst(0x00),
-				0xC8 => self.ret_z(),
-				0xC9 => self.ret(),
-				0xCA => self.jp_conditional(Conditional::Z),
-				0xCB => self.extended(),
-				0xCE => self.adc_a_d8(),
-				0xCC => self.call_conditional(Conditional::Z),
-				0xCD => self.call_a16(),
-				0xCF => self.rst(0x08),
-				0xD0 => self.ret_nc(),
-				0xD1 => self.pop_r16(RegisterPair::DE),
-				0xD2 => self.jp_conditional(Conditional::NC),
-				0xD4 => self.call_conditional(Conditional::NC),
-				0xD5 => self.push_r16(RegisterPair::DE),
-				0xD6 => self.sub_d8(),
-				0xD7 => self.rst(0x10),
-				0xD8 => self.ret_c(),
-				0xD9 => self.reti(),
-				0xDA => self.jp_conditional(Conditional::C),
-				0xDC => self.call_conditional(Conditional::C),
-				0xDE => self.sbc_a_d8(),
-				0xDF => self.rst(0x18),
-				0xE0 => self.ld_at_ff00_plus_a8_a(),
-				0xE1 => self.pop_r16(RegisterPair::HL),
-				0xE2 => self.ld_at_ff00_plus_c_a(),
-				0xE5 => self.push_r16(RegisterPair::HL),
-				0xE6 => self.and_d8(),
-				0xE7 => self.rst(0x20),
-				0xE8 => self.add_sp_nn(),
-				0xE9 => self.jp_hl(),
-				0xEA => self.ld_at_a16_a(),
-				0xEE => self.xor_d8(),
-				0xEF => self.rst(0x28),
-				0xF0 => self.ld_a_at_ff00_plus_a8(),
-				0xF1 => self.pop_af(),
-				0xF2 => self.ld_a_at_ff00_plus_c(),
-				0xF3 => self.di(),
-				0xF5 => self.push_r16(RegisterPair::AF),
-				0xF6 => self.or_d8(),
-				0xF7 => self.rst(0x30),
-				0xF8 => self.ld_hl_sp_plus_nn(),
-				0xF9 => self.ld_sp_hl(),
-				0xFA => self.ld_a_at_a16(),
-				0xFB => self.ei(),
-				0xFE => self.cp_d8(),
-				0xFF => self.rst(0x38),
-				_ => {
-					self.cpu.registers.pc -= 1;
-					panic!("\n{:?}\nUnimplemented opcode {:X}", self.cpu.registers ,opcode);
-				},
-			};
+			(OPCODES[opcode as usize].execute)(self);
 		}
 	}
 
@@ -522,10 +374,52 @@ impl Gameboy {
 
 	///0x10: Stop
 	///1 M-Cycle
-	///Length: 1 byte
-	///Not really sure how to handle this
+	///Length: 1 or 2 bytes
+	///What STOP actually does depends on whether a selected button is being held, whether an interrupt
+	///is pending (IE & IF != 0), and whether a speed switch was requested through KEY1:
+	///- button held, interrupt pending: 1 byte, nothing happens (same as NOP)
+	///- button held, no interrupt pending: 2 bytes, the cpu enters HALT mode, div isn't reset
+	///- speed switch requested (cgb mode only): the speed is switched and div is reset
+	///- otherwise: the cpu enters STOP mode and div is reset
+	///In the last two cases STOP is 2 bytes long if no interrupt is pending, otherwise it's 1 byte.
+	///In STOP mode the clock is stopped until one of the joypad lines selected in JOYP goes low,
+	///on the dmg the lcd is also blanked (without modifying LCDC).
 	fn stop(&mut self) {
-		self.cpu.stop = true;
+		use gameboy::timer::TimerRegister;
+		self.sync_hardware();
+		let interrupt_pending = self.cpu.interrupt_flag.read() & self.cpu.interrupt_enable.read() & 0x1F != 0;
+		if !interrupt_pending {
+			// the byte after STOP gets skipped
+			self.cpu.registers.pc = self.cpu.registers.pc.wrapping_add(1);
+		}
+
+		if self.joypad.selected_key_pressed() {
+			if !interrupt_pending {
+				self.cpu.halt = true;
+			}
+		}
+		else if self.mode == Mode::CGB && self.cpu.prepare_speed_switch {
+			self.speed_switch();
+		}
+		else {
+			self.timer.write_io(TimerRegister::Div, 0);
+			self.scheduler.invalidate();
+			self.cpu.stop = true;
+			if !self.model.is_cgb() {
+				self.ppu.blank_screen();
+			}
+		}
+	}
+
+	///Switch between normal speed and double speed.
+	///The divider is reset, and the cpu is paused for 2050 M-Cycles while the clock is changed.
+	fn speed_switch(&mut self) {
+		use gameboy::timer::TimerRegister;
+		self.cpu.double_speed_mode = !self.cpu.double_speed_mode;
+		self.cpu.prepare_speed_switch = false;
+		self.timer.write_io(TimerRegister::Div, 0);
+		self.scheduler.invalidate();
+		self.emulate_hardware(SPEED_SWITCH_CYCLES);
 	}
 
 	///0x11: LD DE, d16
@@ -817,8 +711,7 @@ impl Gameboy {
 	///[0x40...0x75] U [0x77...0x7F]: LD r1, r2
 	///1 M-Cycle (except 0x_6 & 0x_E which take 2 M-Cycles)
 	///Length: 1 byte
-	fn ld_r_r(&mut self, dest: Register, opcode: u8) {
-		let src: Register = map_register(opcode & 7);
+	fn ld_r_r(&mut self, dest: Register, src: Register) {
 		let val: u8 = self.get_register(src);
 		self.set_register(dest, val);
 	}
@@ -835,66 +728,73 @@ impl Gameboy {
 	///0x76: HALT
 	///1 M-Cycle
 	///Length: 1 byte
+	///If HALT is executed while IME is 0 and an interrupt is already pending (IE & IF != 0), the cpu doesn't halt,
+	///instead the halt bug is triggered: pc isn't incremented after the next opcode is fetched, so the byte after HALT is read twice.
 	fn halt(&mut self) {
-		self.cpu.halt = true;
+		let interrupt_pending = self.cpu.interrupt_flag.read() & self.cpu.interrupt_enable.read() & 0x1F != 0;
+		if !self.cpu.ime && interrupt_pending {
+			self.cpu.halt_bug = true;
+		}
+		else {
+			self.cpu.halt = true;
+		}
 	}
 
 	///0x80...0x8F: ADD A, r8
 	///1 M-Cycle
 	///Length: 1 byte
-	fn add_a_r8(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn add_a_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::add(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
 	///0x80...0x8F: ADC A, r8
 	///1 M-Cycle
 	///Length: 1 byte
-	fn adc_a_r8(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn adc_a_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::adc(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
-	fn sub_a_r8(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn sub_a_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::sub(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
-	fn sbc_a_r8(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn sbc_a_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::sbc(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
 	///0xA0...0xA7: AND r8
 	///1 M-Cycle
 	///Length: 1 byte
-	fn and(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn and(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::and(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
 	///0xA8...0xAF: XOR r8
 	///1 M-Cycle (except for 0xAE, XOR (HL), which takes 2)
 	///Length: 1 byte
-	fn xor(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn xor(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::xor(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
 	///0xB0...0xB7: OR R8
 	///1 M-Cycle, unless the register is (HL), then 2 M-Cycles
 	///Length: 1 byte
-	fn or_r8(&mut self, opcode: u8) {
-		//The register is the low 3 bits of the opcode
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn or_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		self.cpu.registers.a = cpu::alu::or(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
 	///0xB8...0xBF: CP r8
 	///1 M-Cycle
 	///Length: 1 byte
-	fn cp_r8(&mut self, opcode: u8) {
-		let register: u8 = self.get_register(map_register(opcode & 0x7));
+	fn cp_r8(&mut self, reg: Register) {
+		let register: u8 = self.get_register(reg);
 		cpu::alu::cp(self.cpu.registers.a, register, &mut self.cpu.registers.f);
 	}
 
@@ -968,53 +868,60 @@ impl Gameboy {
 		let opcode: u8 = self.read_next();
 		self.emulate_hardware(4);
 
-		let reg: Register = map_register(opcode & 0x7);
-		let val = self.get_register(reg);
-
-		let mut new_val: Option<u8> = None;
-		match opcode {
-			0x00...0x07 => new_val = Some(cpu::alu::rlc(val, &mut self.cpu.registers.f)),
-			0x08...0x0F => new_val = Some(cpu::alu::rrc(val, &mut self.cpu.registers.f)),
-			0x10...0x17 => new_val = Some(cpu::alu::rl(val, &mut self.cpu.registers.f)),
-			0x18...0x1F => new_val = Some(cpu::alu::rr(val, &mut self.cpu.registers.f)),
-			0x20...0x27 => new_val = Some(cpu::alu::sla(val, &mut self.cpu.registers.f)),
-			0x28...0x2F => new_val = Some(cpu::alu::sra(val, &mut self.cpu.registers.f)),
-			0x30...0x37 => new_val = Some(cpu::alu::swap(val, &mut self.cpu.registers.f)),
-			0x38...0x3F => new_val = Some(cpu::alu::srl(val, &mut self.cpu.registers.f)),
-			0x40...0x47 => cpu::alu::bit(val, &mut self.cpu.registers.f, 0),
-			0x48...0x4F => cpu::alu::bit(val, &mut self.cpu.registers.f, 1),
-			0x50...0x57 => cpu::alu::bit(val, &mut self.cpu.registers.f, 2),
-			0x58...0x5F => cpu::alu::bit(val, &mut self.cpu.registers.f, 3),
-			0x60...0x67 => cpu::alu::bit(val, &mut self.cpu.registers.f, 4),
-			0x68...0x6F => cpu::alu::bit(val, &mut self.cpu.registers.f, 5),
-			0x70...0x77 => cpu::alu::bit(val, &mut self.cpu.registers.f, 6),
-			0x78...0x7F => cpu::alu::bit(val, &mut self.cpu.registers.f, 7),
-			0x80...0x87 => new_val = Some(cpu::alu::res(val, 0)),
-			0x88...0x8F => new_val = Some(cpu::alu::res(val, 1)),
-			0x90...0x97 => new_val = Some(cpu::alu::res(val, 2)),
-			0x98...0x9F => new_val = Some(cpu::alu::res(val, 3)),
-			0xA0...0xA7 => new_val = Some(cpu::alu::res(val, 4)),
-			0xA8...0xAF => new_val = Some(cpu::alu::res(val, 5)),
-			0xB0...0xB7 => new_val = Some(cpu::alu::res(val, 6)),
-			0xB8...0xBF => new_val = Some(cpu::alu::res(val, 7)),
-			0xC0...0xC7 => new_val = Some(cpu::alu::set(val, 0)),
-			0xC8...0xCF => new_val = Some(cpu::alu::set(val, 1)),
-			0xD0...0xD7 => new_val = Some(cpu::alu::set(val, 2)),
-			0xD8...0xDF => new_val = Some(cpu::alu::set(val, 3)),
-			0xE0...0xE7 => new_val = Some(cpu::alu::set(val, 4)),
-			0xE8...0xEF => new_val = Some(cpu::alu::set(val, 5)),
-			0xF0...0xF7 => new_val = Some(cpu::alu::set(val, 6)),
-			0xF8...0xFF => new_val = Some(cpu::alu::set(val, 7)),
-			_ => {
-				self.cpu.registers.pc -= 1;
-				panic!("\n{:?}\nUnimlemented extended opcode {:#X}", self.cpu.registers, opcode);
-			}
-		};
+		(CB_OPCODES[opcode as usize].execute)(self);
+	}
+
+	///0xCB 0x00...0x3F: RLC/RRC/RL/RR/SLA/SRA/SWAP/SRL r8
+	///2 M-Cycles (4 for (HL))
+	///Length: 2 bytes
+	fn shift_r8(&mut self, reg: Register, operation: fn(u8, &mut u8) -> u8) {
+		let val: u8 = self.get_register(reg);
+		let new_val: u8 = operation(val, &mut self.cpu.registers.f);
+		self.set_register(reg, new_val);
+	}
+
+	///0xCB 0x40...0x7F: BIT n, r8
+	///2 M-Cycles (3 for (HL))
+	///Length: 2 bytes
+	fn bit_r8(&mut self, bit: u8, reg: Register) {
+		let val: u8 = self.get_register(reg);
+		cpu::alu::bit(val, &mut self.cpu.registers.f, bit);
+	}
 
-		match new_val {
-			Some(v) => self.set_register(reg, v),
-			_ => {},
+	///0xCB 0x80...0xBF: RES n, r8
+	///2 M-Cycles (4 for (HL))
+	///Length: 2 bytes
+	fn res_r8(&mut self, bit: u8, reg: Register) {
+		let val: u8 = self.get_register(reg);
+		self.set_register(reg, cpu::alu::res(val, bit));
+	}
+
+	///0xCB 0xC0...0xFF: SET n, r8
+	///2 M-Cycles (4 for (HL))
+	///Length: 2 bytes
+	fn set_r8(&mut self, bit: u8, reg: Register) {
+		let val: u8 = self.get_register(reg);
+		self.set_register(reg, cpu::alu::set(val, bit));
+	}
+
+	///0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD: illegal opcodes
+	///The cpu locks up: it stops executing instructions and ignores interrupts until the gameboy is reset,
+	///the rest of the hardware keeps running.
+	fn illegal_opcode(&mut self) {
+		use gameboy::cartridge::Cartridge;
+		use gameboy::EmulationEvent;
+
+		self.cpu.registers.pc = self.cpu.registers.pc.wrapping_sub(1);
+		let pc: u16 = self.cpu.registers.pc;
+		let opcode: u8 = self.read_byte(pc);
+		let bank: usize = match pc {
+			0x4000...0x7FFF => self.cart.rom_bank(),
+			0xA000...0xBFFF => self.cart.ram_bank(),
+			_ => 0
 		};
+
+		self.cpu.locked = true;
+		self.events.push_back(EmulationEvent::IllegalOpcode { pc: pc, opcode: opcode, bank: bank });
 	}
 
 	///0xCD: call a16
@@ -0,0 +1,661 @@
+use std::fmt;
+
+use gameboy::Gameboy;
+use gameboy::cpu::alu;
+use gameboy::cpu::registers::{Register, RegisterPair};
+use super::Conditional;
+
+use self::Operand::*;
+use self::MemoryAccess::*;
+use self::Mnemonic::*;
+
+/// The kinds of operands an instruction can have.
+/// Immediate operands (D8, D16, A16, S8, ...) are read from the bytes following the opcode.
+#[derive(Copy, Clone)]
+pub enum Operand {
+	/// 8-bit register, or (HL) which is encoded the same way as the registers
+	R8(Register),
+	/// 16-bit register pair
+	R16(RegisterPair),
+	/// Memory pointed to by a register pair: (BC), (DE)
+	AtR16(RegisterPair),
+	/// (HL+), HL is incremented after the access
+	AtHlPlus,
+	/// (HL-), HL is decremented after the access
+	AtHlMinus,
+	/// (FF00 + C)
+	AtFF00PlusC,
+	/// (FF00 + a8)
+	AtFF00PlusA8,
+	/// (a16)
+	AtA16,
+	/// 8-bit immediate value
+	D8,
+	/// 16-bit immediate value
+	D16,
+	/// 16-bit address (JP, CALL)
+	A16,
+	/// Signed 8-bit immediate value (JR offset, ADD SP)
+	S8,
+	/// SP plus a signed 8-bit immediate value
+	SpPlusS8,
+	/// Condition for a conditional branch
+	Cond(Conditional),
+	/// Bit number for BIT/RES/SET
+	Bit(u8),
+	/// Restart vector for RST
+	Vector(u16),
+}
+
+impl Operand {
+	/// The number of bytes following the opcode used by this operand.
+	pub fn length(&self) -> usize {
+		match *self {
+			AtFF00PlusA8 | D8 | S8 | SpPlusS8 => 1,
+			AtA16 | D16 | A16 => 2,
+			_ => 0
+		}
+	}
+}
+
+/// How an instruction accesses the memory operand it has (if any).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MemoryAccess {
+	NoAccess, Read, Write, ReadWrite
+}
+
+/// The instruction an opcode is, without its operands.
+/// Illegal opcodes lock up the cpu, PREFIX is 0xCB, which selects the extended opcodes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mnemonic {
+	NOP, LD, INC, DEC, ADD, ADC, SUB, SBC, AND, XOR, OR, CP,
+	RLCA, RRCA, RLA, RRA, DAA, CPL, SCF, CCF,
+	JR, JP, CALL, RET, RETI, RST, PUSH, POP,
+	STOP, HALT, DI, EI, PREFIX,
+	RLC, RRC, RL, RR, SLA, SRA, SWAP, SRL, BIT, RES, SET,
+	ILLEGAL
+}
+
+impl fmt::Display for Mnemonic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
+
+/// Metadata for a single opcode, along with the function that executes it.
+/// This is the only place where opcodes are described, the interpreter, disassembler and debugger all use it.
+pub struct OpcodeInfo {
+	pub mnemonic: Mnemonic,
+
+	/// Operands in the same order as the assembly (destination first).
+	pub operands: &'static [Operand],
+
+	/// Length of the instruction in bytes, including the opcode (and the 0xCB prefix for extended instructions).
+	pub length: usize,
+
+	/// Duration in M-Cycles, including the opcode fetch.
+	/// For conditional branches this is the duration when the branch isn't taken.
+	pub cycles: usize,
+
+	/// Duration in M-Cycles when a conditional branch is taken (the same as cycles for every other instruction).
+	pub cycles_branch: usize,
+
+	/// Flags affected, in the order Z N H C.
+	/// '-' means unaffected, '0' or '1' means always reset or set, and the name of the flag means it depends on the result.
+	pub flags: &'static str,
+
+	/// How the memory operand is accessed.
+	pub memory: MemoryAccess,
+
+	/// Executes the instruction (after the opcode has been fetched).
+	pub execute: fn(&mut Gameboy)
+}
+
+impl OpcodeInfo {
+	pub fn is_conditional(&self) -> bool {
+		self.cycles != self.cycles_branch
+	}
+}
+
+const fn op(mnemonic: Mnemonic, operands: &'static [Operand], length: usize, cycles: usize, cycles_branch: usize, flags: &'static str, memory: MemoryAccess, execute: fn(&mut Gameboy)) -> OpcodeInfo {
+	OpcodeInfo {
+		mnemonic: mnemonic,
+		operands: operands,
+		length: length,
+		cycles: cycles,
+		cycles_branch: cycles_branch,
+		flags: flags,
+		memory: memory,
+		execute: execute
+	}
+}
+
+/// Look up the metadata for the instruction at the start of a slice of memory.
+/// Extended instructions (0xCB prefix) are looked up in CB_OPCODES, if the slice is long enough.
+pub fn lookup(bytes: &[u8]) -> Option<&'static OpcodeInfo> {
+	match bytes.get(0) {
+		Some(&0xCB) => bytes.get(1).map(|opcode| &CB_OPCODES[*opcode as usize]),
+		Some(opcode) => Some(&OPCODES[*opcode as usize]),
+		None => None
+	}
+}
+
+/// Opcodes 0x00...0xFF
+pub static OPCODES: [OpcodeInfo; 256] = [
+	op(NOP, &[], 1, 1, 1, "----", NoAccess, |gb| gb.nop()), // 0x00
+	op(LD, &[R16(RegisterPair::BC), D16], 3, 3, 3, "----", NoAccess, |gb| gb.ld_bc_d16()), // 0x01
+	op(LD, &[AtR16(RegisterPair::BC), R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ld_at_bc_a()), // 0x02
+	op(INC, &[R16(RegisterPair::BC)], 1, 2, 2, "----", NoAccess, |gb| gb.inc_r16(RegisterPair::BC)), // 0x03
+	op(INC, &[R8(Register::B)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::B)), // 0x04
+	op(DEC, &[R8(Register::B)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::B)), // 0x05
+	op(LD, &[R8(Register::B), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::B)), // 0x06
+	op(RLCA, &[], 1, 1, 1, "000C", NoAccess, |gb| gb.rlca()), // 0x07
+	op(LD, &[AtA16, R16(RegisterPair::SP)], 3, 5, 5, "----", Write, |gb| gb.ld_at_a16_sp()), // 0x08
+	op(ADD, &[R16(RegisterPair::HL), R16(RegisterPair::BC)], 1, 2, 2, "-0HC", NoAccess, |gb| gb.add_hl_r16(RegisterPair::BC)), // 0x09
+	op(LD, &[R8(Register::A), AtR16(RegisterPair::BC)], 1, 2, 2, "----", Read, |gb| gb.ld_a_at_bc()), // 0x0A
+	op(DEC, &[R16(RegisterPair::BC)], 1, 2, 2, "----", NoAccess, |gb| gb.dec_r16(RegisterPair::BC)), // 0x0B
+	op(INC, &[R8(Register::C)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::C)), // 0x0C
+	op(DEC, &[R8(Register::C)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::C)), // 0x0D
+	op(LD, &[R8(Register::C), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::C)), // 0x0E
+	op(RRCA, &[], 1, 1, 1, "000C", NoAccess, |gb| gb.rrca()), // 0x0F
+	op(STOP, &[], 2, 1, 1, "----", NoAccess, |gb| gb.stop()), // 0x10
+	op(LD, &[R16(RegisterPair::DE), D16], 3, 3, 3, "----", NoAccess, |gb| gb.ld_de_d16()), // 0x11
+	op(LD, &[AtR16(RegisterPair::DE), R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ld_at_de_a()), // 0x12
+	op(INC, &[R16(RegisterPair::DE)], 1, 2, 2, "----", NoAccess, |gb| gb.inc_r16(RegisterPair::DE)), // 0x13
+	op(INC, &[R8(Register::D)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::D)), // 0x14
+	op(DEC, &[R8(Register::D)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::D)), // 0x15
+	op(LD, &[R8(Register::D), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::D)), // 0x16
+	op(RLA, &[], 1, 1, 1, "000C", NoAccess, |gb| gb.rla()), // 0x17
+	op(JR, &[S8], 2, 3, 3, "----", NoAccess, |gb| gb.jr_r8()), // 0x18
+	op(ADD, &[R16(RegisterPair::HL), R16(RegisterPair::DE)], 1, 2, 2, "-0HC", NoAccess, |gb| gb.add_hl_r16(RegisterPair::DE)), // 0x19
+	op(LD, &[R8(Register::A), AtR16(RegisterPair::DE)], 1, 2, 2, "----", Read, |gb| gb.ld_a_at_de()), // 0x1A
+	op(DEC, &[R16(RegisterPair::DE)], 1, 2, 2, "----", NoAccess, |gb| gb.dec_r16(RegisterPair::DE)), // 0x1B
+	op(INC, &[R8(Register::E)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::E)), // 0x1C
+	op(DEC, &[R8(Register::E)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::E)), // 0x1D
+	op(LD, &[R8(Register::E), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::E)), // 0x1E
+	op(RRA, &[], 1, 1, 1, "000C", NoAccess, |gb| gb.rra()), // 0x1F
+	op(JR, &[Cond(Conditional::NZ), S8], 2, 2, 3, "----", NoAccess, |gb| gb.jr_nz_r8()), // 0x20
+	op(LD, &[R16(RegisterPair::HL), D16], 3, 3, 3, "----", NoAccess, |gb| gb.ld_hl_d16()), // 0x21
+	op(LD, &[AtHlPlus, R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ldi_at_hl_a()), // 0x22
+	op(INC, &[R16(RegisterPair::HL)], 1, 2, 2, "----", NoAccess, |gb| gb.inc_r16(RegisterPair::HL)), // 0x23
+	op(INC, &[R8(Register::H)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::H)), // 0x24
+	op(DEC, &[R8(Register::H)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::H)), // 0x25
+	op(LD, &[R8(Register::H), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::H)), // 0x26
+	op(DAA, &[], 1, 1, 1, "Z-0C", NoAccess, |gb| gb.daa()), // 0x27
+	op(JR, &[Cond(Conditional::Z), S8], 2, 2, 3, "----", NoAccess, |gb| gb.jr_z_r8()), // 0x28
+	op(ADD, &[R16(RegisterPair::HL), R16(RegisterPair::HL)], 1, 2, 2, "-0HC", NoAccess, |gb| gb.add_hl_r16(RegisterPair::HL)), // 0x29
+	op(LD, &[R8(Register::A), AtHlPlus], 1, 2, 2, "----", Read, |gb| gb.ldi_a_at_hl()), // 0x2A
+	op(DEC, &[R16(RegisterPair::HL)], 1, 2, 2, "----", NoAccess, |gb| gb.dec_r16(RegisterPair::HL)), // 0x2B
+	op(INC, &[R8(Register::L)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::L)), // 0x2C
+	op(DEC, &[R8(Register::L)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::L)), // 0x2D
+	op(LD, &[R8(Register::L), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::L)), // 0x2E
+	op(CPL, &[], 1, 1, 1, "-11-", NoAccess, |gb| gb.cpl()), // 0x2F
+	op(JR, &[Cond(Conditional::NC), S8], 2, 2, 3, "----", NoAccess, |gb| gb.jr_nc_r8()), // 0x30
+	op(LD, &[R16(RegisterPair::SP), D16], 3, 3, 3, "----", NoAccess, |gb| gb.ld_sp_d16()), // 0x31
+	op(LD, &[AtHlMinus, R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ldd_at_hl_a()), // 0x32
+	op(INC, &[R16(RegisterPair::SP)], 1, 2, 2, "----", NoAccess, |gb| gb.inc_r16(RegisterPair::SP)), // 0x33
+	op(INC, &[R8(Register::AT_HL)], 1, 3, 3, "Z0H-", ReadWrite, |gb| gb.inc_r8(Register::AT_HL)), // 0x34
+	op(DEC, &[R8(Register::AT_HL)], 1, 3, 3, "Z1H-", ReadWrite, |gb| gb.dec_r8(Register::AT_HL)), // 0x35
+	op(LD, &[R8(Register::AT_HL), D8], 2, 3, 3, "----", Write, |gb| gb.ld_r8_d8(Register::AT_HL)), // 0x36
+	op(SCF, &[], 1, 1, 1, "-001", NoAccess, |gb| gb.scf()), // 0x37
+	op(JR, &[Cond(Conditional::C), S8], 2, 2, 3, "----", NoAccess, |gb| gb.jr_c_r8()), // 0x38
+	op(ADD, &[R16(RegisterPair::HL), R16(RegisterPair::SP)], 1, 2, 2, "-0HC", NoAccess, |gb| gb.add_hl_r16(RegisterPair::SP)), // 0x39
+	op(LD, &[R8(Register::A), AtHlMinus], 1, 2, 2, "----", Read, |gb| gb.ldd_a_at_hl()), // 0x3A
+	op(DEC, &[R16(RegisterPair::SP)], 1, 2, 2, "----", NoAccess, |gb| gb.dec_r16(RegisterPair::SP)), // 0x3B
+	op(INC, &[R8(Register::A)], 1, 1, 1, "Z0H-", NoAccess, |gb| gb.inc_r8(Register::A)), // 0x3C
+	op(DEC, &[R8(Register::A)], 1, 1, 1, "Z1H-", NoAccess, |gb| gb.dec_r8(Register::A)), // 0x3D
+	op(LD, &[R8(Register::A), D8], 2, 2, 2, "----", NoAccess, |gb| gb.ld_r8_d8(Register::A)), // 0x3E
+	op(CCF, &[], 1, 1, 1, "-00C", NoAccess, |gb| gb.ccf()), // 0x3F
+	op(LD, &[R8(Register::B), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::B)), // 0x40
+	op(LD, &[R8(Register::B), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::C)), // 0x41
+	op(LD, &[R8(Register::B), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::D)), // 0x42
+	op(LD, &[R8(Register::B), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::E)), // 0x43
+	op(LD, &[R8(Register::B), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::H)), // 0x44
+	op(LD, &[R8(Register::B), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::L)), // 0x45
+	op(LD, &[R8(Register::B), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::B, Register::AT_HL)), // 0x46
+	op(LD, &[R8(Register::B), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::B, Register::A)), // 0x47
+	op(LD, &[R8(Register::C), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::B)), // 0x48
+	op(LD, &[R8(Register::C), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::C)), // 0x49
+	op(LD, &[R8(Register::C), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::D)), // 0x4A
+	op(LD, &[R8(Register::C), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::E)), // 0x4B
+	op(LD, &[R8(Register::C), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::H)), // 0x4C
+	op(LD, &[R8(Register::C), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::L)), // 0x4D
+	op(LD, &[R8(Register::C), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::C, Register::AT_HL)), // 0x4E
+	op(LD, &[R8(Register::C), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::C, Register::A)), // 0x4F
+	op(LD, &[R8(Register::D), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::B)), // 0x50
+	op(LD, &[R8(Register::D), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::C)), // 0x51
+	op(LD, &[R8(Register::D), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::D)), // 0x52
+	op(LD, &[R8(Register::D), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::E)), // 0x53
+	op(LD, &[R8(Register::D), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::H)), // 0x54
+	op(LD, &[R8(Register::D), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::L)), // 0x55
+	op(LD, &[R8(Register::D), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::D, Register::AT_HL)), // 0x56
+	op(LD, &[R8(Register::D), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::D, Register::A)), // 0x57
+	op(LD, &[R8(Register::E), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::B)), // 0x58
+	op(LD, &[R8(Register::E), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::C)), // 0x59
+	op(LD, &[R8(Register::E), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::D)), // 0x5A
+	op(LD, &[R8(Register::E), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::E)), // 0x5B
+	op(LD, &[R8(Register::E), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::H)), // 0x5C
+	op(LD, &[R8(Register::E), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::L)), // 0x5D
+	op(LD, &[R8(Register::E), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::E, Register::AT_HL)), // 0x5E
+	op(LD, &[R8(Register::E), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::E, Register::A)), // 0x5F
+	op(LD, &[R8(Register::H), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::B)), // 0x60
+	op(LD, &[R8(Register::H), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::C)), // 0x61
+	op(LD, &[R8(Register::H), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::D)), // 0x62
+	op(LD, &[R8(Register::H), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::E)), // 0x63
+	op(LD, &[R8(Register::H), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::H)), // 0x64
+	op(LD, &[R8(Register::H), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::L)), // 0x65
+	op(LD, &[R8(Register::H), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::H, Register::AT_HL)), // 0x66
+	op(LD, &[R8(Register::H), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::H, Register::A)), // 0x67
+	op(LD, &[R8(Register::L), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::B)), // 0x68
+	op(LD, &[R8(Register::L), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::C)), // 0x69
+	op(LD, &[R8(Register::L), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::D)), // 0x6A
+	op(LD, &[R8(Register::L), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::E)), // 0x6B
+	op(LD, &[R8(Register::L), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::H)), // 0x6C
+	op(LD, &[R8(Register::L), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::L)), // 0x6D
+	op(LD, &[R8(Register::L), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::L, Register::AT_HL)), // 0x6E
+	op(LD, &[R8(Register::L), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::L, Register::A)), // 0x6F
+	op(LD, &[R8(Register::AT_HL), R8(Register::B)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::B)), // 0x70
+	op(LD, &[R8(Register::AT_HL), R8(Register::C)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::C)), // 0x71
+	op(LD, &[R8(Register::AT_HL), R8(Register::D)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::D)), // 0x72
+	op(LD, &[R8(Register::AT_HL), R8(Register::E)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::E)), // 0x73
+	op(LD, &[R8(Register::AT_HL), R8(Register::H)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::H)), // 0x74
+	op(LD, &[R8(Register::AT_HL), R8(Register::L)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::L)), // 0x75
+	op(HALT, &[], 1, 1, 1, "----", NoAccess, |gb| gb.halt()), // 0x76
+	op(LD, &[R8(Register::AT_HL), R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ld_at_hl_r8(Register::A)), // 0x77
+	op(LD, &[R8(Register::A), R8(Register::B)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::B)), // 0x78
+	op(LD, &[R8(Register::A), R8(Register::C)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::C)), // 0x79
+	op(LD, &[R8(Register::A), R8(Register::D)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::D)), // 0x7A
+	op(LD, &[R8(Register::A), R8(Register::E)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::E)), // 0x7B
+	op(LD, &[R8(Register::A), R8(Register::H)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::H)), // 0x7C
+	op(LD, &[R8(Register::A), R8(Register::L)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::L)), // 0x7D
+	op(LD, &[R8(Register::A), R8(Register::AT_HL)], 1, 2, 2, "----", Read, |gb| gb.ld_r_r(Register::A, Register::AT_HL)), // 0x7E
+	op(LD, &[R8(Register::A), R8(Register::A)], 1, 1, 1, "----", NoAccess, |gb| gb.ld_r_r(Register::A, Register::A)), // 0x7F
+	op(ADD, &[R8(Register::A), R8(Register::B)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::B)), // 0x80
+	op(ADD, &[R8(Register::A), R8(Register::C)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::C)), // 0x81
+	op(ADD, &[R8(Register::A), R8(Register::D)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::D)), // 0x82
+	op(ADD, &[R8(Register::A), R8(Register::E)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::E)), // 0x83
+	op(ADD, &[R8(Register::A), R8(Register::H)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::H)), // 0x84
+	op(ADD, &[R8(Register::A), R8(Register::L)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::L)), // 0x85
+	op(ADD, &[R8(Register::A), R8(Register::AT_HL)], 1, 2, 2, "Z0HC", Read, |gb| gb.add_a_r8(Register::AT_HL)), // 0x86
+	op(ADD, &[R8(Register::A), R8(Register::A)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.add_a_r8(Register::A)), // 0x87
+	op(ADC, &[R8(Register::A), R8(Register::B)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::B)), // 0x88
+	op(ADC, &[R8(Register::A), R8(Register::C)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::C)), // 0x89
+	op(ADC, &[R8(Register::A), R8(Register::D)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::D)), // 0x8A
+	op(ADC, &[R8(Register::A), R8(Register::E)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::E)), // 0x8B
+	op(ADC, &[R8(Register::A), R8(Register::H)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::H)), // 0x8C
+	op(ADC, &[R8(Register::A), R8(Register::L)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::L)), // 0x8D
+	op(ADC, &[R8(Register::A), R8(Register::AT_HL)], 1, 2, 2, "Z0HC", Read, |gb| gb.adc_a_r8(Register::AT_HL)), // 0x8E
+	op(ADC, &[R8(Register::A), R8(Register::A)], 1, 1, 1, "Z0HC", NoAccess, |gb| gb.adc_a_r8(Register::A)), // 0x8F
+	op(SUB, &[R8(Register::A), R8(Register::B)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::B)), // 0x90
+	op(SUB, &[R8(Register::A), R8(Register::C)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::C)), // 0x91
+	op(SUB, &[R8(Register::A), R8(Register::D)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::D)), // 0x92
+	op(SUB, &[R8(Register::A), R8(Register::E)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::E)), // 0x93
+	op(SUB, &[R8(Register::A), R8(Register::H)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::H)), // 0x94
+	op(SUB, &[R8(Register::A), R8(Register::L)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::L)), // 0x95
+	op(SUB, &[R8(Register::A), R8(Register::AT_HL)], 1, 2, 2, "Z1HC", Read, |gb| gb.sub_a_r8(Register::AT_HL)), // 0x96
+	op(SUB, &[R8(Register::A), R8(Register::A)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sub_a_r8(Register::A)), // 0x97
+	op(SBC, &[R8(Register::A), R8(Register::B)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::B)), // 0x98
+	op(SBC, &[R8(Register::A), R8(Register::C)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::C)), // 0x99
+	op(SBC, &[R8(Register::A), R8(Register::D)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::D)), // 0x9A
+	op(SBC, &[R8(Register::A), R8(Register::E)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::E)), // 0x9B
+	op(SBC, &[R8(Register::A), R8(Register::H)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::H)), // 0x9C
+	op(SBC, &[R8(Register::A), R8(Register::L)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::L)), // 0x9D
+	op(SBC, &[R8(Register::A), R8(Register::AT_HL)], 1, 2, 2, "Z1HC", Read, |gb| gb.sbc_a_r8(Register::AT_HL)), // 0x9E
+	op(SBC, &[R8(Register::A), R8(Register::A)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.sbc_a_r8(Register::A)), // 0x9F
+	op(AND, &[R8(Register::B)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::B)), // 0xA0
+	op(AND, &[R8(Register::C)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::C)), // 0xA1
+	op(AND, &[R8(Register::D)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::D)), // 0xA2
+	op(AND, &[R8(Register::E)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::E)), // 0xA3
+	op(AND, &[R8(Register::H)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::H)), // 0xA4
+	op(AND, &[R8(Register::L)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::L)), // 0xA5
+	op(AND, &[R8(Register::AT_HL)], 1, 2, 2, "Z010", Read, |gb| gb.and(Register::AT_HL)), // 0xA6
+	op(AND, &[R8(Register::A)], 1, 1, 1, "Z010", NoAccess, |gb| gb.and(Register::A)), // 0xA7
+	op(XOR, &[R8(Register::B)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::B)), // 0xA8
+	op(XOR, &[R8(Register::C)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::C)), // 0xA9
+	op(XOR, &[R8(Register::D)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::D)), // 0xAA
+	op(XOR, &[R8(Register::E)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::E)), // 0xAB
+	op(XOR, &[R8(Register::H)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::H)), // 0xAC
+	op(XOR, &[R8(Register::L)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::L)), // 0xAD
+	op(XOR, &[R8(Register::AT_HL)], 1, 2, 2, "Z000", Read, |gb| gb.xor(Register::AT_HL)), // 0xAE
+	op(XOR, &[R8(Register::A)], 1, 1, 1, "Z000", NoAccess, |gb| gb.xor(Register::A)), // 0xAF
+	op(OR, &[R8(Register::B)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::B)), // 0xB0
+	op(OR, &[R8(Register::C)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::C)), // 0xB1
+	op(OR, &[R8(Register::D)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::D)), // 0xB2
+	op(OR, &[R8(Register::E)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::E)), // 0xB3
+	op(OR, &[R8(Register::H)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::H)), // 0xB4
+	op(OR, &[R8(Register::L)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::L)), // 0xB5
+	op(OR, &[R8(Register::AT_HL)], 1, 2, 2, "Z000", Read, |gb| gb.or_r8(Register::AT_HL)), // 0xB6
+	op(OR, &[R8(Register::A)], 1, 1, 1, "Z000", NoAccess, |gb| gb.or_r8(Register::A)), // 0xB7
+	op(CP, &[R8(Register::B)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::B)), // 0xB8
+	op(CP, &[R8(Register::C)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::C)), // 0xB9
+	op(CP, &[R8(Register::D)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::D)), // 0xBA
+	op(CP, &[R8(Register::E)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::E)), // 0xBB
+	op(CP, &[R8(Register::H)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::H)), // 0xBC
+	op(CP, &[R8(Register::L)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::L)), // 0xBD
+	op(CP, &[R8(Register::AT_HL)], 1, 2, 2, "Z1HC", Read, |gb| gb.cp_r8(Register::AT_HL)), // 0xBE
+	op(CP, &[R8(Register::A)], 1, 1, 1, "Z1HC", NoAccess, |gb| gb.cp_r8(Register::A)), // 0xBF
+	op(RET, &[Cond(Conditional::NZ)], 1, 2, 5, "----", NoAccess, |gb| gb.ret_nz()), // 0xC0
+	op(POP, &[R16(RegisterPair::BC)], 1, 3, 3, "----", NoAccess, |gb| gb.pop_r16(RegisterPair::BC)), // 0xC1
+	op(JP, &[Cond(Conditional::NZ), A16], 3, 3, 4, "----", NoAccess, |gb| gb.jp_conditional(Conditional::NZ)), // 0xC2
+	op(JP, &[A16], 3, 4, 4, "----", NoAccess, |gb| gb.jp_a16()), // 0xC3
+	op(CALL, &[Cond(Conditional::NZ), A16], 3, 3, 6, "----", NoAccess, |gb| gb.call_conditional(Conditional::NZ)), // 0xC4
+	op(PUSH, &[R16(RegisterPair::BC)], 1, 4, 4, "----", NoAccess, |gb| gb.push_r16(RegisterPair::BC)), // 0xC5
+	op(ADD, &[R8(Register::A), D8], 2, 2, 2, "Z0HC", NoAccess, |gb| gb.add_d8()), // 0xC6
+	op(RST, &[Vector(0x00)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x00)), // 0xC7
+	op(RET, &[Cond(Conditional::Z)], 1, 2, 5, "----", NoAccess, |gb| gb.ret_z()), // 0xC8
+	op(RET, &[], 1, 4, 4, "----", NoAccess, |gb| gb.ret()), // 0xC9
+	op(JP, &[Cond(Conditional::Z), A16], 3, 3, 4, "----", NoAccess, |gb| gb.jp_conditional(Conditional::Z)), // 0xCA
+	op(PREFIX, &[], 2, 1, 1, "----", NoAccess, |gb| gb.extended()), // 0xCB
+	op(CALL, &[Cond(Conditional::Z), A16], 3, 3, 6, "----", NoAccess, |gb| gb.call_conditional(Conditional::Z)), // 0xCC
+	op(CALL, &[A16], 3, 6, 6, "----", NoAccess, |gb| gb.call_a16()), // 0xCD
+	op(ADC, &[R8(Register::A), D8], 2, 2, 2, "Z0HC", NoAccess, |gb| gb.adc_a_d8()), // 0xCE
+	op(RST, &[Vector(0x08)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x08)), // 0xCF
+	op(RET, &[Cond(Conditional::NC)], 1, 2, 5, "----", NoAccess, |gb| gb.ret_nc()), // 0xD0
+	op(POP, &[R16(RegisterPair::DE)], 1, 3, 3, "----", NoAccess, |gb| gb.pop_r16(RegisterPair::DE)), // 0xD1
+	op(JP, &[Cond(Conditional::NC), A16], 3, 3, 4, "----", NoAccess, |gb| gb.jp_conditional(Conditional::NC)), // 0xD2
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xD3
+	op(CALL, &[Cond(Conditional::NC), A16], 3, 3, 6, "----", NoAccess, |gb| gb.call_conditional(Conditional::NC)), // 0xD4
+	op(PUSH, &[R16(RegisterPair::DE)], 1, 4, 4, "----", NoAccess, |gb| gb.push_r16(RegisterPair::DE)), // 0xD5
+	op(SUB, &[D8], 2, 2, 2, "Z1HC", NoAccess, |gb| gb.sub_d8()), // 0xD6
+	op(RST, &[Vector(0x10)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x10)), // 0xD7
+	op(RET, &[Cond(Conditional::C)], 1, 2, 5, "----", NoAccess, |gb| gb.ret_c()), // 0xD8
+	op(RETI, &[], 1, 4, 4, "----", NoAccess, |gb| gb.reti()), // 0xD9
+	op(JP, &[Cond(Conditional::C), A16], 3, 3, 4, "----", NoAccess, |gb| gb.jp_conditional(Conditional::C)), // 0xDA
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xDB
+	op(CALL, &[Cond(Conditional::C), A16], 3, 3, 6, "----", NoAccess, |gb| gb.call_conditional(Conditional::C)), // 0xDC
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xDD
+	op(SBC, &[R8(Register::A), D8], 2, 2, 2, "Z1HC", NoAccess, |gb| gb.sbc_a_d8()), // 0xDE
+	op(RST, &[Vector(0x18)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x18)), // 0xDF
+	op(LD, &[AtFF00PlusA8, R8(Register::A)], 2, 3, 3, "----", Write, |gb| gb.ld_at_ff00_plus_a8_a()), // 0xE0
+	op(POP, &[R16(RegisterPair::HL)], 1, 3, 3, "----", NoAccess, |gb| gb.pop_r16(RegisterPair::HL)), // 0xE1
+	op(LD, &[AtFF00PlusC, R8(Register::A)], 1, 2, 2, "----", Write, |gb| gb.ld_at_ff00_plus_c_a()), // 0xE2
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xE3
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xE4
+	op(PUSH, &[R16(RegisterPair::HL)], 1, 4, 4, "----", NoAccess, |gb| gb.push_r16(RegisterPair::HL)), // 0xE5
+	op(AND, &[D8], 2, 2, 2, "Z010", NoAccess, |gb| gb.and_d8()), // 0xE6
+	op(RST, &[Vector(0x20)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x20)), // 0xE7
+	op(ADD, &[R16(RegisterPair::SP), S8], 2, 4, 4, "00HC", NoAccess, |gb| gb.add_sp_nn()), // 0xE8
+	op(JP, &[R16(RegisterPair::HL)], 1, 1, 1, "----", NoAccess, |gb| gb.jp_hl()), // 0xE9
+	op(LD, &[AtA16, R8(Register::A)], 3, 4, 4, "----", Write, |gb| gb.ld_at_a16_a()), // 0xEA
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xEB
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xEC
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xED
+	op(XOR, &[D8], 2, 2, 2, "Z000", NoAccess, |gb| gb.xor_d8()), // 0xEE
+	op(RST, &[Vector(0x28)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x28)), // 0xEF
+	op(LD, &[R8(Register::A), AtFF00PlusA8], 2, 3, 3, "----", Read, |gb| gb.ld_a_at_ff00_plus_a8()), // 0xF0
+	op(POP, &[R16(RegisterPair::AF)], 1, 3, 3, "ZNHC", NoAccess, |gb| gb.pop_af()), // 0xF1
+	op(LD, &[R8(Register::A), AtFF00PlusC], 1, 2, 2, "----", Read, |gb| gb.ld_a_at_ff00_plus_c()), // 0xF2
+	op(DI, &[], 1, 1, 1, "----", NoAccess, |gb| gb.di()), // 0xF3
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xF4
+	op(PUSH, &[R16(RegisterPair::AF)], 1, 4, 4, "----", NoAccess, |gb| gb.push_r16(RegisterPair::AF)), // 0xF5
+	op(OR, &[D8], 2, 2, 2, "Z000", NoAccess, |gb| gb.or_d8()), // 0xF6
+	op(RST, &[Vector(0x30)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x30)), // 0xF7
+	op(LD, &[R16(RegisterPair::HL), SpPlusS8], 2, 3, 3, "00HC", NoAccess, |gb| gb.ld_hl_sp_plus_nn()), // 0xF8
+	op(LD, &[R16(RegisterPair::SP), R16(RegisterPair::HL)], 1, 2, 2, "----", NoAccess, |gb| gb.ld_sp_hl()), // 0xF9
+	op(LD, &[R8(Register::A), AtA16], 3, 4, 4, "----", Read, |gb| gb.ld_a_at_a16()), // 0xFA
+	op(EI, &[], 1, 1, 1, "----", NoAccess, |gb| gb.ei()), // 0xFB
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xFC
+	op(ILLEGAL, &[], 1, 1, 1, "----", NoAccess, |gb| gb.illegal_opcode()), // 0xFD
+	op(CP, &[D8], 2, 2, 2, "Z1HC", NoAccess, |gb| gb.cp_d8()), // 0xFE
+	op(RST, &[Vector(0x38)], 1, 4, 4, "----", NoAccess, |gb| gb.rst(0x38)), // 0xFF
+];
+
+/// Extended opcodes (0xCB 0x00...0xFF)
+pub static CB_OPCODES: [OpcodeInfo; 256] = [
+	op(RLC, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::rlc)), // 0x00
+	op(RLC, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::rlc)), // 0x01
+	op(RLC, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::rlc)), // 0x02
+	op(RLC, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::rlc)), // 0x03
+	op(RLC, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::rlc)), // 0x04
+	op(RLC, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::rlc)), // 0x05
+	op(RLC, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::rlc)), // 0x06
+	op(RLC, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::rlc)), // 0x07
+	op(RRC, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::rrc)), // 0x08
+	op(RRC, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::rrc)), // 0x09
+	op(RRC, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::rrc)), // 0x0A
+	op(RRC, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::rrc)), // 0x0B
+	op(RRC, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::rrc)), // 0x0C
+	op(RRC, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::rrc)), // 0x0D
+	op(RRC, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::rrc)), // 0x0E
+	op(RRC, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::rrc)), // 0x0F
+	op(RL, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::rl)), // 0x10
+	op(RL, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::rl)), // 0x11
+	op(RL, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::rl)), // 0x12
+	op(RL, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::rl)), // 0x13
+	op(RL, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::rl)), // 0x14
+	op(RL, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::rl)), // 0x15
+	op(RL, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::rl)), // 0x16
+	op(RL, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::rl)), // 0x17
+	op(RR, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::rr)), // 0x18
+	op(RR, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::rr)), // 0x19
+	op(RR, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::rr)), // 0x1A
+	op(RR, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::rr)), // 0x1B
+	op(RR, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::rr)), // 0x1C
+	op(RR, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::rr)), // 0x1D
+	op(RR, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::rr)), // 0x1E
+	op(RR, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::rr)), // 0x1F
+	op(SLA, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::sla)), // 0x20
+	op(SLA, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::sla)), // 0x21
+	op(SLA, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::sla)), // 0x22
+	op(SLA, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::sla)), // 0x23
+	op(SLA, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::sla)), // 0x24
+	op(SLA, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::sla)), // 0x25
+	op(SLA, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::sla)), // 0x26
+	op(SLA, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::sla)), // 0x27
+	op(SRA, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::sra)), // 0x28
+	op(SRA, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::sra)), // 0x29
+	op(SRA, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::sra)), // 0x2A
+	op(SRA, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::sra)), // 0x2B
+	op(SRA, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::sra)), // 0x2C
+	op(SRA, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::sra)), // 0x2D
+	op(SRA, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::sra)), // 0x2E
+	op(SRA, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::sra)), // 0x2F
+	op(SWAP, &[R8(Register::B)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::B, alu::swap)), // 0x30
+	op(SWAP, &[R8(Register::C)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::C, alu::swap)), // 0x31
+	op(SWAP, &[R8(Register::D)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::D, alu::swap)), // 0x32
+	op(SWAP, &[R8(Register::E)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::E, alu::swap)), // 0x33
+	op(SWAP, &[R8(Register::H)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::H, alu::swap)), // 0x34
+	op(SWAP, &[R8(Register::L)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::L, alu::swap)), // 0x35
+	op(SWAP, &[R8(Register::AT_HL)], 2, 4, 4, "Z000", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::swap)), // 0x36
+	op(SWAP, &[R8(Register::A)], 2, 2, 2, "Z000", NoAccess, |gb| gb.shift_r8(Register::A, alu::swap)), // 0x37
+	op(SRL, &[R8(Register::B)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::B, alu::srl)), // 0x38
+	op(SRL, &[R8(Register::C)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::C, alu::srl)), // 0x39
+	op(SRL, &[R8(Register::D)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::D, alu::srl)), // 0x3A
+	op(SRL, &[R8(Register::E)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::E, alu::srl)), // 0x3B
+	op(SRL, &[R8(Register::H)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::H, alu::srl)), // 0x3C
+	op(SRL, &[R8(Register::L)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::L, alu::srl)), // 0x3D
+	op(SRL, &[R8(Register::AT_HL)], 2, 4, 4, "Z00C", ReadWrite, |gb| gb.shift_r8(Register::AT_HL, alu::srl)), // 0x3E
+	op(SRL, &[R8(Register::A)], 2, 2, 2, "Z00C", NoAccess, |gb| gb.shift_r8(Register::A, alu::srl)), // 0x3F
+	op(BIT, &[Bit(0), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::B)), // 0x40
+	op(BIT, &[Bit(0), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::C)), // 0x41
+	op(BIT, &[Bit(0), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::D)), // 0x42
+	op(BIT, &[Bit(0), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::E)), // 0x43
+	op(BIT, &[Bit(0), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::H)), // 0x44
+	op(BIT, &[Bit(0), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::L)), // 0x45
+	op(BIT, &[Bit(0), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(0, Register::AT_HL)), // 0x46
+	op(BIT, &[Bit(0), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(0, Register::A)), // 0x47
+	op(BIT, &[Bit(1), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::B)), // 0x48
+	op(BIT, &[Bit(1), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::C)), // 0x49
+	op(BIT, &[Bit(1), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::D)), // 0x4A
+	op(BIT, &[Bit(1), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::E)), // 0x4B
+	op(BIT, &[Bit(1), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::H)), // 0x4C
+	op(BIT, &[Bit(1), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::L)), // 0x4D
+	op(BIT, &[Bit(1), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(1, Register::AT_HL)), // 0x4E
+	op(BIT, &[Bit(1), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(1, Register::A)), // 0x4F
+	op(BIT, &[Bit(2), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::B)), // 0x50
+	op(BIT, &[Bit(2), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::C)), // 0x51
+	op(BIT, &[Bit(2), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::D)), // 0x52
+	op(BIT, &[Bit(2), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::E)), // 0x53
+	op(BIT, &[Bit(2), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::H)), // 0x54
+	op(BIT, &[Bit(2), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::L)), // 0x55
+	op(BIT, &[Bit(2), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(2, Register::AT_HL)), // 0x56
+	op(BIT, &[Bit(2), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(2, Register::A)), // 0x57
+	op(BIT, &[Bit(3), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::B)), // 0x58
+	op(BIT, &[Bit(3), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::C)), // 0x59
+	op(BIT, &[Bit(3), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::D)), // 0x5A
+	op(BIT, &[Bit(3), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::E)), // 0x5B
+	op(BIT, &[Bit(3), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::H)), // 0x5C
+	op(BIT, &[Bit(3), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::L)), // 0x5D
+	op(BIT, &[Bit(3), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(3, Register::AT_HL)), // 0x5E
+	op(BIT, &[Bit(3), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(3, Register::A)), // 0x5F
+	op(BIT, &[Bit(4), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::B)), // 0x60
+	op(BIT, &[Bit(4), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::C)), // 0x61
+	op(BIT, &[Bit(4), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::D)), // 0x62
+	op(BIT, &[Bit(4), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::E)), // 0x63
+	op(BIT, &[Bit(4), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::H)), // 0x64
+	op(BIT, &[Bit(4), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::L)), // 0x65
+	op(BIT, &[Bit(4), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(4, Register::AT_HL)), // 0x66
+	op(BIT, &[Bit(4), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(4, Register::A)), // 0x67
+	op(BIT, &[Bit(5), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::B)), // 0x68
+	op(BIT, &[Bit(5), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::C)), // 0x69
+	op(BIT, &[Bit(5), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::D)), // 0x6A
+	op(BIT, &[Bit(5), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::E)), // 0x6B
+	op(BIT, &[Bit(5), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::H)), // 0x6C
+	op(BIT, &[Bit(5), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::L)), // 0x6D
+	op(BIT, &[Bit(5), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(5, Register::AT_HL)), // 0x6E
+	op(BIT, &[Bit(5), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(5, Register::A)), // 0x6F
+	op(BIT, &[Bit(6), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::B)), // 0x70
+	op(BIT, &[Bit(6), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::C)), // 0x71
+	op(BIT, &[Bit(6), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::D)), // 0x72
+	op(BIT, &[Bit(6), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::E)), // 0x73
+	op(BIT, &[Bit(6), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::H)), // 0x74
+	op(BIT, &[Bit(6), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::L)), // 0x75
+	op(BIT, &[Bit(6), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(6, Register::AT_HL)), // 0x76
+	op(BIT, &[Bit(6), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(6, Register::A)), // 0x77
+	op(BIT, &[Bit(7), R8(Register::B)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::B)), // 0x78
+	op(BIT, &[Bit(7), R8(Register::C)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::C)), // 0x79
+	op(BIT, &[Bit(7), R8(Register::D)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::D)), // 0x7A
+	op(BIT, &[Bit(7), R8(Register::E)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::E)), // 0x7B
+	op(BIT, &[Bit(7), R8(Register::H)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::H)), // 0x7C
+	op(BIT, &[Bit(7), R8(Register::L)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::L)), // 0x7D
+	op(BIT, &[Bit(7), R8(Register::AT_HL)], 2, 3, 3, "Z01-", Read, |gb| gb.bit_r8(7, Register::AT_HL)), // 0x7E
+	op(BIT, &[Bit(7), R8(Register::A)], 2, 2, 2, "Z01-", NoAccess, |gb| gb.bit_r8(7, Register::A)), // 0x7F
+	op(RES, &[Bit(0), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::B)), // 0x80
+	op(RES, &[Bit(0), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::C)), // 0x81
+	op(RES, &[Bit(0), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::D)), // 0x82
+	op(RES, &[Bit(0), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::E)), // 0x83
+	op(RES, &[Bit(0), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::H)), // 0x84
+	op(RES, &[Bit(0), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::L)), // 0x85
+	op(RES, &[Bit(0), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(0, Register::AT_HL)), // 0x86
+	op(RES, &[Bit(0), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(0, Register::A)), // 0x87
+	op(RES, &[Bit(1), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::B)), // 0x88
+	op(RES, &[Bit(1), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::C)), // 0x89
+	op(RES, &[Bit(1), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::D)), // 0x8A
+	op(RES, &[Bit(1), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::E)), // 0x8B
+	op(RES, &[Bit(1), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::H)), // 0x8C
+	op(RES, &[Bit(1), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::L)), // 0x8D
+	op(RES, &[Bit(1), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(1, Register::AT_HL)), // 0x8E
+	op(RES, &[Bit(1), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(1, Register::A)), // 0x8F
+	op(RES, &[Bit(2), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::B)), // 0x90
+	op(RES, &[Bit(2), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::C)), // 0x91
+	op(RES, &[Bit(2), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::D)), // 0x92
+	op(RES, &[Bit(2), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::E)), // 0x93
+	op(RES, &[Bit(2), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::H)), // 0x94
+	op(RES, &[Bit(2), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::L)), // 0x95
+	op(RES, &[Bit(2), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(2, Register::AT_HL)), // 0x96
+	op(RES, &[Bit(2), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(2, Register::A)), // 0x97
+	op(RES, &[Bit(3), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::B)), // 0x98
+	op(RES, &[Bit(3), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::C)), // 0x99
+	op(RES, &[Bit(3), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::D)), // 0x9A
+	op(RES, &[Bit(3), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::E)), // 0x9B
+	op(RES, &[Bit(3), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::H)), // 0x9C
+	op(RES, &[Bit(3), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::L)), // 0x9D
+	op(RES, &[Bit(3), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(3, Register::AT_HL)), // 0x9E
+	op(RES, &[Bit(3), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(3, Register::A)), // 0x9F
+	op(RES, &[Bit(4), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::B)), // 0xA0
+	op(RES, &[Bit(4), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::C)), // 0xA1
+	op(RES, &[Bit(4), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::D)), // 0xA2
+	op(RES, &[Bit(4), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::E)), // 0xA3
+	op(RES, &[Bit(4), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::H)), // 0xA4
+	op(RES, &[Bit(4), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::L)), // 0xA5
+	op(RES, &[Bit(4), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(4, Register::AT_HL)), // 0xA6
+	op(RES, &[Bit(4), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(4, Register::A)), // 0xA7
+	op(RES, &[Bit(5), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::B)), // 0xA8
+	op(RES, &[Bit(5), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::C)), // 0xA9
+	op(RES, &[Bit(5), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::D)), // 0xAA
+	op(RES, &[Bit(5), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::E)), // 0xAB
+	op(RES, &[Bit(5), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::H)), // 0xAC
+	op(RES, &[Bit(5), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::L)), // 0xAD
+	op(RES, &[Bit(5), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(5, Register::AT_HL)), // 0xAE
+	op(RES, &[Bit(5), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(5, Register::A)), // 0xAF
+	op(RES, &[Bit(6), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::B)), // 0xB0
+	op(RES, &[Bit(6), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::C)), // 0xB1
+	op(RES, &[Bit(6), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::D)), // 0xB2
+	op(RES, &[Bit(6), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::E)), // 0xB3
+	op(RES, &[Bit(6), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::H)), // 0xB4
+	op(RES, &[Bit(6), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::L)), // 0xB5
+	op(RES, &[Bit(6), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(6, Register::AT_HL)), // 0xB6
+	op(RES, &[Bit(6), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(6, Register::A)), // 0xB7
+	op(RES, &[Bit(7), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::B)), // 0xB8
+	op(RES, &[Bit(7), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::C)), // 0xB9
+	op(RES, &[Bit(7), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::D)), // 0xBA
+	op(RES, &[Bit(7), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::E)), // 0xBB
+	op(RES, &[Bit(7), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::H)), // 0xBC
+	op(RES, &[Bit(7), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::L)), // 0xBD
+	op(RES, &[Bit(7), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.res_r8(7, Register::AT_HL)), // 0xBE
+	op(RES, &[Bit(7), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.res_r8(7, Register::A)), // 0xBF
+	op(SET, &[Bit(0), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::B)), // 0xC0
+	op(SET, &[Bit(0), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::C)), // 0xC1
+	op(SET, &[Bit(0), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::D)), // 0xC2
+	op(SET, &[Bit(0), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::E)), // 0xC3
+	op(SET, &[Bit(0), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::H)), // 0xC4
+	op(SET, &[Bit(0), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::L)), // 0xC5
+	op(SET, &[Bit(0), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(0, Register::AT_HL)), // 0xC6
+	op(SET, &[Bit(0), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(0, Register::A)), // 0xC7
+	op(SET, &[Bit(1), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::B)), // 0xC8
+	op(SET, &[Bit(1), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::C)), // 0xC9
+	op(SET, &[Bit(1), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::D)), // 0xCA
+	op(SET, &[Bit(1), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::E)), // 0xCB
+	op(SET, &[Bit(1), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::H)), // 0xCC
+	op(SET, &[Bit(1), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::L)), // 0xCD
+	op(SET, &[Bit(1), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(1, Register::AT_HL)), // 0xCE
+	op(SET, &[Bit(1), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(1, Register::A)), // 0xCF
+	op(SET, &[Bit(2), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::B)), // 0xD0
+	op(SET, &[Bit(2), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::C)), // 0xD1
+	op(SET, &[Bit(2), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::D)), // 0xD2
+	op(SET, &[Bit(2), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::E)), // 0xD3
+	op(SET, &[Bit(2), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::H)), // 0xD4
+	op(SET, &[Bit(2), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::L)), // 0xD5
+	op(SET, &[Bit(2), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(2, Register::AT_HL)), // 0xD6
+	op(SET, &[Bit(2), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(2, Register::A)), // 0xD7
+	op(SET, &[Bit(3), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::B)), // 0xD8
+	op(SET, &[Bit(3), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::C)), // 0xD9
+	op(SET, &[Bit(3), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::D)), // 0xDA
+	op(SET, &[Bit(3), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::E)), // 0xDB
+	op(SET, &[Bit(3), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::H)), // 0xDC
+	op(SET, &[Bit(3), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::L)), // 0xDD
+	op(SET, &[Bit(3), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(3, Register::AT_HL)), // 0xDE
+	op(SET, &[Bit(3), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(3, Register::A)), // 0xDF
+	op(SET, &[Bit(4), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::B)), // 0xE0
+	op(SET, &[Bit(4), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::C)), // 0xE1
+	op(SET, &[Bit(4), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::D)), // 0xE2
+	op(SET, &[Bit(4), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::E)), // 0xE3
+	op(SET, &[Bit(4), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::H)), // 0xE4
+	op(SET, &[Bit(4), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::L)), // 0xE5
+	op(SET, &[Bit(4), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(4, Register::AT_HL)), // 0xE6
+	op(SET, &[Bit(4), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(4, Register::A)), // 0xE7
+	op(SET, &[Bit(5), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::B)), // 0xE8
+	op(SET, &[Bit(5), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::C)), // 0xE9
+	op(SET, &[Bit(5), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::D)), // 0xEA
+	op(SET, &[Bit(5), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::E)), // 0xEB
+	op(SET, &[Bit(5), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::H)), // 0xEC
+	op(SET, &[Bit(5), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::L)), // 0xED
+	op(SET, &[Bit(5), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(5, Register::AT_HL)), // 0xEE
+	op(SET, &[Bit(5), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(5, Register::A)), // 0xEF
+	op(SET, &[Bit(6), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::B)), // 0xF0
+	op(SET, &[Bit(6), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::C)), // 0xF1
+	op(SET, &[Bit(6), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::D)), // 0xF2
+	op(SET, &[Bit(6), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::E)), // 0xF3
+	op(SET, &[Bit(6), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::H)), // 0xF4
+	op(SET, &[Bit(6), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::L)), // 0xF5
+	op(SET, &[Bit(6), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(6, Register::AT_HL)), // 0xF6
+	op(SET, &[Bit(6), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(6, Register::A)), // 0xF7
+	op(SET, &[Bit(7), R8(Register::B)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::B)), // 0xF8
+	op(SET, &[Bit(7), R8(Register::C)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::C)), // 0xF9
+	op(SET, &[Bit(7), R8(Register::D)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::D)), // 0xFA
+	op(SET, &[Bit(7), R8(Register::E)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::E)), // 0xFB
+	op(SET, &[Bit(7), R8(Register::H)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::H)), // 0xFC
+	op(SET, &[Bit(7), R8(Register::L)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::L)), // 0xFD
+	op(SET, &[Bit(7), R8(Register::AT_HL)], 2, 4, 4, "----", ReadWrite, |gb| gb.set_r8(7, Register::AT_HL)), // 0xFE
+	op(SET, &[Bit(7), R8(Register::A)], 2, 2, 2, "----", NoAccess, |gb| gb.set_r8(7, Register::A)), // 0xFF
+];
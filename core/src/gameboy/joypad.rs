@@ -21,8 +21,11 @@ impl Joypad {
 	}
 
 	///Keydown event
-	pub fn keydown(&mut self, key: Key) {
+	///Returns true if one of the selected joypad lines went low as a result (which requests a joypad interrupt).
+	pub fn keydown(&mut self, key: Key) -> bool {
+		let old = self.read_joyp();
 		self.keys[key as usize] = true;
+		(old & !self.read_joyp()) & 0x0F != 0
 	}
 
 	///Keyup event
@@ -30,6 +33,11 @@ impl Joypad {
 		self.keys[key as usize] = false;
 	}
 
+	///Is any of the buttons in the selected group(s) being held down?
+	pub fn selected_key_pressed(&self) -> bool {
+		self.read_joyp() & 0x0F != 0x0F
+	}
+
 	///Query the state of a button
 	pub fn key_state(&self, key: Key) -> bool {
 		self.keys[key as usize]
@@ -1,8 +1,9 @@
-use gameboy::Gameboy;
+use gameboy::{Gameboy, Mode};
 
 use gameboy::{WRAM_BANK_SIZE, WRAM_NUM_BANKS};
 use gameboy::cartridge::Cartridge;
 use gameboy::ppu::PPU;
+use gameboy::scheduler::HardwareScheduler;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MemoryRegion {
@@ -29,27 +30,58 @@ impl MemoryRegion {
 }
 
 trait MmuHelpers {
+	fn read_byte_rom(&self, offset: u16) -> u8;
+	fn selected_wram_bank(&self) -> usize;
 	fn read_byte_wram(&self, offset: u16) -> u8;
 	fn write_byte_wram(&mut self, offset: u16, value: u8);
 	fn read_byte_io(&self, offset: u16) -> u8;
 	fn write_byte_io(&mut self, offset: u16, value: u8);
+	fn sync_before_access(&mut self, region: MemoryRegion);
 }
 
 impl MmuHelpers for Gameboy {
+	/// The ppu, timer and serial port are emulated lazily (see scheduler.rs),
+	/// so they need to be caught up before the cpu can see their registers or vram/oam.
+	/// The cartridge's rtc gets the current time before its ram (or rtc registers) are accessed.
+	fn sync_before_access(&mut self, region: MemoryRegion) {
+		match region {
+			MemoryRegion::Vram | MemoryRegion::Oam | MemoryRegion::Io => self.sync_hardware(),
+			MemoryRegion::CartridgeRam => self.sync_cartridge_clock(),
+			_ => {}
+		}
+	}
+
+	/// The boot rom (if there is one) is mapped over the cartridge rom until it is disabled by writing to $FF50.
+	fn read_byte_rom(&self, offset: u16) -> u8 {
+		match self.boot_rom {
+			Some(ref boot_rom) if boot_rom.is_mapped(offset) => boot_rom.read_byte(offset),
+			_ => self.cart.read_byte_rom(offset)
+		}
+	}
+
+	/// The bank mapped to 0xD000 - 0xDFFF is selected with SVBK ($FF70) in cgb mode, a value of 0 selects bank 1.
+	/// In dmg mode it's always bank 1.
+	fn selected_wram_bank(&self) -> usize {
+		match self.svbk as usize & (WRAM_NUM_BANKS - 1) {
+			0 => 1,
+			bank => bank
+		}
+	}
+
 	fn read_byte_wram(&self, offset: u16) -> u8 {
-		let selected_wram_bank = 1;	//TODO: wram banks
+		let selected_wram_bank = self.selected_wram_bank();
 		match offset {
 			0x0000...0x0FFF => self.wram[offset as usize],
-			0x1000...0x1FFF => self.wram[(offset - 0x1000) as usize + (WRAM_BANK_SIZE * selected_wram_bank) as usize],
+			0x1000...0x1FFF => self.wram[(offset - 0x1000) as usize + WRAM_BANK_SIZE * selected_wram_bank],
 			_ => panic!("read_byte_wram - invalid offset (must be in the range [0, 0x1FFF]")
 		}
 	}
 
 	fn write_byte_wram(&mut self, offset: u16, value: u8) {
-		let selected_wram_bank = 1;	//TODO: wram banks
+		let selected_wram_bank = self.selected_wram_bank();
 		match offset {
 			0x0000...0x0FFF => self.wram[offset as usize] = value,
-			0x1000...0x1FFF => self.wram[(offset - 0x1000) as usize + (WRAM_BANK_SIZE * selected_wram_bank) as usize] = value,
+			0x1000...0x1FFF => self.wram[(offset - 0x1000) as usize + WRAM_BANK_SIZE * selected_wram_bank] = value,
 			_ => panic!("write_byte_wram - invalid offset (must be in the range [0, 0x1FFF]")
 		};
 	}
@@ -60,7 +92,12 @@ impl MmuHelpers for Gameboy {
 
 		assert!(offset <= 0x7F);
 		if let Some(register) = PpuIoRegister::map_address(offset + 0xFF00) {
-			self.ppu.read_io(register)
+			if register.cgb_only() && self.mode == Mode::DMG {
+				0xFF
+			}
+			else {
+				self.ppu.read_io(register)
+			}
 		}
 		else if let Some(register) = TimerRegister::map_address(offset + 0xFF00) {
 			self.timer.read_io(register)
@@ -72,6 +109,12 @@ impl MmuHelpers for Gameboy {
 				0x02 => self.serial.read_sc(),
 				0x0F => self.cpu.interrupt_flag.read(),
 				0x46 => self.oam_dma_state.read_ff46(),
+				0x4D if self.mode == Mode::CGB => self.cpu.read_key1(),
+				0x70 if self.mode == Mode::CGB => self.svbk | 0xF8,
+				0x50 => match self.boot_rom {
+					Some(ref boot_rom) => boot_rom.read_ff50(),
+					None => self.io[offset as usize]
+				},
 				_ => self.io[offset as usize]
 			}
 		}
@@ -84,7 +127,9 @@ impl MmuHelpers for Gameboy {
 
 		assert!(offset <= 0x7F);
 		if let Some(register) = PpuIoRegister::map_address(offset + 0xFF00) {
-			self.ppu.write_io(register, value);
+			if !(register.cgb_only() && self.mode == Mode::DMG) {
+				self.ppu.write_io(register, value);
+			}
 		}
 		else if let Some(register) = TimerRegister::map_address(offset + 0xFF00) {
 			self.timer.write_io(register, value);
@@ -96,6 +141,12 @@ impl MmuHelpers for Gameboy {
 				0x02 => self.serial.write_sc(value),
 				0x0F => self.cpu.interrupt_flag.write(value),
 				0x46 => self.start_oam_dma(value),
+				0x4D if self.mode == Mode::CGB => self.cpu.write_key1(value),
+				0x70 if self.mode == Mode::CGB => self.svbk = value & 0x07,
+				0x50 => match self.boot_rom {
+					Some(ref mut boot_rom) => boot_rom.write_ff50(value),
+					None => self.io[offset as usize] = value
+				},
 				_ => self.io[offset as usize] = value
 			};
 		}
@@ -109,7 +160,7 @@ pub trait Mmu {
 	///similar to read/write, but sometimes a coprocessor has
 	///exclusive access to a region of memory and the cpu
 	///can't read from / write to it.
-	fn read_byte_cpu(&self, address: u16) -> u8;
+	fn read_byte_cpu(&mut self, address: u16) -> u8;
 	fn write_byte_cpu(&mut self, address: u16, value: u8);
 
 	fn rom(&self) -> &[u8];
@@ -124,7 +175,7 @@ impl Mmu for Gameboy {
 		use self::MemoryRegion::*;
 		let (region, offset) = MemoryRegion::map_address(address);
 		match region {
-			CartridgeRom => self.cart.read_byte_rom(offset),
+			CartridgeRom => self.read_byte_rom(offset),
 			Vram => self.ppu.read_byte_vram(offset),
 			CartridgeRam => self.cart.read_byte_ram(offset),
 			Wram => self.read_byte_wram(offset),
@@ -156,15 +207,16 @@ impl Mmu for Gameboy {
 	///Not all memory is readable all of the time,
 	///for instance, vram and oam can't be read during certain ppu states.
 	///and the cpu can't read anything other than hram and iem during a dma transfer
-	fn read_byte_cpu(&self, address: u16) -> u8 {
+	fn read_byte_cpu(&mut self, address: u16) -> u8 {
 		use self::MemoryRegion::*;
 		if self.oam_dma_state.should_block_cpu_access(address) {
 			return 0xFF;
 		}
 		else {
 			let (region, offset) = MemoryRegion::map_address(address);
+			self.sync_before_access(region);
 			match region {
-				CartridgeRom => self.cart.read_byte_rom(offset),
+				CartridgeRom => self.read_byte_rom(offset),
 				Vram => self.ppu.read_byte_vram(offset),
 				CartridgeRam => self.cart.read_byte_ram(offset),
 				Wram => self.read_byte_wram(offset),
@@ -183,14 +235,23 @@ impl Mmu for Gameboy {
 			return;
 		}
 		let (region, offset) = MemoryRegion::map_address(address);
+		self.sync_before_access(region);
 		match region {
-			CartridgeRom => self.cart.write_byte_rom(offset, value),
+			CartridgeRom => {
+				// mbc registers can latch the rtc, this isn't done in sync_before_access to keep the clock out of opcode fetches
+				self.sync_cartridge_clock();
+				self.cart.write_byte_rom(offset, value);
+			},
 			Vram => self.ppu.write_byte_vram(offset, value),
 			CartridgeRam => self.cart.write_byte_ram(offset, value),
 			Wram => self.write_byte_wram(offset, value),
 			Oam => self.ppu.write_byte_oam(offset, value),
 			Unmapped => {},
-			Io => self.write_byte_io(offset, value),
+			Io => {
+				self.write_byte_io(offset, value);
+				// the write might have moved the next event (enabling the timer, changing lyc, ...)
+				self.scheduler.invalidate();
+			},
 			Hram => self.cpu.write_byte_hram(offset, value),
 			Ier => self.cpu.interrupt_enable.write(value)
 		};
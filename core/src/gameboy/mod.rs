@@ -9,9 +9,15 @@ pub mod debugger;
 pub mod assembly;
 mod serial;
 mod oam_dma;
+mod boot_rom;
+mod boot_state;
+mod scheduler;
 mod mode;
+mod events;
+pub mod clock;
 mod util;
 
+use std::collections::VecDeque;
 use std::error::Error;
 use std::io::BufRead;
 use std::sync::mpsc::{Sender, Receiver};
@@ -26,14 +32,20 @@ use gameboy::cpu::registers::Register;
 use gameboy::ppu::PPU;
 use gameboy::ppu::dmg_ppu::DmgPpu;
 use gameboy::timer::Timer;
-use gameboy::cartridge::{Cartridge, VirtualCartridge};
+use gameboy::cartridge::{Cartridge, CartInfo, VirtualCartridge};
+use gameboy::cartridge::gbs::{self, Gbs, GbsHeader};
 use gameboy::joypad::Joypad;
 use gameboy::debugger::{Debugger, DebuggerInterface};
 use gameboy::cpu::interrupts::Interrupt;
-use gameboy::oam_dma::{OamDmaState, OamDmaController};
+use gameboy::oam_dma::OamDmaState;
 use gameboy::serial::Serial;
+use gameboy::boot_rom::{BootRom, CGB_BOOT_ROM_SIZE};
+use gameboy::boot_state::PostBootState;
+use gameboy::scheduler::{Scheduler, HardwareScheduler};
+use gameboy::clock::Clock;
 pub use gameboy::joypad::Key;
-pub use gameboy::mode::Mode;
+pub use gameboy::mode::{Mode, Model};
+pub use gameboy::events::EmulationEvent;
 
 const IO_SIZE: usize = 128;
 
@@ -50,10 +62,20 @@ pub struct Gameboy {
 	pub cart: VirtualCartridge,
 	pub io: Box<[u8]>,
 	pub wram: Box<[u8]>,
+	/// SVBK ($FF70) - selects the wram bank mapped to 0xD000 - 0xDFFF in cgb mode (bits 0-2).
+	pub svbk: u8,
+	pub model: Model,
 	pub mode: Mode,
 	#[serde(skip)]
 	pub debugger: Debugger,
 	pub oam_dma_state: OamDmaState,
+	pub boot_rom: Option<BootRom>,
+	scheduler: Scheduler,
+	#[serde(skip)]
+	events: VecDeque<EmulationEvent>,
+	#[serde(skip, default = "clock::default_clock")]
+	clock: Box<Clock>,
+	gbs: Option<Gbs>,
 }
 
 #[derive(Debug)]
@@ -67,60 +89,119 @@ impl Error for GameboyInitializationError {}
 
 #[allow(dead_code)]
 impl Gameboy {
-	pub fn new(rom: Box<[u8]>, ram: Option<Box<[u8]>>) -> Result<Gameboy, GameboyInitializationError> {
-		let cart = VirtualCartridge::new(rom, ram).map_err(|e| GameboyInitializationError(format!("Failed to initialize cartridge: {}", e)))?;
-		let mode: Mode = match cart.get_cart_info().cgb {
-			true => Mode::CGB,
-			false => Mode::DMG,
+	/// Create a new gameboy with a cartridge rom, and optionally the contents of the cartridge ram and a boot rom.
+	/// If a boot rom is supplied, the gameboy starts executing it at 0x0000 in its power on state,
+	/// otherwise it starts at 0x0100 in the state the boot rom would have left it in.
+	/// The hardware model is picked based on the cartridge header (a cgb if the cartridge supports it, otherwise a dmg),
+	/// unless there is a boot rom, in which case it has to match the boot rom.
+	pub fn new(rom: Box<[u8]>, ram: Option<Box<[u8]>>, boot_rom: Option<Box<[u8]>>) -> Result<Gameboy, GameboyInitializationError> {
+		let cgb_boot_rom = boot_rom.as_ref().map(|boot_rom| boot_rom.len() == CGB_BOOT_ROM_SIZE);
+		let model = match (cgb_boot_rom, CartInfo::new(&rom).map(|info| info.cgb)) {
+			(Some(true), _) | (None, Ok(true)) => Model::CGB,
+			_ => Model::DMG
 		};
+		Gameboy::with_model(model, rom, ram, boot_rom)
+	}
 
-		/* initialize io memory to what it should be at the end of the bootrom if
-		   no boot rom is loaded
-		   TODO: allow the loading of a boot rom
-		 */
-		let io: [u8; IO_SIZE] = match mode {
-			Mode::DMG => {
-				[
-					0xCF, 0x00, 0x7E, 0xFF, 0x19, 0x00, 0x00, 0xF8, //ff00
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE1, //ff08
-					0x80, 0xBF, 0xF3, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, //ff10
-					0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF, //ff18
-					0xFF, 0x00, 0x00, 0xBF, 0x77, 0xF3, 0xF1, 0xFF, //ff20
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff28
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff30
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff38
-					0x91, 0x83, 0x00, 0x00, 0x01, 0x00, 0xFF, 0xFC, //ff40
-					0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, //ff48
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff50
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff58
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff60
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff68
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff70
-					0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //ff78
-				]
-			},
-			Mode::CGB => {
-				//TODO: cgb bootrom values
-				[0; IO_SIZE]
-			},
+	/// Create a new gameboy that emulates a specific hardware model.
+	/// Cgb games run in dmg mode on the dmg/mgb/sgb/sgb2, and dmg games run in compatibility mode on the cgb/agb.
+	pub fn with_model(model: Model, rom: Box<[u8]>, ram: Option<Box<[u8]>>, boot_rom: Option<Box<[u8]>>) -> Result<Gameboy, GameboyInitializationError> {
+		let cart = VirtualCartridge::new(rom, ram).map_err(|e| GameboyInitializationError(format!("Failed to initialize cartridge: {}", e)))?;
+		let boot_rom: Option<BootRom> = match boot_rom {
+			Some(boot_rom) => Some(BootRom::new(boot_rom).map_err(|e| GameboyInitializationError(format!("Failed to initialize boot rom: {}", e)))?),
+			None => None
 		};
 
-		let gameboy = Gameboy {
+		if let Some(ref boot_rom) = boot_rom {
+			if boot_rom.is_cgb() != model.is_cgb() {
+				return Err(GameboyInitializationError(format!("The boot rom can't be used with the {:?}", model)));
+			}
+		}
+
+		let mode: Mode = model.mode(cart.get_cart_info().cgb);
+
+		let mut gameboy = Gameboy {
 			cpu: CPU::new(),
-			timer: Timer::new(mode),
+			timer: Timer::new(model),
 			ppu: DmgPpu::new(),
 			serial: Serial::new(),
 			joypad: Joypad::new(),
 			cart: cart,
-			io: Box::new(io),
+			io: Box::new([0xFF; IO_SIZE]),
 			wram: Box::new([0; WRAM_BANK_SIZE * WRAM_NUM_BANKS]),
+			svbk: 0,
+			model: model,
 			mode: mode,
 			debugger: Debugger::new(),
 			oam_dma_state: OamDmaState::new(),
+			boot_rom: boot_rom,
+			scheduler: Scheduler::new(),
+			events: VecDeque::new(),
+			clock: clock::default_clock(),
+			gbs: None,
 		};
+
+		gameboy.init_hardware();
+
+		Ok(gameboy)
+	}
+
+	/// Create a gameboy that plays a GBS file (music ripped from a game) instead of running a cartridge.
+	/// The file is loaded into a pseudo cartridge with a driver that calls the file's play routine on every v-blank
+	/// or timer interrupt (see cartridge/gbs.rs), starting with the file's first song.
+	/// The hardware model is picked like it is for cartridges (a cgb if the driver runs in double speed).
+	pub fn with_gbs(gbs: &[u8]) -> Result<Gameboy, GameboyInitializationError> {
+		let load_error = |e| GameboyInitializationError(format!("Failed to load GBS file: {}", e));
+		let header = GbsHeader::new(gbs).map_err(load_error)?;
+		let rom = gbs::gbs_rom(gbs, &header).map_err(load_error)?;
+		let mut gameboy = Gameboy::new(rom, None, None)?;
+		gameboy.gbs = Some(Gbs::new(header));
+		// again, to pass the track to the driver
+		gameboy.init_hardware();
 		Ok(gameboy)
 	}
 
+	/// The header of the GBS file being played (title, author, number of songs...), None when running a cartridge.
+	pub fn gbs_header(&self) -> Option<&GbsHeader> {
+		self.gbs.as_ref().map(|gbs| &gbs.header)
+	}
+
+	/// The GBS track being played (counted from 0).
+	pub fn gbs_track(&self) -> Option<u8> {
+		self.gbs.as_ref().map(|gbs| gbs.track)
+	}
+
+	/// Start playing another track of the GBS file (counted from 0).
+	/// The gameboy is reset, so the driver calls init again with the new track.
+	pub fn select_gbs_track(&mut self, track: u8) -> Result<(), &'static str> {
+		match self.gbs {
+			Some(ref mut gbs) if track < gbs.header.songs => gbs.track = track,
+			Some(_) => return Err("The GBS file doesn't have that many songs"),
+			None => return Err("Not playing a GBS file")
+		}
+		DebuggerInterface::reset(self);
+		Ok(())
+	}
+
+	/// Put the hardware into the state it's in when the first instruction is executed:
+	/// the power on state if there is a boot rom to run, otherwise the state the boot rom would have left it in.
+	fn init_hardware(&mut self) {
+		self.svbk = 0;
+		if self.boot_rom.is_some() {
+			self.io.copy_from_slice(&[0xFF; IO_SIZE]);
+			self.cpu.power_on();
+			self.ppu.power_on();
+		}
+		else {
+			let state = PostBootState::new(self.model, self.mode, self.cart.get_cart_info(), self.cart.rom());
+			state.apply(self);
+		}
+		if let Some(ref gbs) = self.gbs {
+			// the driver passes e to init as the track number (see cartridge/gbs.rs)
+			self.cpu.registers.e = gbs.track;
+		}
+	}
+
 	pub fn emulate(&mut self, time: Duration) {
 		let clock_cycles = ((time.as_secs() * 4_194_304) + ((time.subsec_nanos() as u64 * 4_194_304) / 1_000_000_000)) as usize;
 		let mut counter = 0;
@@ -129,10 +210,17 @@ impl Gameboy {
 			self.interrupt_service_routine();
 			if self.debugger.enabled() {
 				if let Some(breakpoint) = self.breakpoint_lookahead() {
+					self.sync_hardware();
 					self.debugger.breakpoint_callback(breakpoint);
 					return;
 				}
+				let events = self.events.len();
 				self.execute();
+				if self.events.len() > events {
+					// give the debugger a chance to stop on the new event
+					self.sync_hardware();
+					return;
+				}
 			}
 			else {
 				self.execute();
@@ -145,28 +233,36 @@ impl Gameboy {
 				end - start
 			};
 		}
+
+		// catch the hardware up so the frontend sees the current frame
+		self.sync_hardware();
 	}
 
 	/// Emulate a variable number of t cycles (usually 4 at a time)
-	fn emulate_hardware(&mut self, mut t_cycles: usize) {
-		use gameboy::cpu::interrupts::InterruptLine;
-
-		while t_cycles > 0 {
-			self.service_oam_dma_transfer();
-			let mut interrupt_line = InterruptLine::new(&mut self.cpu.interrupt_flag, &mut self.cpu.halt, &mut self.cpu.stop);
-			self.timer.emulate_hardware(&mut interrupt_line);
-			self.ppu.emulate_hardware(&mut interrupt_line);
-			self.serial.emulate_hardware(&mut interrupt_line);
-			self.cpu.cycle_counter += 1;
-
-			t_cycles -= 1;
+	/// The hardware isn't actually emulated cycle by cycle, the cycles are only counted until one of
+	/// the components has an event coming up (see scheduler.rs), or until the cpu accesses one of their registers.
+	/// In double speed mode the cpu, timer, and oam dma run at twice the normal rate,
+	/// so the ppu and serial port only get stepped on every other cycle.
+	fn emulate_hardware(&mut self, t_cycles: usize) {
+		if self.cpu.stop {
+			// the clock is stopped, so nothing runs until the joypad wakes the cpu back up
+			self.skip_hardware(t_cycles);
+			return;
 		}
+
+		self.cpu.count_cycles(t_cycles);
+		if self.scheduler.due(self.cpu.cycle_counter) {
+			self.sync_hardware();
+		}
+	}
+
+	/// Get the next event that happened during emulation (if there is one), see EmulationEvent.
+	pub fn poll_event(&mut self) -> Option<EmulationEvent> {
+		self.events.pop_front()
 	}
 
 	fn request_interrupt(&mut self, req_int: Interrupt) {
 		self.cpu.interrupt_flag.request_interrupt(req_int);
-		self.cpu.stop = false;
-		self.cpu.halt = false;
 	}
 
 	///Handles interupts
@@ -179,8 +275,23 @@ impl Gameboy {
 	///3. Timer
 	///4. Serial
 	///5. Joypad
+	///Interrupts aren't serviced while the cpu is in stop mode, or after it has locked up.
+	///A halted cpu wakes up as soon as an interrupt is both requested in IF and enabled in IE, even if IME is 0.
+	///If IME is 0, waking up takes an extra M-Cycle before the next instruction is executed, if IME is 1 the
+	///wake up cycle overlaps with the first cycle of the interrupt dispatch.
 	fn interrupt_service_routine(&mut self) {
-		if self.cpu.ime {
+		if self.cpu.locked {
+			return;
+		}
+
+		if self.cpu.halt && (self.cpu.interrupt_flag.read() & self.cpu.interrupt_enable.read() & 0x1F) != 0 {
+			self.cpu.halt = false;
+			if !self.cpu.ime {
+				self.emulate_hardware(4);
+			}
+		}
+
+		if self.cpu.ime && !self.cpu.stop {
 			let interrupt_flag: u8 = self.cpu.interrupt_flag.read();
 			let interrupt_enable: u8 = self.cpu.interrupt_enable.read();
 
@@ -211,6 +322,10 @@ impl Gameboy {
 			}
 
 			if let Some(interrupt) = interrupt {
+				//The ppu can request the interrupt again on the next cycle (the coincidence interrupt is requested
+				//on every cycle while ly == lyc), so the hardware has to be synchronized again
+				self.scheduler.invalidate();
+
 				//Interrupts are enabled, so service this interrupt
 				//Nested interrupts are disabled unless the interrupt handler re enables them
 				self.cpu.next_ime_state = false;
@@ -267,9 +382,13 @@ impl Gameboy {
 		}
 	}*/
 
+	/// A joypad interrupt is only requested if the key is in one of the groups selected in JOYP,
+	/// that is also the only way to wake the cpu up from stop mode.
 	pub fn keydown(&mut self, key: Key) {
-		self.joypad.keydown(key);
-		self.request_interrupt(Interrupt::Joypad);
+		if self.joypad.keydown(key) {
+			self.request_interrupt(Interrupt::Joypad);
+			self.cpu.stop = false;
+		}
 	}
 
 	pub fn keyup(&mut self, key: Key) {
@@ -289,9 +408,63 @@ impl Gameboy {
 		self.ppu.get_frame_counter()
 	}
 
+	/// Is the cartridge's rumble motor currently on? Frontends can poll this after emulating to drive a rumble device.
+	/// Always false for cartridges without a rumble motor.
+	pub fn rumble(&self) -> bool {
+		self.cart.rumble()
+	}
+
+	/// Is the cartridge's infrared led on? The ir port isn't connected to anything,
+	/// but frontends can poll this to show when the game is transmitting.
+	pub fn ir_led(&self) -> bool {
+		self.cart.ir_led()
+	}
+
+	/// Tilt the cartridge (only MBC7 carts have an accelerometer).
+	/// x and y are the acceleration in g, from -1.0 to 1.0: x is positive when the right side is tilted down,
+	/// y is positive when the top is tilted down. Values outside of that range are clamped.
+	pub fn set_tilt(&mut self, x: f32, y: f32) {
+		self.cart.set_tilt(x, y);
+	}
+
+	/// The cartridge's battery backed ram, followed by any mapper state that is saved with it (rtc, flash...).
+	/// This is the format the ram passed to Gameboy::new and import_battery_ram is expected to be in.
+	pub fn export_battery_ram(&mut self) -> Vec<u8> {
+		// the saved rtc has to be current
+		self.sync_cartridge_clock();
+		self.cart.export_battery_ram()
+	}
+
+	/// Replace the cartridge's battery backed ram (and the mapper state saved with it).
+	pub fn import_battery_ram(&mut self, ram: &[u8]) {
+		self.cart.import_battery_ram(ram);
+	}
+
+	/// Changes every time the battery backed ram is written (or imported, or replaced by loading a state),
+	/// frontends can save it only when this is different from the last time they did.
+	pub fn battery_ram_generation(&self) -> u64 {
+		self.cart.battery_generation()
+	}
+
+	/// Set the clock the cartridge's rtc gets the time from (the real time by default).
+	/// Use a CycleClock to make the rtc deterministic, or to start it at a fixed date.
+	pub fn set_clock(&mut self, clock: Box<Clock>) {
+		self.clock = clock;
+	}
+
+	/// Pass the current time to the cartridge's rtc (if it has one), before the cpu accesses it.
+	fn sync_cartridge_clock(&mut self) {
+		if self.cart.get_cart_info().rtc {
+			let now = self.clock.now(self.cpu.normal_speed_cycles());
+			self.cart.set_time(now);
+		}
+	}
+
 	/// Create channels to handle async serial transfers.
 	pub fn create_serial_channels(&mut self) -> (Sender<u8>, Receiver<u8>) {
-		self.serial.create_channels()
+		let channels = self.serial.create_channels();
+		self.scheduler.invalidate();
+		channels
 	}
 
 	// experimental save state api
@@ -324,10 +497,36 @@ impl Gameboy {
 		// preserve serial channel connection
 		swap(&mut state.serial.channels, &mut self.serial.channels);
 
+		// preserve the clock
+		swap(&mut state.clock, &mut self.clock);
+
+		// preserve the camera's image source
+		state.cart.swap_camera_image_source(&mut self.cart);
+
+		// the battery ram was replaced, it has to look changed to frontends
+		state.cart.continue_battery_generation(&self.cart);
+
 		// preserve debugger state
 		swap(&mut state.debugger, &mut self.debugger);
 
+		// load boot rom from current state (also not included in the save state)
+		if let Some(ref mut boot_rom) = state.boot_rom {
+			match self.boot_rom {
+				Some(ref mut current) => swap(&mut boot_rom.rom, &mut current.rom),
+				None if boot_rom.mapped() => {
+					let message = "The save state was made while the boot rom was running, it can only be loaded with a boot rom";
+					return Err(Box::new(bincode::ErrorKind::Custom(String::from(message))));
+				},
+				None => {}
+			}
+		}
+		if self.boot_rom.is_none() {
+			// the boot rom had already finished, without one a reset starts from the post boot state
+			state.boot_rom = None;
+		}
+
 		*self = state;
+		self.scheduler.invalidate();
 		Ok(())
 	}
 }
@@ -1,9 +1,68 @@
+use std::str::FromStr;
+
 const DMG_MODE: u8 = 0;
 const CGB_MODE: u8 = 1;
 
+/// The mode the hardware is running the game in.
+/// A cgb runs games that don't support the cgb in dmg mode (compatibility mode).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Mode {
 	DMG = DMG_MODE, CGB = CGB_MODE
 }
+
+/// The hardware model that is being emulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub enum Model {
+	/// Early dmg revision (only released in japan).
+	DMG0,
+	DMG,
+	/// Game Boy Pocket/Light
+	MGB,
+	/// Super Game Boy
+	SGB,
+	/// Super Game Boy 2
+	SGB2,
+	CGB,
+	/// Game Boy Advance (running gameboy games)
+	AGB
+}
+
+impl Model {
+	/// Can this model run games in cgb mode?
+	pub fn is_cgb(&self) -> bool {
+		match *self {
+			Model::CGB | Model::AGB => true,
+			_ => false
+		}
+	}
+
+	/// The mode a game runs in on this model, depending on whether or not the cartridge supports the cgb.
+	pub fn mode(&self, cgb_cartridge: bool) -> Mode {
+		if cgb_cartridge && self.is_cgb() {
+			Mode::CGB
+		}
+		else {
+			Mode::DMG
+		}
+	}
+}
+
+impl FromStr for Model {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Model, &'static str> {
+		match s.to_lowercase().as_str() {
+			"dmg0" => Ok(Model::DMG0),
+			"dmg" => Ok(Model::DMG),
+			"mgb" => Ok(Model::MGB),
+			"sgb" => Ok(Model::SGB),
+			"sgb2" => Ok(Model::SGB2),
+			"cgb" => Ok(Model::CGB),
+			"agb" => Ok(Model::AGB),
+			_ => Err("Unknown hardware model (must be one of dmg0, dmg, mgb, sgb, sgb2, cgb, agb)")
+		}
+	}
+}
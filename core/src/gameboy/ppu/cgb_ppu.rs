@@ -33,6 +33,13 @@ impl PPU for CgbPpu {
 		//TODO
 	}
 
+	/// Nothing is emulated yet, so the scheduler always steps it one clock at a time.
+	fn cycles_until_event(&self) -> usize {
+		0
+	}
+
+	fn skip(&mut self, _cycles: usize, _interrupt_line: &mut InterruptLine) {}
+
 	fn read_io(&self, _reg: PpuIoRegister) -> u8 {
 		panic!("unimplemented");
 	}
@@ -1,6 +1,7 @@
+use std::cmp;
 use std::num::Wrapping;
 
-use super::{PPU, VRAM_BANK_SIZE, VRAM_NUM_BANKS_DMG, OAM_SIZE, WIDTH, HEIGHT, PpuMode, Bitmap, PpuIoRegister, TileDataAddress, Sprite, SpritePalette, SpritePriority, framebuffer::FrameBuffer};
+use super::{PPU, VRAM_BANK_SIZE, VRAM_NUM_BANKS_DMG, OAM_SIZE, PALETTE_RAM_SIZE, WIDTH, HEIGHT, PpuMode, Bitmap, PpuIoRegister, TileDataAddress, Sprite, SpritePalette, SpritePriority, framebuffer::FrameBuffer};
 use gameboy::cpu::interrupts::{Interrupt, InterruptLine};
 
 /* RGBA shades for dmg */
@@ -41,6 +42,12 @@ pub struct DmgPpu {
 	obp0: u8,
 	obp1: u8,
 
+	/* cgb palette ram (8 palettes of 4 15-bit colors each, little endian) */
+	bgpi: u8,
+	bg_palette_ram: Box<[u8]>, //[u8; PALETTE_RAM_SIZE]
+	obpi: u8,
+	obj_palette_ram: Box<[u8]>, //[u8; PALETTE_RAM_SIZE]
+
 	//MMIO Registers
 	//pub lcdc: u8,	//0xFF40
 	//pub stat: u8,	//0xFF41
@@ -97,8 +104,32 @@ impl DmgPpu {
 			wy: 0,
 			bgp: 0xFC,
 			obp0: 0xFF,
-			obp1: 0xFF
+			obp1: 0xFF,
+
+			bgpi: 0,
+			bg_palette_ram: Box::new([0; PALETTE_RAM_SIZE]),
+			obpi: 0,
+			obj_palette_ram: Box::new([0; PALETTE_RAM_SIZE])
+		}
+	}
+
+	/// Reset the ppu to the state it is in when the gameboy is powered on.
+	/// The lcd is disabled until the boot rom turns it on.
+	pub fn power_on(&mut self) {
+		self.reset();
+		self.lcdc = 0;
+		self.bgp = 0;
+		self.obp0 = 0;
+		self.obp1 = 0;
+	}
+
+	/// Blank the screen while the lcd is turned off (when the cpu is in stop mode on the dmg).
+	pub fn blank_screen(&mut self) {
+		let white = self.shades[0];
+		for pixel in self.buffers.get_front_buffer_mut().iter_mut() {
+			*pixel = white;
 		}
+		self.frame_counter += 1;
 	}
 
 	fn draw_scanline(&mut self) {
@@ -378,6 +409,10 @@ impl PPU for DmgPpu {
 			Bgp => self.bgp,
 			Obp0 => self.obp0,
 			Obp1 => self.obp1,
+			Bgpi => self.bgpi | 0x40, /* bit 6 is unused */
+			Bgpd => self.bg_palette_ram[(self.bgpi & 0x3F) as usize],
+			Obpi => self.obpi | 0x40,
+			Obpd => self.obj_palette_ram[(self.obpi & 0x3F) as usize],
 			_ => 0xFF
 		}
 	}
@@ -401,6 +436,16 @@ impl PPU for DmgPpu {
 			Bgp => self.bgp = value,
 			Obp0 => self.obp0 = value,
 			Obp1 => self.obp1 = value,
+			Bgpi => self.bgpi = value & 0xBF,
+			Bgpd => {
+				self.bg_palette_ram[(self.bgpi & 0x3F) as usize] = value;
+				self.bgpi = increment_palette_index(self.bgpi);
+			},
+			Obpi => self.obpi = value & 0xBF,
+			Obpd => {
+				self.obj_palette_ram[(self.obpi & 0x3F) as usize] = value;
+				self.obpi = increment_palette_index(self.obpi);
+			},
 			_ => {}
 		}
 	}
@@ -492,6 +537,36 @@ impl PPU for DmgPpu {
 		}
 	}
 
+	fn cycles_until_event(&self) -> usize {
+		if self.lcdc & 128 == 0 {
+			return usize::max_value();
+		}
+
+		// the mode changes on the clock that takes the clock past the end of the current mode
+		let mode_length = match self.mode {
+			PpuMode::HBLANK => 228,
+			PpuMode::VBLANK => 456,
+			PpuMode::SEARCH_OAM => 76,
+			PpuMode::TRANSFER_TO_LCD => 152
+		};
+		mode_length - cmp::min(self.clock as usize, mode_length)
+	}
+
+	fn skip(&mut self, cycles: usize, interrupt_line: &mut InterruptLine) {
+		if self.lcdc & 128 == 0 || cycles == 0 {
+			return;
+		}
+
+		self.clock += cycles as u32;
+		if self.lyc == self.line {
+			// the coincidence interrupt is requested on every clock while ly == lyc, requesting it once covers all of them
+			if self.lyc_interrupt_enable {
+				interrupt_line.request_interrupt(Interrupt::LcdStat);
+			}
+			self.coincidence_flag = true;
+		}
+	}
+
 	///Read a byte from the vram as the cpu.
 	///When the ppu is in mode 3, the cpu can't access vram, so 0xFF is returned instead
 	fn read_byte_vram(&self, offset: u16) -> u8 {
@@ -653,3 +728,13 @@ impl PPU for DmgPpu {
 
 	}
 }
+
+/// Writing to BGPD/OBPD increments the palette index if auto increment (bit 7) is set.
+fn increment_palette_index(index: u8) -> u8 {
+	if index & 0x80 == 0x80 {
+		0x80 | (index.wrapping_add(1) & 0x3F)
+	}
+	else {
+		index
+	}
+}
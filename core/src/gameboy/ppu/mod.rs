@@ -8,6 +8,7 @@ pub const VRAM_BANK_SIZE: usize = 8192;
 pub const VRAM_NUM_BANKS_CGB: usize = 2;
 pub const VRAM_NUM_BANKS_DMG: usize = 1;
 pub const OAM_SIZE: usize = 160;
+pub const PALETTE_RAM_SIZE: usize = 64;
 
 pub const COINCIDENCE_INTERRUPT_ENABLE_MASK: u8 = 64;
 pub const OAM_INTERUPT_ENABLE_MASK: u8 = 32;
@@ -173,6 +174,15 @@ impl PpuIoRegister {
 		}
 	}
 
+	/// Registers that only exist in cgb mode (they read as 0xFF and ignore writes in dmg mode).
+	pub fn cgb_only(&self) -> bool {
+		use self::PpuIoRegister::*;
+		match *self {
+			Bgpi | Bgpd | Obpi | Obpd | Vbk => true,
+			_ => false
+		}
+	}
+
 	pub fn map_address(address: u16) -> Option<PpuIoRegister> {
 		use self::PpuIoRegister::*;
 		match address {
@@ -221,6 +231,13 @@ pub trait PPU : Send {
 	///Emulate the ppu for 1 M-Cycle (4 Clocks)
 	fn emulate_hardware(&mut self, interrupt_line: &mut InterruptLine);
 
+	/// How many clocks the ppu can skip before it changes modes or lines.
+	fn cycles_until_event(&self) -> usize;
+
+	/// Skip a number of clocks that doesn't reach the next event (see cycles_until_event).
+	/// Interrupts that are requested on every clock of a mode or line (the coincidence interrupt) are requested here.
+	fn skip(&mut self, cycles: usize, interrupt_line: &mut InterruptLine);
+
 	fn reset(&mut self);
 
 	///Gets a pointer to the framebuffer, which is an 160*144 RGBA array of u32's that represents
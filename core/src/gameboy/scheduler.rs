@@ -0,0 +1,139 @@
+use std::cmp;
+
+use gameboy::Gameboy;
+use gameboy::cpu::interrupts::InterruptLine;
+use gameboy::oam_dma::OamDmaController;
+use gameboy::ppu::PPU;
+
+/// Keeps track of how far the timer, ppu, serial port and oam dma controller have been emulated,
+/// and when they next have to be emulated cycle by cycle.
+///
+/// Most of the time these components are just counting (the ppu clock, the divider, the serial bit clock),
+/// which can be done in one big step. Each component reports how many cycles it can skip before its next event
+/// (an interrupt request, a ppu mode change, a serial bit being shifted out, ...), and the hardware is only
+/// synchronized when the cpu reaches that event, or when the cpu touches one of the components' registers.
+#[derive(Serialize, Deserialize)]
+pub struct Scheduler {
+	/// The value of the cycle counter that the components have been emulated up to.
+	last_sync: usize,
+
+	/// The first cycle in which one of the components might do something other than count.
+	/// The components have to be synchronized once the cycle counter has gone past it.
+	next_event: usize
+}
+
+impl Scheduler {
+	pub fn new() -> Scheduler {
+		Scheduler {
+			last_sync: 0,
+			next_event: 0
+		}
+	}
+
+	/// Drop any cycles that haven't been emulated yet, and start scheduling again from `cycle`.
+	pub fn reset(&mut self, cycle: usize) {
+		self.last_sync = cycle;
+		self.next_event = cycle;
+	}
+
+	/// Have the components fallen behind far enough that they have to be synchronized?
+	pub fn due(&self, cycle: usize) -> bool {
+		cycle > self.next_event
+	}
+
+	/// Force the components to be synchronized on the next cycle.
+	/// This needs to be called whenever the state of a component is changed by something other than the scheduler
+	/// (writing to one of its registers for example), because the next event might have moved.
+	pub fn invalidate(&mut self) {
+		self.next_event = self.last_sync;
+	}
+}
+
+pub trait HardwareScheduler {
+	/// Catch the timer, ppu, serial port and oam dma controller up to the current cycle,
+	/// and schedule the next event.
+	fn sync_hardware(&mut self);
+
+	/// Advance the cycle counter without emulating any of the components (the clock is stopped).
+	fn skip_hardware(&mut self, t_cycles: usize);
+}
+
+trait SchedulerHelpers {
+	fn cycles_until_event(&self, cycle: usize) -> usize;
+	fn step_cycle(&mut self, cycle: usize);
+	fn skip_cycles(&mut self, cycle: usize, t_cycles: usize);
+}
+
+impl SchedulerHelpers for Gameboy {
+	/// How many cycles, starting at `cycle`, can be skipped before one of the components has to be stepped.
+	/// In double speed mode the ppu and serial port are only stepped on even cycles,
+	/// so they can skip twice as many cycles (plus one if the first cycle is odd).
+	fn cycles_until_event(&self, cycle: usize) -> usize {
+		if self.oam_dma_state.active {
+			// the oam dma controller shares the bus with the cpu, so it's always emulated cycle by cycle
+			return 0;
+		}
+
+		let timer = self.timer.cycles_until_event();
+		let ppu = cmp::min(self.ppu.cycles_until_event(), self.serial.cycles_until_event());
+		let ppu = if self.cpu.double_speed_mode {
+			ppu.saturating_mul(2).saturating_add(cycle & 1)
+		}
+		else {
+			ppu
+		};
+		cmp::min(timer, ppu)
+	}
+
+	/// Emulate a single cycle of all of the components.
+	fn step_cycle(&mut self, cycle: usize) {
+		self.service_oam_dma_transfer();
+		let mut interrupt_line = InterruptLine::new(&mut self.cpu.interrupt_flag);
+		self.timer.emulate_hardware(&mut interrupt_line);
+		if !self.cpu.double_speed_mode || cycle & 1 == 0 {
+			self.ppu.emulate_hardware(&mut interrupt_line);
+			self.serial.emulate_hardware(&mut interrupt_line);
+		}
+	}
+
+	/// Skip a number of cycles where none of the components have any events.
+	fn skip_cycles(&mut self, cycle: usize, t_cycles: usize) {
+		self.timer.skip(t_cycles);
+		let ppu_cycles = if self.cpu.double_speed_mode {
+			// number of even cycles in [cycle, cycle + t_cycles)
+			(t_cycles + 1 - (cycle & 1)) / 2
+		}
+		else {
+			t_cycles
+		};
+		let mut interrupt_line = InterruptLine::new(&mut self.cpu.interrupt_flag);
+		self.ppu.skip(ppu_cycles, &mut interrupt_line);
+		self.serial.skip(ppu_cycles);
+	}
+}
+
+impl HardwareScheduler for Gameboy {
+	fn sync_hardware(&mut self) {
+		let now = self.cpu.cycle_counter;
+		while self.scheduler.last_sync < now {
+			let cycle = self.scheduler.last_sync;
+			let skip = cmp::min(now - cycle, self.cycles_until_event(cycle));
+			if skip == 0 {
+				self.step_cycle(cycle);
+				self.scheduler.last_sync += 1;
+			}
+			else {
+				self.skip_cycles(cycle, skip);
+				self.scheduler.last_sync += skip;
+			}
+		}
+		self.scheduler.next_event = now.saturating_add(self.cycles_until_event(now));
+	}
+
+	fn skip_hardware(&mut self, t_cycles: usize) {
+		self.sync_hardware();
+		self.cpu.count_cycles(t_cycles);
+		self.scheduler.last_sync = self.cpu.cycle_counter;
+		self.scheduler.next_event = self.cpu.cycle_counter.saturating_add(self.cycles_until_event(self.cpu.cycle_counter));
+	}
+}
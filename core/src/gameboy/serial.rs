@@ -1,3 +1,4 @@
+use std::cmp;
 use std::sync::mpsc::{Sender, Receiver, channel};
 use ::gameboy::cpu::interrupts::{InterruptLine, Interrupt};
 
@@ -104,6 +105,27 @@ impl Serial {
 		(input_send, output_recv)
 	}
 
+	/// How many cycles the serial port can skip before the next bit is shifted out.
+	/// When a device is connected, it can start a transfer at any time, so it has to be polled every cycle.
+	pub fn cycles_until_event(&self) -> usize {
+		if self.channels.is_some() {
+			0
+		}
+		else if self.sc & 0x81 == 0x81 {
+			64 - cmp::min(self.current_bit_cycles, 64)
+		}
+		else {
+			usize::max_value()
+		}
+	}
+
+	/// Skip a number of cycles that doesn't reach the next event (see cycles_until_event).
+	pub fn skip(&mut self, cycles: usize) {
+		if self.sc & 0x81 == 0x81 {
+			self.current_bit_cycles += cycles;
+		}
+	}
+
 	/// Emulate the serial port behaviour for 1 cycle.
 	/// TODO: different transfer speeds for CGB mode.
 	pub fn emulate_hardware(&mut self, interrupt_line: &mut InterruptLine) {
@@ -1,5 +1,5 @@
 use gameboy::cpu::interrupts::{Interrupt, InterruptLine};
-use gameboy::Mode;
+use gameboy::Model;
 
 const FREQ: [u16; 4] = [512, 8, 32, 128];
 
@@ -50,7 +50,7 @@ impl TimerRegister {
 ///             3: CPU Clock / 256
 #[derive(Serialize, Deserialize)]
 pub struct Timer {
-	model: Mode,
+	model: Model,
 
 	/// Divider register (DIV) - incremented every cpu clock (4MHz)
 	/// the high 8-bits of DIV are mapped to memory at address 0xFF04.
@@ -80,7 +80,7 @@ pub struct Timer {
 }
 
 impl Timer {
-	pub fn new(model: Mode) -> Timer {
+	pub fn new(model: Model) -> Timer {
 		Timer {
 			model: model,
 			div: 0,
@@ -133,6 +133,31 @@ impl Timer {
 		}
 	}
 
+	/// How many cycles the timer can skip before tima overflows (or before it gets reloaded if it has already overflowed).
+	pub fn cycles_until_event(&self) -> usize {
+		if self.tima_overflow_delay.is_some() {
+			0
+		}
+		else if self.tac & 4 == 0 {
+			usize::max_value()
+		}
+		else {
+			// tima is incremented when div becomes a multiple of twice the frequency bit
+			let period = (FREQ[(self.tac & 3) as usize] as usize) * 2;
+			let first_increment = period - (self.div as usize % period);
+			first_increment + (0xFF - self.tima as usize) * period - 1
+		}
+	}
+
+	/// Skip a number of cycles that doesn't reach the next event (see cycles_until_event).
+	pub fn skip(&mut self, t_cycles: usize) {
+		if self.tac & 4 == 4 {
+			let period = (FREQ[(self.tac & 3) as usize] as usize) * 2;
+			self.tima += ((self.div as usize % period + t_cycles) / period) as u16;
+		}
+		self.div = self.div.wrapping_add(t_cycles as u16);
+	}
+
 	/// Read from one of the timers memory mapped io registers.
 	pub fn read_io(&self, reg: TimerRegister) -> u8 {
 		use self::TimerRegister::*;
@@ -167,7 +192,8 @@ impl Timer {
 			Tma => self.tma = value,
 			Tac => {
 				match self.model {
-					Mode::DMG => {
+					Model::CGB | Model::AGB => self.tac = value,
+					Model::DMG0 | Model::DMG | Model::MGB | Model::SGB | Model::SGB2 => {
 						// On early models there is a bug that causes the timer to sometimes increment on writes to TAC.
 						// More specifically, if the value of tac.enable & (div & freq) goes from high to low as a result
 						// of the write to TAC, then the timer register is incremented.
@@ -184,7 +210,6 @@ impl Timer {
 						}
 						self.tac = value;
 					}
-					Mode::CGB => self.tac = value
 				}
 			}
 		};
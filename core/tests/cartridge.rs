@@ -0,0 +1,800 @@
+extern crate agb_core;
+
+mod common;
+
+use std::time::Duration;
+
+use agb_core::gameboy::Gameboy;
+use agb_core::gameboy::cartridge::{CartInfo, MBCType};
+use agb_core::gameboy::cartridge::camera::{self, ImageSource, PHOTO_SIZE, SENSOR_WIDTH, SENSOR_HEIGHT};
+use agb_core::gameboy::clock::{Clock, CycleClock};
+use agb_core::gameboy::debugger::DebuggerInterface;
+
+use common::{cartridge, cartridge_with_cgb_flag, execute, store};
+
+const CART_MBC1: u8 = 0x01;
+const CART_MMM01_RAM_BATTERY: u8 = 0x0D;
+const CART_MBC3_TIMER_RAM_BATTERY: u8 = 0x10;
+const CART_MBC5_RAM_BATTERY: u8 = 0x1B;
+const CART_MBC6: u8 = 0x20;
+const CART_MBC7: u8 = 0x22;
+const CART_CAMERA: u8 = 0xFC;
+const CART_TAMA5: u8 = 0xFD;
+const CART_HUC3: u8 = 0xFE;
+const RAM_SIZE_NONE: u8 = 0x00;
+const RAM_SIZE_8KB: u8 = 0x02;
+const RAM_SIZE_32KB: u8 = 0x03;
+const RAM_SIZE_128KB: u8 = 0x04;
+
+/// A clock that is always at the same time.
+struct FixedClock(i64);
+
+impl Clock for FixedClock {
+	#[allow(unused_variables)]
+	fn now(&self, cycle_counter: usize) -> i64 {
+		self.0
+	}
+}
+
+#[test]
+fn battery_generation_only_changes_with_the_ram() {
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC5_RAM_BATTERY, RAM_SIZE_8KB), None, None).unwrap();
+	let generation = gameboy.battery_ram_generation();
+
+	// the ram is disabled
+	store(&mut gameboy, 0xA000, 0x12);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+
+	// bank switching
+	store(&mut gameboy, 0x0000, 0x0A);
+	store(&mut gameboy, 0x4000, 0x00);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+
+	// the byte is already 0
+	store(&mut gameboy, 0xA000, 0x00);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+
+	store(&mut gameboy, 0xA000, 0x12);
+	assert!(gameboy.battery_ram_generation() > generation);
+	let generation = gameboy.battery_ram_generation();
+	store(&mut gameboy, 0xA000, 0x12);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+	assert_eq!(gameboy.read_memory(0xA000), 0x12);
+}
+
+/// Execute a HuC3 rtc command (the cartridge has to be in rtc command mode).
+fn huc3_command(gameboy: &mut Gameboy, command: u8, argument: u8) {
+	store(gameboy, 0xA000, (command << 4) | argument);
+}
+
+#[test]
+fn huc3_rtc_is_saved_with_the_ram() {
+	const START: i64 = 1_500_000_000;
+	let mut gameboy = Gameboy::new(cartridge(CART_HUC3, RAM_SIZE_32KB), None, None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START)));
+
+	// write a nibble of the rtc memory (address 0x10), and check that it counts as a change to the save
+	store(&mut gameboy, 0x0000, 0x0B);
+	let generation = gameboy.battery_ram_generation();
+	huc3_command(&mut gameboy, 0x4, 0x0);
+	huc3_command(&mut gameboy, 0x5, 0x1);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+	huc3_command(&mut gameboy, 0x3, 0x9);
+	assert!(gameboy.battery_ram_generation() > generation);
+
+	// 90 minutes later
+	gameboy.set_clock(Box::new(FixedClock(START + 90 * 60)));
+	let save = gameboy.export_battery_ram();
+
+	// a day after the save
+	let mut gameboy = Gameboy::new(cartridge(CART_HUC3, RAM_SIZE_32KB), Some(save.into_boxed_slice()), None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START + 90 * 60 + 86400)));
+	store(&mut gameboy, 0x0000, 0x0B);
+	huc3_command(&mut gameboy, 0x6, 0x0); // copy the time to the rtc memory
+
+	let mut nibbles = Vec::new();
+	huc3_command(&mut gameboy, 0x4, 0x0);
+	huc3_command(&mut gameboy, 0x5, 0x0);
+	for _ in 0..7 {
+		store(&mut gameboy, 0x0000, 0x0B);
+		huc3_command(&mut gameboy, 0x1, 0x0);
+		store(&mut gameboy, 0x0000, 0x0C);
+		nibbles.push(gameboy.read_memory(0xA000) & 0x0F);
+	}
+	// 90 minutes (0x05A) and 1 day (0x0001), low nibble first
+	assert_eq!(nibbles, vec![0xA, 0x5, 0x0, 0x1, 0x0, 0x0, 0x0]);
+
+	store(&mut gameboy, 0x0000, 0x0B);
+	huc3_command(&mut gameboy, 0x4, 0x0);
+	huc3_command(&mut gameboy, 0x5, 0x1);
+	huc3_command(&mut gameboy, 0x1, 0x0);
+	store(&mut gameboy, 0x0000, 0x0C);
+	assert_eq!(gameboy.read_memory(0xA000) & 0x0F, 0x9);
+}
+
+/// Latch the MBC3's rtc and read its registers (seconds, minutes, hours, days low, days high).
+fn mbc3_rtc(gameboy: &mut Gameboy) -> [u8; 5] {
+	store(gameboy, 0x6000, 0x00);
+	store(gameboy, 0x6000, 0x01);
+	mbc3_rtc_registers(gameboy)
+}
+
+/// Read the MBC3's rtc registers without latching them.
+fn mbc3_rtc_registers(gameboy: &mut Gameboy) -> [u8; 5] {
+	let mut registers = [0; 5];
+	for (i, register) in registers.iter_mut().enumerate() {
+		store(gameboy, 0x4000, 0x08 + i as u8);
+		*register = gameboy.read_memory(0xA000);
+	}
+	registers
+}
+
+#[test]
+fn cycle_clock_runs_at_the_same_speed_in_double_speed_mode() {
+	const CYCLES_PER_SECOND: usize = 4_194_304;
+	let mut gameboy = Gameboy::new(cartridge_with_cgb_flag(CART_MBC3_TIMER_RAM_BATTERY, RAM_SIZE_8KB, 0x80), None, None).unwrap();
+	gameboy.set_clock(Box::new(CycleClock::new(1_500_000_000)));
+
+	// set the rtc to 2 seconds before day 511 ends
+	store(&mut gameboy, 0x0000, 0x0A);
+	for (register, value) in [58, 59, 23, 0xFF, 0x01].iter().enumerate() {
+		store(&mut gameboy, 0x4000, 0x08 + register as u8);
+		store(&mut gameboy, 0xA000, *value);
+	}
+	assert_eq!(mbc3_rtc(&mut gameboy), [58, 59, 23, 0xFF, 0x01]);
+
+	// switch to double speed
+	execute(&mut gameboy, &[0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00]); // ld a, 1; ldh [KEY1], a; stop
+	assert_eq!(gameboy.read_memory(0xFF4D) & 0x80, 0x80);
+
+	// 3 seconds is twice as many cycles in double speed mode
+	execute(&mut gameboy, &[]); // loop without switching the speed again
+	let start = gameboy.get_cycle_counter();
+	gameboy.emulate(Duration::from_secs(3));
+	assert!(gameboy.get_cycle_counter() - start >= 6 * CYCLES_PER_SECOND);
+
+	// the days wrapped around, so the day carry bit (bit 7 of days high) is set
+	assert_eq!(mbc3_rtc(&mut gameboy), [1, 0, 0, 0x00, 0x80]);
+}
+
+#[test]
+fn mbc3_rtc_is_saved_with_the_ram() {
+	const START: i64 = 1_500_000_000;
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC3_TIMER_RAM_BATTERY, RAM_SIZE_8KB), None, None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START)));
+
+	// day 511, 23:59:30
+	store(&mut gameboy, 0x0000, 0x0A);
+	for (register, value) in [30, 59, 23, 0xFF, 0x01].iter().enumerate() {
+		store(&mut gameboy, 0x4000, 0x08 + register as u8);
+		store(&mut gameboy, 0xA000, *value);
+	}
+
+	// latch 10 seconds later, and save 10 seconds after that
+	gameboy.set_clock(Box::new(FixedClock(START + 10)));
+	assert_eq!(mbc3_rtc(&mut gameboy), [40, 59, 23, 0xFF, 0x01]);
+	gameboy.set_clock(Box::new(FixedClock(START + 20)));
+	let save = gameboy.export_battery_ram();
+
+	// the latched registers are restored
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC3_TIMER_RAM_BATTERY, RAM_SIZE_8KB), Some(save.into_boxed_slice()), None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START + 20)));
+	store(&mut gameboy, 0x0000, 0x0A);
+	assert_eq!(mbc3_rtc_registers(&mut gameboy), [40, 59, 23, 0xFF, 0x01]);
+
+	// the clock runs for the 15 seconds after the save, and the day counter overflows
+	gameboy.set_clock(Box::new(FixedClock(START + 35)));
+	assert_eq!(mbc3_rtc(&mut gameboy), [5, 0, 0, 0x00, 0x80]);
+}
+
+#[test]
+fn rtc_saved_at_timestamp_0_catches_up() {
+	// a cycle clock started at 0 reads 0 during the first second
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC3_TIMER_RAM_BATTERY, RAM_SIZE_8KB), None, None).unwrap();
+	gameboy.set_clock(Box::new(CycleClock::new(0)));
+	store(&mut gameboy, 0x0000, 0x0A);
+	assert_eq!(mbc3_rtc(&mut gameboy), [0, 0, 0, 0x00, 0x00]);
+	let save = gameboy.export_battery_ram();
+
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC3_TIMER_RAM_BATTERY, RAM_SIZE_8KB), Some(save.into_boxed_slice()), None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(90)));
+	store(&mut gameboy, 0x0000, 0x0A);
+	assert_eq!(mbc3_rtc(&mut gameboy), [30, 1, 0, 0x00, 0x00]);
+}
+
+/* MBC7 eeprom commands (the address goes in the low bits of READ, WRITE and ERASE) */
+const EEPROM_READ: u16 = 0b10 << 8;
+const EEPROM_WRITE: u16 = 0b01 << 8;
+const EEPROM_ERASE: u16 = 0b11 << 8;
+const EEPROM_EWEN: u16 = 0b0011 << 6;
+const EEPROM_EWDS: u16 = 0b0000 << 6;
+const EEPROM_ERAL: u16 = 0b0010 << 6;
+const EEPROM_WRAL: u16 = 0b0001 << 6;
+
+/// An MBC7 cart with its registers (accelerometer and eeprom) enabled.
+fn mbc7() -> Gameboy {
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC7, 0x00), None, None).unwrap();
+	store(&mut gameboy, 0x0000, 0x0A);
+	store(&mut gameboy, 0x4000, 0x40);
+	gameboy
+}
+
+/// Shift a bit into the MBC7's eeprom (on the rising edge of the clock), and return the data out bit after it.
+fn eeprom_clock(gameboy: &mut Gameboy, bit: bool) -> bool {
+	store(gameboy, 0xA080, 0x80 | ((bit as u8) << 1));
+	store(gameboy, 0xA080, 0xC0 | ((bit as u8) << 1));
+	gameboy.read_memory(0xA080) & 1 != 0
+}
+
+/// Send a start bit, a 10 bit command and the data bits (if the command has any), then deselect the eeprom.
+fn eeprom_command(gameboy: &mut Gameboy, command: u16, data: Option<u16>) {
+	store(gameboy, 0xA080, 0x00);
+	eeprom_clock(gameboy, true);
+	for bit in (0..10).rev() {
+		eeprom_clock(gameboy, command & (1 << bit) != 0);
+	}
+	if let Some(data) = data {
+		for bit in (0..16).rev() {
+			eeprom_clock(gameboy, data & (1 << bit) != 0);
+		}
+	}
+	store(gameboy, 0xA080, 0x00);
+}
+
+/// Read words from the eeprom, starting at an address (reading continues with the next address).
+fn eeprom_read(gameboy: &mut Gameboy, address: u8, words: usize) -> Vec<u16> {
+	store(gameboy, 0xA080, 0x00);
+	eeprom_clock(gameboy, true);
+	let command = EEPROM_READ | address as u16;
+	let mut data_out = true;
+	for bit in (0..10).rev() {
+		data_out = eeprom_clock(gameboy, command & (1 << bit) != 0);
+	}
+	assert!(!data_out, "the dummy bit before the data should be 0");
+	let mut values = Vec::new();
+	for _ in 0..words {
+		let mut value = 0;
+		for _ in 0..16 {
+			value = (value << 1) | eeprom_clock(gameboy, false) as u16;
+		}
+		values.push(value);
+	}
+	store(gameboy, 0xA080, 0x00);
+	values
+}
+
+#[test]
+fn mbc7_eeprom() {
+	let mut gameboy = mbc7();
+	let initial = eeprom_read(&mut gameboy, 0x05, 1)[0];
+
+	// writes are disabled until EWEN
+	let generation = gameboy.battery_ram_generation();
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x05, Some(initial ^ 0xFFFF));
+	eeprom_command(&mut gameboy, EEPROM_ERAL, None);
+	assert_eq!(eeprom_read(&mut gameboy, 0x05, 1), vec![initial]);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+
+	eeprom_command(&mut gameboy, EEPROM_EWEN, None);
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x05, Some(0x1234));
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x06, Some(0xABCD));
+	assert!(gameboy.battery_ram_generation() > generation);
+	// reading continues with the next word, and wraps around after the last one
+	assert_eq!(eeprom_read(&mut gameboy, 0x05, 2), vec![0x1234, 0xABCD]);
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x7F, Some(0x5678));
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x00, Some(0x9ABC));
+	assert_eq!(eeprom_read(&mut gameboy, 0x7F, 2), vec![0x5678, 0x9ABC]);
+	// only the lower 7 bits of the address are used
+	assert_eq!(eeprom_read(&mut gameboy, 0x85, 1), vec![0x1234]);
+	// the words are little endian in the save
+	assert_eq!(&gameboy.export_battery_ram()[0x0A..0x0E], &[0x34, 0x12, 0xCD, 0xAB]);
+
+	eeprom_command(&mut gameboy, EEPROM_ERASE | 0x05, None);
+	assert_eq!(eeprom_read(&mut gameboy, 0x05, 2), vec![0xFFFF, 0xABCD]);
+
+	eeprom_command(&mut gameboy, EEPROM_WRAL, Some(0x0F0F));
+	assert_eq!(eeprom_read(&mut gameboy, 0x00, 1), vec![0x0F0F]);
+	assert_eq!(eeprom_read(&mut gameboy, 0x7F, 1), vec![0x0F0F]);
+
+	eeprom_command(&mut gameboy, EEPROM_ERAL, None);
+	assert_eq!(gameboy.export_battery_ram(), vec![0xFF; 256]);
+
+	// EWDS disables writes again
+	eeprom_command(&mut gameboy, EEPROM_EWDS, None);
+	let generation = gameboy.battery_ram_generation();
+	eeprom_command(&mut gameboy, EEPROM_WRITE | 0x01, Some(0x0000));
+	eeprom_command(&mut gameboy, EEPROM_ERASE | 0x01, None);
+	eeprom_command(&mut gameboy, EEPROM_WRAL, Some(0x0000));
+	assert_eq!(eeprom_read(&mut gameboy, 0x01, 1), vec![0xFFFF]);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+}
+
+/// Read the latched accelerometer values (x, y).
+fn mbc7_accelerometer(gameboy: &mut Gameboy) -> (u16, u16) {
+	let word = |gameboy: &mut Gameboy, low: u16| gameboy.read_memory(low) as u16 | (gameboy.read_memory(low + 0x10) as u16) << 8;
+	(word(gameboy, 0xA020), word(gameboy, 0xA040))
+}
+
+/// Erase the latched accelerometer values, then latch the current ones.
+fn mbc7_latch(gameboy: &mut Gameboy) {
+	store(gameboy, 0xA000, 0x55);
+	store(gameboy, 0xA010, 0xAA);
+}
+
+#[test]
+fn mbc7_accelerometer_latch() {
+	let mut gameboy = mbc7();
+	assert_eq!(gameboy.read_memory(0xA060), 0x00);
+	assert_eq!(gameboy.read_memory(0xA070), 0xFF);
+
+	// erasing sets both values to 0x8000
+	store(&mut gameboy, 0xA000, 0x55);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x8000, 0x8000));
+	store(&mut gameboy, 0xA010, 0xAA);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0, 0x81D0));
+
+	// the values only change when they're latched, and they can only be latched after being erased
+	gameboy.set_tilt(0.5, -0.5);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0, 0x81D0));
+	store(&mut gameboy, 0xA010, 0xAA);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0, 0x81D0));
+	mbc7_latch(&mut gameboy);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0 + 0x38, 0x81D0 - 0x38));
+
+	// the accelerometer saturates at 1g
+	gameboy.set_tilt(5.0, -::std::f32::INFINITY);
+	mbc7_latch(&mut gameboy);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0 + 0x70, 0x81D0 - 0x70));
+	gameboy.set_tilt(::std::f32::NAN, 1.0);
+	mbc7_latch(&mut gameboy);
+	assert_eq!(mbc7_accelerometer(&mut gameboy), (0x81D0, 0x81D0 + 0x70));
+
+	// the registers are disabled without both ram enables
+	store(&mut gameboy, 0x4000, 0x00);
+	assert_eq!(gameboy.read_memory(0xA020), 0xFF);
+	assert_eq!(gameboy.read_memory(0xA060), 0xFF);
+}
+
+/* MBC6 flash commands, written to 0x5555 after the unlock sequence */
+const FLASH_ERASE: u8 = 0x80;
+const FLASH_ID: u8 = 0x90;
+const FLASH_PROGRAM: u8 = 0xA0;
+const FLASH_PROTECT: u8 = 0x60;
+
+/// An MBC6 cartridge with the flash enabled and writable.
+fn mbc6(save: Option<Vec<u8>>) -> Gameboy {
+	let mut gameboy = Gameboy::new(cartridge(CART_MBC6, RAM_SIZE_32KB), save.map(|save| save.into_boxed_slice()), None).unwrap();
+	store(&mut gameboy, 0x0C00, 0x01);
+	store(&mut gameboy, 0x1000, 0x01);
+	store(&mut gameboy, 0x2800, 0x08);
+	store(&mut gameboy, 0x3800, 0x08);
+	gameboy
+}
+
+/// Write 0xAA to 0x5555 and 0x55 to 0x2AAA in the flash (mapped to window A and B).
+fn flash_unlock(gameboy: &mut Gameboy) {
+	store(gameboy, 0x2000, 0x02);
+	store(gameboy, 0x3000, 0x01);
+	store(gameboy, 0x5555, 0xAA);
+	store(gameboy, 0x6AAA, 0x55);
+}
+
+fn flash_command(gameboy: &mut Gameboy, command: u8) {
+	flash_unlock(gameboy);
+	store(gameboy, 0x5555, command);
+}
+
+/// Write to an address in the flash through window A.
+fn flash_write(gameboy: &mut Gameboy, address: usize, value: u8) {
+	store(gameboy, 0x2000, (address / 0x2000) as u8);
+	store(gameboy, 0x4000 + (address % 0x2000) as u16, value);
+}
+
+fn flash_read(gameboy: &mut Gameboy, address: usize) -> u8 {
+	store(gameboy, 0x2000, (address / 0x2000) as u8);
+	gameboy.read_memory(0x4000 + (address % 0x2000) as u16)
+}
+
+fn flash_program(gameboy: &mut Gameboy, address: usize, value: u8) {
+	flash_command(gameboy, FLASH_PROGRAM);
+	flash_write(gameboy, address, value);
+}
+
+fn flash_erase_sector(gameboy: &mut Gameboy, address: usize) {
+	flash_command(gameboy, FLASH_ERASE);
+	flash_unlock(gameboy);
+	flash_write(gameboy, address, 0x30);
+}
+
+fn flash_erase_chip(gameboy: &mut Gameboy) {
+	flash_command(gameboy, FLASH_ERASE);
+	flash_command(gameboy, 0x10);
+}
+
+/// Protect (0x40) or unprotect (0x20) the sector containing address.
+fn flash_protect(gameboy: &mut Gameboy, address: usize, protect: u8) {
+	flash_command(gameboy, FLASH_PROTECT);
+	flash_unlock(gameboy);
+	flash_write(gameboy, address, protect);
+}
+
+#[test]
+fn mbc6_flash_program_and_erase() {
+	let mut gameboy = mbc6(None);
+	assert_eq!(flash_read(&mut gameboy, 0x20010), 0xFF);
+
+	// programming can only clear bits
+	let generation = gameboy.battery_ram_generation();
+	flash_program(&mut gameboy, 0x20010, 0x5A);
+	assert!(gameboy.battery_ram_generation() > generation);
+	assert_eq!(flash_read(&mut gameboy, 0x20010), 0x5A);
+	flash_program(&mut gameboy, 0x20010, 0x0F);
+	assert_eq!(flash_read(&mut gameboy, 0x20010), 0x0A);
+
+	// writes without a command don't change anything
+	flash_write(&mut gameboy, 0x20011, 0x00);
+	assert_eq!(flash_read(&mut gameboy, 0x20011), 0xFF);
+
+	// nothing is programmed while the flash isn't writable
+	store(&mut gameboy, 0x1000, 0x00);
+	flash_program(&mut gameboy, 0x20011, 0x00);
+	assert_eq!(flash_read(&mut gameboy, 0x20011), 0xFF);
+	store(&mut gameboy, 0x1000, 0x01);
+
+	// a sector erase only erases the 128KiB sector it's written to
+	flash_program(&mut gameboy, 0x00000, 0x12);
+	flash_program(&mut gameboy, 0x3FFFF, 0x34);
+	flash_erase_sector(&mut gameboy, 0x3FFFF);
+	assert_eq!(flash_read(&mut gameboy, 0x00000), 0x12);
+	assert_eq!(flash_read(&mut gameboy, 0x20010), 0xFF);
+	assert_eq!(flash_read(&mut gameboy, 0x3FFFF), 0xFF);
+
+	// and a chip erase erases everything
+	flash_program(&mut gameboy, 0xFFFFF, 0x56);
+	flash_erase_chip(&mut gameboy);
+	assert_eq!(flash_read(&mut gameboy, 0x00000), 0xFF);
+	assert_eq!(flash_read(&mut gameboy, 0xFFFFF), 0xFF);
+}
+
+#[test]
+fn mbc6_flash_id_mode() {
+	let mut gameboy = mbc6(None);
+	flash_program(&mut gameboy, 0x20000, 0x00);
+	flash_command(&mut gameboy, FLASH_ID);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0xC2);
+	assert_eq!(flash_read(&mut gameboy, 0x20001), 0x81);
+	assert_eq!(flash_read(&mut gameboy, 0x20002), 0x00);
+
+	// 0xF0 goes back to reading the flash
+	flash_write(&mut gameboy, 0x20000, 0xF0);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0x00);
+	assert_eq!(flash_read(&mut gameboy, 0x20001), 0xFF);
+}
+
+#[test]
+fn mbc6_flash_protect() {
+	let mut gameboy = mbc6(None);
+	flash_program(&mut gameboy, 0x20000, 0x12);
+	flash_program(&mut gameboy, 0x40000, 0x34);
+	let generation = gameboy.battery_ram_generation();
+	flash_protect(&mut gameboy, 0x20000, 0x40);
+	assert!(gameboy.battery_ram_generation() > generation);
+
+	flash_command(&mut gameboy, FLASH_ID);
+	assert_eq!(flash_read(&mut gameboy, 0x20002), 0x01);
+	assert_eq!(flash_read(&mut gameboy, 0x40002), 0x00);
+	flash_write(&mut gameboy, 0x00000, 0xF0);
+
+	// the protected sector can't be programmed or erased, a chip erase skips it
+	flash_program(&mut gameboy, 0x20001, 0x00);
+	flash_erase_sector(&mut gameboy, 0x20000);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0x12);
+	assert_eq!(flash_read(&mut gameboy, 0x20001), 0xFF);
+	flash_erase_chip(&mut gameboy);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0x12);
+	assert_eq!(flash_read(&mut gameboy, 0x40000), 0xFF);
+
+	// the protection is saved with the flash
+	let save = gameboy.export_battery_ram();
+	let mut gameboy = mbc6(Some(save));
+	flash_erase_sector(&mut gameboy, 0x20000);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0x12);
+
+	// until it's unprotected
+	flash_protect(&mut gameboy, 0x20000, 0x20);
+	flash_erase_sector(&mut gameboy, 0x20000);
+	assert_eq!(flash_read(&mut gameboy, 0x20000), 0xFF);
+}
+
+/// A 128KiB MMM01 multicart: a game's header at the start, and the menu in the last 32KiB.
+/// The byte at 0x2000 of each 16KiB bank is the bank's number.
+fn mmm01_rom() -> Box<[u8]> {
+	let mut rom = vec![0; 0x20000];
+	rom[..0x8000].copy_from_slice(&cartridge(CART_MBC1, RAM_SIZE_NONE));
+	rom[0x18000..].copy_from_slice(&cartridge(CART_MMM01_RAM_BATTERY, RAM_SIZE_32KB));
+	for bank in 0..8 {
+		rom[bank * 0x4000 + 0x2000] = bank as u8;
+	}
+	rom.into_boxed_slice()
+}
+
+/// The banks mapped to 0x0000 - 0x3FFF and 0x4000 - 0x7FFF.
+fn mmm01_banks(gameboy: &mut Gameboy) -> (u8, u8) {
+	(gameboy.read_memory(0x2000), gameboy.read_memory(0x6000))
+}
+
+#[test]
+fn mmm01_header_is_read_from_the_menu() {
+	let info = CartInfo::new(&mmm01_rom()).unwrap();
+	match info.mbc_type {
+		MBCType::MMM01 => {},
+		mbc_type => panic!("expected an MMM01 cart, got {:?}", mbc_type)
+	}
+	assert!(info.battery);
+	assert_eq!(info.rom_size, 0x20000);
+	assert_eq!(info.ram_size, 0x8000);
+}
+
+#[test]
+fn mmm01_maps_a_game() {
+	let mut gameboy = Gameboy::new(mmm01_rom(), None, None).unwrap();
+
+	// the menu is in banks 0x1FE and 0x1FF, which wrap around to the end of the rom
+	assert_eq!(mmm01_banks(&mut gameboy), (6, 7));
+	store(&mut gameboy, 0x2000, 0x01);
+	assert_eq!(mmm01_banks(&mut gameboy), (6, 7));
+
+	// the game is in banks 4 - 7: rom bank 4, with bit 2 of the rom bank locked
+	store(&mut gameboy, 0x2000, 0x04);
+	store(&mut gameboy, 0x6000, 0x04 << 1);
+	store(&mut gameboy, 0x0000, 0x40 | 0x0A); // map, enable the ram
+	assert_eq!(mmm01_banks(&mut gameboy), (4, 5));
+
+	// the game can only change the unlocked bits, and bank 0 of the game can't be mapped to 0x4000
+	store(&mut gameboy, 0x2000, 0x03);
+	assert_eq!(mmm01_banks(&mut gameboy), (4, 7));
+	store(&mut gameboy, 0x2000, 0x00);
+	assert_eq!(mmm01_banks(&mut gameboy), (4, 5));
+
+	// the mapping and mask can't be changed after the game is mapped
+	store(&mut gameboy, 0x0000, 0x00);
+	store(&mut gameboy, 0x6000, 0x3C);
+	store(&mut gameboy, 0x2000, 0x02);
+	assert_eq!(mmm01_banks(&mut gameboy), (4, 6));
+
+	// without the mode write protect the game can switch to ram banking mode
+	store(&mut gameboy, 0x0000, 0x0A);
+	store(&mut gameboy, 0x4000, 0x01);
+	store(&mut gameboy, 0xA000, 0x11);
+	store(&mut gameboy, 0x6000, 0x01);
+	store(&mut gameboy, 0xA000, 0x22);
+	let ram = gameboy.export_battery_ram();
+	assert_eq!((ram[0x0000], ram[0x2000]), (0x11, 0x22));
+}
+
+#[test]
+fn mmm01_ram_bank_mask_and_mode_lock() {
+	let mut gameboy = Gameboy::new(mmm01_rom(), None, None).unwrap();
+
+	// ram bank 1 in ram banking mode, with bit 0 of the ram bank and the mode locked
+	store(&mut gameboy, 0x4000, 0x40 | 0x01);
+	store(&mut gameboy, 0x6000, 0x01);
+	store(&mut gameboy, 0x0000, 0x40 | (0x01 << 4) | 0x0A);
+	store(&mut gameboy, 0xA000, 0x11);
+
+	// only bit 1 of the ram bank can be changed
+	store(&mut gameboy, 0x4000, 0x02);
+	store(&mut gameboy, 0xA000, 0x33);
+	store(&mut gameboy, 0x4000, 0x00);
+	assert_eq!(gameboy.read_memory(0xA000), 0x11);
+
+	// and the mode can't be changed
+	store(&mut gameboy, 0x6000, 0x00);
+	assert_eq!(gameboy.read_memory(0xA000), 0x11);
+
+	let ram = gameboy.export_battery_ram();
+	assert_eq!((ram[0x0000], ram[0x2000], ram[0x6000]), (0x00, 0x11, 0x33));
+}
+
+/* TAMA5 registers, selected through 0xA001 */
+const TAMA5_ROM_BANK_LOW: u8 = 0x0;
+const TAMA5_ROM_BANK_HIGH: u8 = 0x1;
+const TAMA5_DATA_LOW: u8 = 0x4;
+const TAMA5_DATA_HIGH: u8 = 0x5;
+const TAMA5_COMMAND: u8 = 0x6;
+const TAMA5_ADDRESS_LOW: u8 = 0x7;
+const TAMA5_READY: u8 = 0xA;
+const TAMA5_RESULT_LOW: u8 = 0xC;
+const TAMA5_RESULT_HIGH: u8 = 0xD;
+
+/* TAMA5 commands */
+const TAMA5_RAM_WRITE: u8 = 0x0;
+const TAMA5_RAM_READ: u8 = 0x1;
+const TAMA5_RTC: u8 = 0x2;
+const TAMA5_RTC_PAGE: u8 = 0x4;
+
+/// Write a nibble to one of the TAMA5's registers.
+fn tama5_write(gameboy: &mut Gameboy, register: u8, value: u8) {
+	store(gameboy, 0xA001, register);
+	store(gameboy, 0xA000, value);
+}
+
+fn tama5_read(gameboy: &mut Gameboy, register: u8) -> u8 {
+	store(gameboy, 0xA001, register);
+	gameboy.read_memory(0xA000)
+}
+
+/// Execute a command (writing the low address bits executes it), and return the result registers.
+fn tama5_command(gameboy: &mut Gameboy, command: u8, address: u8, data: u8) -> u8 {
+	tama5_write(gameboy, TAMA5_DATA_LOW, data & 0x0F);
+	tama5_write(gameboy, TAMA5_DATA_HIGH, data >> 4);
+	tama5_write(gameboy, TAMA5_COMMAND, (command << 1) | (address >> 4));
+	tama5_write(gameboy, TAMA5_ADDRESS_LOW, address & 0x0F);
+	((tama5_read(gameboy, TAMA5_RESULT_HIGH) & 0x0F) << 4) | (tama5_read(gameboy, TAMA5_RESULT_LOW) & 0x0F)
+}
+
+/// Write nibbles of the rtc's timer page (or the alarm page).
+fn tama5_write_page(gameboy: &mut Gameboy, alarm: bool, nibbles: &[(u8, u8)]) {
+	for &(index, value) in nibbles {
+		tama5_command(gameboy, TAMA5_RTC_PAGE, (alarm as u8) << 1, index | (value << 4));
+	}
+}
+
+/// Read the 14 nibbles of the rtc's timer page (or the alarm page).
+fn tama5_read_page(gameboy: &mut Gameboy, alarm: bool) -> Vec<u8> {
+	(0..14).map(|index| tama5_command(gameboy, TAMA5_RTC_PAGE, ((alarm as u8) << 1) | 1, index)).collect()
+}
+
+#[test]
+fn tama5_registers_and_ram() {
+	let mut rom = cartridge(CART_TAMA5, RAM_SIZE_NONE);
+	rom[0x2000] = 0xAA;
+	rom[0x6000] = 0xBB;
+	let mut gameboy = Gameboy::new(rom, None, None).unwrap();
+	assert_eq!(tama5_read(&mut gameboy, TAMA5_READY), 0xF1);
+	assert_eq!(gameboy.read_memory(0xA001), 0xFF);
+
+	// the rom bank is 5 bits, split over two registers
+	assert_eq!(gameboy.read_memory(0x6000), 0xAA);
+	tama5_write(&mut gameboy, TAMA5_ROM_BANK_LOW, 0x01);
+	assert_eq!(gameboy.read_memory(0x6000), 0xBB);
+	tama5_write(&mut gameboy, TAMA5_ROM_BANK_HIGH, 0x01);
+	assert_eq!(gameboy.read_memory(0x6000), 0xFF);
+
+	// the ram is written and read through the data and result registers
+	let generation = gameboy.battery_ram_generation();
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RAM_WRITE, 0x15, 0x5A), 0x00);
+	assert!(gameboy.battery_ram_generation() > generation);
+	let generation = gameboy.battery_ram_generation();
+	tama5_command(&mut gameboy, TAMA5_RAM_WRITE, 0x15, 0x5A);
+	assert_eq!(gameboy.battery_ram_generation(), generation);
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RAM_READ, 0x15, 0x00), 0x5A);
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RAM_READ, 0x14, 0x00), 0x00);
+	assert_eq!(gameboy.export_battery_ram()[0x15], 0x5A);
+}
+
+#[test]
+fn tama5_rtc_is_saved_with_the_ram() {
+	const START: i64 = 1_500_000_000;
+	let mut gameboy = Gameboy::new(cartridge(CART_TAMA5, RAM_SIZE_NONE), None, None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START)));
+
+	// 28th of february (year 3), 23:59:50
+	let generation = gameboy.battery_ram_generation();
+	tama5_write_page(&mut gameboy, false, &[(0x0, 0), (0x1, 5), (0x2, 9), (0x3, 5), (0x4, 3), (0x5, 2), (0x7, 8), (0x8, 2), (0x9, 2), (0xB, 3)]);
+	assert!(gameboy.battery_ram_generation() > generation);
+	assert_eq!(tama5_read_page(&mut gameboy, false), [0, 5, 9, 5, 3, 2, 0, 8, 2, 2, 0, 3, 0, 0x8]);
+
+	// 20 seconds later it's the 1st of march
+	gameboy.set_clock(Box::new(FixedClock(START + 20)));
+	assert_eq!(tama5_read_page(&mut gameboy, false), [0, 1, 0, 0, 0, 0, 1, 1, 0, 3, 0, 3, 0, 0x8]);
+
+	// the hour and minute can also be read and written as BCD
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RTC, 0x07, 0x00), 0x00);
+	tama5_command(&mut gameboy, TAMA5_RTC, 0x05, 0x12);
+	tama5_command(&mut gameboy, TAMA5_RTC, 0x04, 0x34);
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RTC, 0x07, 0x00), 0x12);
+	assert_eq!(tama5_command(&mut gameboy, TAMA5_RTC, 0x06, 0x00), 0x34);
+
+	// the alarm goes off at 12:35
+	tama5_write_page(&mut gameboy, true, &[(0x2, 5), (0x3, 3), (0x4, 2), (0x5, 1)]);
+	tama5_command(&mut gameboy, TAMA5_RTC, 0x11, 0x00);
+	assert_eq!(tama5_read_page(&mut gameboy, true), [0, 0, 5, 3, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0xC]);
+	gameboy.set_clock(Box::new(FixedClock(START + 60)));
+	assert_eq!(tama5_read_page(&mut gameboy, true)[0xD], 0xC);
+	gameboy.set_clock(Box::new(FixedClock(START + 80)));
+	assert_eq!(tama5_read_page(&mut gameboy, true)[0xD], 0xD);
+	let save = gameboy.export_battery_ram();
+	assert_eq!(save.len(), 32 + 18);
+
+	// an hour after the save, the alarm is still set
+	let mut gameboy = Gameboy::new(cartridge(CART_TAMA5, RAM_SIZE_NONE), Some(save.into_boxed_slice()), None).unwrap();
+	gameboy.set_clock(Box::new(FixedClock(START + 80 + 3600)));
+	assert_eq!(tama5_read_page(&mut gameboy, false), [0, 1, 5, 3, 3, 1, 1, 1, 0, 3, 0, 3, 0, 0xD]);
+	assert_eq!(tama5_read_page(&mut gameboy, true), [0, 0, 5, 3, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0xD]);
+
+	// disabling the alarm clears it
+	tama5_command(&mut gameboy, TAMA5_RTC, 0x10, 0x00);
+	assert_eq!(tama5_read_page(&mut gameboy, true)[0xD], 0x8);
+}
+
+/// Columns of 8 pixels that are 0, 100, 200 and 255 bright, repeating.
+struct Stripes;
+
+impl ImageSource for Stripes {
+	fn capture(&mut self) -> Box<[u8]> {
+		(0..SENSOR_WIDTH * SENSOR_HEIGHT).map(|i| [0, 100, 200, 255][(i % SENSOR_WIDTH / 8) % 4]).collect::<Vec<u8>>().into_boxed_slice()
+	}
+}
+
+#[test]
+fn camera_capture() {
+	let mut gameboy = Gameboy::new(cartridge(CART_CAMERA, RAM_SIZE_128KB), None, None).unwrap();
+	gameboy.cart.set_camera_image_source(Box::new(Stripes)).unwrap();
+	store(&mut gameboy, 0x4000, 0x10); // map the registers
+
+	// the lowest gain with the nominal exposure scales the sensor values by 0.88: 0, 88, 176 and 224
+	store(&mut gameboy, 0xA001, 0x00);
+	store(&mut gameboy, 0xA002, 0x10);
+	store(&mut gameboy, 0xA003, 0x00);
+	store(&mut gameboy, 0xA004, 0x00);
+
+	// the dither matrix splits the values evenly, except that odd columns are always white,
+	// and every 4th row is always black
+	for y in 0..4 {
+		for x in 0..4 {
+			let thresholds = match (x % 2, y) {
+				(_, 3) => [0xFF, 0xFF, 0xFF],
+				(1, _) => [0x00, 0x00, 0x00],
+				_ => [0x40, 0x80, 0xC0]
+			};
+			for (i, threshold) in thresholds.iter().enumerate() {
+				store(&mut gameboy, 0xA006 + ((y * 4 + x) * 3 + i) as u16, *threshold);
+			}
+		}
+	}
+
+	let generation = gameboy.battery_ram_generation();
+	store(&mut gameboy, 0xA000, 0x01);
+	assert!(gameboy.battery_ram_generation() > generation);
+	assert_eq!(gameboy.read_memory(0xA000), 0x00);
+
+	// each column of tiles is one shade (black to white), in the even columns of rows 0 - 2 of every 4
+	let tiles: [[u8; 16]; 4] = [
+		[0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xFF, 0xFF, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xFF, 0xFF],
+		[0x00, 0xAA, 0x00, 0xAA, 0x00, 0xAA, 0xFF, 0xFF, 0x00, 0xAA, 0x00, 0xAA, 0x00, 0xAA, 0xFF, 0xFF],
+		[0xAA, 0x00, 0xAA, 0x00, 0xAA, 0x00, 0xFF, 0xFF, 0xAA, 0x00, 0xAA, 0x00, 0xAA, 0x00, 0xFF, 0xFF],
+		[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF]
+	];
+	let ram = gameboy.export_battery_ram();
+	for (tile, data) in ram[0x0100..0x0100 + PHOTO_SIZE].chunks(16).enumerate() {
+		assert_eq!(data, &tiles[tile % 4][..], "tile {}", tile);
+	}
+}
+
+#[test]
+fn camera_saved_photos_are_in_album_order() {
+	const STATE_VECTOR: usize = 0x11B2;
+	let mut ram = vec![0; 0x20000];
+	for byte in ram[STATE_VECTOR..STATE_VECTOR + camera::PHOTO_SLOTS].iter_mut() {
+		*byte = 0xFF;
+	}
+
+	// slot 0 is white and third in the album, slot 3 is black and first, slot 7 is light gray and second
+	for &(slot, position, low, high) in [(0, 2, 0x00, 0x00), (3, 0, 0xFF, 0xFF), (7, 1, 0xFF, 0x00)].iter() {
+		ram[STATE_VECTOR + slot] = position;
+		let address = 0x2000 + slot * 0x1000;
+		for pair in ram[address..address + PHOTO_SIZE].chunks_mut(2) {
+			pair[0] = low;
+			pair[1] = high;
+		}
+	}
+
+	let photos = camera::saved_photos(&ram);
+	let shades: Vec<u32> = photos.iter().map(|photo| photo.data[0]).collect();
+	assert_eq!(shades, [0x000000FF, 0xAAAAAAFF, 0xFFFFFFFF]);
+	assert!(photos.iter().all(|photo| photo.data.iter().all(|pixel| *pixel == photo.data[0])));
+
+	// a ram that's too small for the album has no photos
+	assert!(camera::saved_photos(&ram[..0x10000]).is_empty());
+}
@@ -0,0 +1,46 @@
+// each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use agb_core::gameboy::Gameboy;
+use agb_core::gameboy::cartridge::NINTENDO_LOGO;
+use agb_core::gameboy::debugger::DebuggerInterface;
+
+/// Where the test roms' entry point jumps to, in wram.
+pub const CODE: u16 = 0xC000;
+
+/// A 32KiB rom with a valid header, whose entry point jumps to CODE.
+pub fn cartridge(cart_type: u8, ram_size: u8) -> Box<[u8]> {
+	cartridge_with_cgb_flag(cart_type, ram_size, 0x00)
+}
+
+pub fn cartridge_with_cgb_flag(cart_type: u8, ram_size: u8, cgb_flag: u8) -> Box<[u8]> {
+	let mut rom = vec![0; 0x8000];
+	rom[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, CODE as u8, (CODE >> 8) as u8]); // nop; jp CODE
+	rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+	rom[0x0143] = cgb_flag;
+	rom[0x0147] = cart_type;
+	rom[0x0149] = ram_size;
+	rom[0x014D] = rom[0x0134..0x014D].iter().fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+	rom.into_boxed_slice()
+}
+
+/// Step until the cpu gets to CODE.
+pub fn run_to_code(gameboy: &mut Gameboy) {
+	while gameboy.get_registers().pc != CODE {
+		gameboy.debug_step();
+	}
+}
+
+/// Run code from wram, writes to the cartridge have to be done by the cpu to reach the memory bank controller
+/// (the debugger's write_memory patches the rom instead).
+pub fn execute(gameboy: &mut Gameboy, code: &[u8]) {
+	run_to_code(gameboy);
+	gameboy.write_range(CODE, code);
+	gameboy.write_range(CODE + code.len() as u16, &[0xC3, CODE as u8, (CODE >> 8) as u8]); // jp CODE
+	gameboy.debug_step();
+	run_to_code(gameboy);
+}
+
+pub fn store(gameboy: &mut Gameboy, address: u16, value: u8) {
+	execute(gameboy, &[0x3E, value, 0xEA, address as u8, (address >> 8) as u8]); // ld a, value; ld [address], a
+}
@@ -0,0 +1,56 @@
+extern crate agb_core;
+
+use std::time::Duration;
+
+use agb_core::gameboy::Gameboy;
+use agb_core::gameboy::debugger::DebuggerInterface;
+
+/// A GBS file with 3 songs, whose init stores the track number at 0xC100.
+fn gbs() -> Vec<u8> {
+	let mut gbs = vec![0; 0x70];
+	gbs[0x00..0x03].copy_from_slice(b"GBS");
+	gbs[0x03] = 1; // version
+	gbs[0x04] = 3; // songs
+	gbs[0x05] = 2; // first song
+	gbs[0x06..0x08].copy_from_slice(&[0x00, 0x04]); // load address
+	gbs[0x08..0x0A].copy_from_slice(&[0x00, 0x04]); // init address
+	gbs[0x0A..0x0C].copy_from_slice(&[0x04, 0x04]); // play address
+	gbs[0x0C..0x0E].copy_from_slice(&[0xFE, 0xFF]); // stack pointer
+	gbs[0x10..0x14].copy_from_slice(b"Test");
+	gbs.extend_from_slice(&[0xEA, 0x00, 0xC1, 0xC9]); // init: ld [0xC100], a; ret
+	gbs.push(0xC9);                                  // play: ret
+	gbs
+}
+
+#[test]
+fn select_track() {
+	let mut gameboy = Gameboy::with_gbs(&gbs()).unwrap();
+	assert_eq!(gameboy.gbs_header().unwrap().title, "Test");
+	assert_eq!(gameboy.gbs_track(), Some(1));
+	gameboy.emulate(Duration::from_millis(200));
+	assert_eq!(gameboy.read_memory(0xC100), 1);
+	let driver: Vec<u8> = (0x0150..0x0200).map(|address| gameboy.read_memory(address)).collect();
+
+	assert!(gameboy.select_gbs_track(3).is_err());
+	gameboy.select_gbs_track(2).unwrap();
+	assert_eq!(gameboy.gbs_track(), Some(2));
+	gameboy.emulate(Duration::from_millis(200));
+	assert_eq!(gameboy.read_memory(0xC100), 2);
+
+	// the rom isn't changed
+	let after: Vec<u8> = (0x0150..0x0200).map(|address| gameboy.read_memory(address)).collect();
+	assert_eq!(driver, after);
+}
+
+#[test]
+fn track_is_saved_in_save_states() {
+	let mut gameboy = Gameboy::with_gbs(&gbs()).unwrap();
+	gameboy.select_gbs_track(0).unwrap();
+	let state = gameboy.save_state().unwrap();
+
+	let mut gameboy = Gameboy::with_gbs(&gbs()).unwrap();
+	gameboy.load_state(&state[..]).unwrap();
+	assert_eq!(gameboy.gbs_track(), Some(0));
+	gameboy.emulate(Duration::from_millis(200));
+	assert_eq!(gameboy.read_memory(0xC100), 0);
+}
@@ -0,0 +1,111 @@
+extern crate agb_core;
+
+mod common;
+
+use std::time::Duration;
+
+use agb_core::gameboy::{Gameboy, EmulationEvent};
+use agb_core::gameboy::assembly;
+use agb_core::gameboy::cpu::registers::RegisterPair;
+use agb_core::gameboy::debugger::DebuggerInterface;
+use agb_core::gameboy::instructions::opcodes::{self, Mnemonic, OPCODES, CB_OPCODES};
+
+use common::{cartridge, run_to_code, CODE};
+
+const CART_ROM_ONLY: u8 = 0x00;
+const RAM_SIZE_NONE: u8 = 0x00;
+
+/// Execute a single instruction at CODE, and return how many M-Cycles it took and where pc ended up.
+/// Register pairs point to wram, and the immediate operands are 0x10 (d8, s8) or 0xC110 (d16, a16).
+fn execute(instruction: &[u8], flags: u8) -> (usize, u16) {
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, None).unwrap();
+	run_to_code(&mut gameboy);
+	gameboy.write_range(CODE, instruction);
+	gameboy.write_range(CODE + instruction.len() as u16, &[0x10, 0xC1]);
+	for pair in [RegisterPair::BC, RegisterPair::DE, RegisterPair::HL].iter() {
+		gameboy.set_register_pair(*pair, 0xC180);
+	}
+	gameboy.set_register_pair(RegisterPair::SP, 0xD000);
+	gameboy.set_register_pair(RegisterPair::AF, flags as u16);
+
+	let start = gameboy.get_cycle_counter();
+	gameboy.debug_step();
+	((gameboy.get_cycle_counter() - start) / 4, gameboy.get_registers().pc)
+}
+
+#[test]
+fn opcode_cycles_match_the_table() {
+	let mut mismatches = Vec::new();
+	let instructions = (0..256).map(|opcode| vec![opcode as u8]).chain((0..256).map(|opcode| vec![0xCB, opcode as u8]));
+	for instruction in instructions {
+		let info = match opcodes::lookup(&instruction) {
+			Some(info) => info,
+			None => continue // the prefix on its own
+		};
+		if info.mnemonic == Mnemonic::ILLEGAL || info.mnemonic == Mnemonic::PREFIX {
+			continue;
+		}
+
+		// conditions are all false with the flags reset, and all true with them set (or the other way around)
+		for flags in [0x00, 0xF0].iter() {
+			let (cycles, pc) = execute(&instruction, *flags);
+			let taken = info.is_conditional() && pc != CODE + info.length as u16;
+			let expected = if taken { info.cycles_branch } else { info.cycles };
+			if cycles != expected {
+				mismatches.push(format!("{:02X?} {} (flags {:02X}): {} cycles, expected {}",
+					instruction, assembly::get_assembly_for_instruction(&instruction).unwrap_or_default(), flags, cycles, expected));
+			}
+		}
+	}
+	assert!(mismatches.is_empty(), "\n{}", mismatches.join("\n"));
+}
+
+#[test]
+fn conditional_opcodes_take_both_branches() {
+	for info in OPCODES.iter().filter(|info| info.is_conditional()) {
+		assert!(info.cycles_branch > info.cycles);
+	}
+	for info in CB_OPCODES.iter() {
+		assert!(!info.is_conditional());
+	}
+}
+
+#[test]
+fn disassembly() {
+	let disassemble = |bytes: &[u8]| assembly::get_assembly_for_instruction(bytes).unwrap();
+	assert_eq!(disassemble(&[0x01, 0x34, 0x12]), "LD BC, 1234");
+	assert_eq!(disassemble(&[0x10, 0x00]), "STOP");
+	assert_eq!(disassemble(&[0x18, 0xFE]), "JR FE");
+	assert_eq!(disassemble(&[0x90]), "SUB A, B");
+	assert_eq!(disassemble(&[0xD6, 0x12]), "SUB 12");
+	assert_eq!(disassemble(&[0xE0, 0x40]), "LD (FF00 + 40), A");
+	assert_eq!(disassemble(&[0xE2]), "LD (C), A");
+	assert_eq!(disassemble(&[0xC7]), "RST 00H");
+	assert_eq!(disassemble(&[0xD3]), "Invalid opcode D3");
+	assert_eq!(disassemble(&[0xCB, 0x7E]), "BIT 7,(HL)");
+	assert_eq!(disassemble(&[0xCB, 0x37]), "SWAP A");
+	assert!(assembly::get_assembly_for_instruction(&[0xC3, 0x00]).is_err());
+}
+
+#[test]
+fn illegal_opcode_event_only_stops_the_debugger_once() {
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, None).unwrap();
+	run_to_code(&mut gameboy);
+	gameboy.write_range(CODE, &[0xD3]);
+	gameboy.debugger.enable();
+
+	// emulation stops right after the illegal opcode
+	let start = gameboy.get_cycle_counter();
+	gameboy.emulate(Duration::from_millis(100));
+	assert!(gameboy.get_cycle_counter() - start < 100);
+
+	// but not again while the event hasn't been polled
+	let start = gameboy.get_cycle_counter();
+	gameboy.emulate(Duration::from_millis(100));
+	assert!(gameboy.get_cycle_counter() - start >= 4_194_304 / 10);
+	match gameboy.poll_event() {
+		Some(EmulationEvent::IllegalOpcode { pc: CODE, opcode: 0xD3, .. }) => {},
+		event => panic!("expected an illegal opcode event, got {:?}", event)
+	}
+	assert!(gameboy.poll_event().is_none());
+}
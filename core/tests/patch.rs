@@ -0,0 +1,263 @@
+extern crate flate2;
+
+extern crate agb_core;
+
+use flate2::Crc;
+
+use agb_core::gameboy::cartridge::patch::{apply_patch, PatchError, PatchFormat};
+
+fn crc32(data: &[u8]) -> u32 {
+	let mut crc = Crc::new();
+	crc.update(data);
+	crc.sum()
+}
+
+fn rom() -> Vec<u8> {
+	(0..0x100).map(|i| i as u8).collect()
+}
+
+/// A UPS/BPS variable length number.
+fn number(mut value: usize) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	loop {
+		let bits = (value & 0x7F) as u8;
+		value >>= 7;
+		if value == 0 {
+			bytes.push(0x80 | bits);
+			return bytes;
+		}
+		bytes.push(bits);
+		value -= 1;
+	}
+}
+
+fn push_u32(patch: &mut Vec<u8>, value: u32) {
+	for i in 0..4 {
+		patch.push((value >> (i * 8)) as u8);
+	}
+}
+
+/// Add the source, target and patch checksums to the end of a UPS/BPS patch.
+fn finish(mut patch: Vec<u8>, source_crc: u32, target_crc: u32) -> Vec<u8> {
+	push_u32(&mut patch, source_crc);
+	push_u32(&mut patch, target_crc);
+	let patch_crc = crc32(&patch);
+	push_u32(&mut patch, patch_crc);
+	patch
+}
+
+/// A UPS patch's header and hunks (without the footer).
+fn ups_body(source: &[u8], target: &[u8]) -> Vec<u8> {
+	let mut patch = b"UPS1".to_vec();
+	patch.extend(number(source.len()));
+	patch.extend(number(target.len()));
+	let length = source.len().max(target.len());
+	let xor: Vec<u8> = (0..length).map(|i| source.get(i).unwrap_or(&0) ^ target.get(i).unwrap_or(&0)).collect();
+	let mut last = 0;
+	let mut i = 0;
+	while i < length {
+		if xor[i] == 0 {
+			i += 1;
+			continue;
+		}
+		patch.extend(number(i - last));
+		while i < length && xor[i] != 0 {
+			patch.push(xor[i]);
+			i += 1;
+		}
+		patch.push(0);
+		i += 1;
+		last = i;
+	}
+	patch
+}
+
+fn ups(source: &[u8], target: &[u8]) -> Vec<u8> {
+	finish(ups_body(source, target), crc32(source), crc32(target))
+}
+
+/// A BPS patch that reads the start of the target from the source, and the rest from the patch.
+fn bps_body(source: &[u8], target: &[u8], unchanged: usize) -> Vec<u8> {
+	let mut patch = b"BPS1".to_vec();
+	patch.extend(number(source.len()));
+	patch.extend(number(target.len()));
+	patch.extend(number(0)); // no metadata
+	patch.extend(number(((unchanged - 1) << 2) | 0)); // source read
+	patch.extend(number(((target.len() - unchanged - 1) << 2) | 1)); // target read
+	patch.extend_from_slice(&target[unchanged..]);
+	patch
+}
+
+fn bps(source: &[u8], target: &[u8], unchanged: usize) -> Vec<u8> {
+	finish(bps_body(source, target, unchanged), crc32(source), crc32(target))
+}
+
+/// The patched rom used by the UPS and BPS tests: a few bytes changed, and 16 bytes added.
+fn target() -> Vec<u8> {
+	let mut target = rom();
+	target[0x80..0x84].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+	target.extend_from_slice(&[0x55; 16]);
+	target
+}
+
+#[test]
+fn unknown_format() {
+	assert_eq!(apply_patch(&rom(), b"NOT A PATCH"), Err(PatchError::UnknownFormat));
+}
+
+#[test]
+fn ips_record() {
+	let mut patch = b"PATCH".to_vec();
+	patch.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x03, 0xAA, 0xBB, 0xCC]);
+	patch.extend_from_slice(b"EOF");
+	let patched = apply_patch(&rom(), &patch).unwrap();
+
+	let mut expected = rom();
+	expected[0x10..0x13].copy_from_slice(&[0xAA, 0xBB, 0xCC]);
+	assert_eq!(&patched[..], &expected[..]);
+}
+
+#[test]
+fn ips_rle_record() {
+	let mut patch = b"PATCH".to_vec();
+	patch.extend_from_slice(&[0x00, 0x00, 0xF8, 0x00, 0x00, 0x00, 0x10, 0x42]); // 16 * 0x42 at 0xF8, past the end
+	patch.extend_from_slice(b"EOF");
+	let patched = apply_patch(&rom(), &patch).unwrap();
+
+	let mut expected = rom();
+	expected.truncate(0xF8);
+	expected.extend_from_slice(&[0x42; 16]);
+	assert_eq!(&patched[..], &expected[..]);
+}
+
+#[test]
+fn ips_truncate_extension() {
+	let mut patch = b"PATCH".to_vec();
+	patch.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x01, 0xFF]);
+	patch.extend_from_slice(b"EOF");
+	patch.extend_from_slice(&[0x00, 0x00, 0x80]);
+	let patched = apply_patch(&rom(), &patch).unwrap();
+
+	let mut expected = rom();
+	expected[0] = 0xFF;
+	expected.truncate(0x80);
+	assert_eq!(&patched[..], &expected[..]);
+}
+
+#[test]
+fn ips_truncated() {
+	let mut patch = b"PATCH".to_vec();
+	patch.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x03, 0xAA, 0xBB, 0xCC]);
+	patch.extend_from_slice(b"EOF");
+	for length in 5..patch.len() - 3 {
+		assert_eq!(apply_patch(&rom(), &patch[..length]), Err(PatchError::Truncated(PatchFormat::Ips)));
+	}
+}
+
+#[test]
+fn ups_patch() {
+	let patched = apply_patch(&rom(), &ups(&rom(), &target())).unwrap();
+	assert_eq!(&patched[..], &target()[..]);
+}
+
+#[test]
+fn bps_patch() {
+	let patched = apply_patch(&rom(), &bps(&rom(), &target(), 0x80)).unwrap();
+	assert_eq!(&patched[..], &target()[..]);
+}
+
+#[test]
+fn source_size_mismatch() {
+	let rom = rom();
+	let expected = Err(PatchError::SourceSize { expected: rom.len(), actual: rom.len() - 1 });
+	assert_eq!(apply_patch(&rom[1..], &ups(&rom, &target())), expected);
+	assert_eq!(apply_patch(&rom[1..], &bps(&rom, &target(), 0x80)), expected);
+}
+
+#[test]
+fn source_checksum_mismatch() {
+	let mut other = rom();
+	other[0] = 0xFF;
+	let expected = Err(PatchError::SourceChecksum { expected: crc32(&rom()), actual: crc32(&other) });
+	assert_eq!(apply_patch(&other, &ups(&rom(), &target())), expected);
+	assert_eq!(apply_patch(&other, &bps(&rom(), &target(), 0x80)), expected);
+}
+
+#[test]
+fn target_checksum_mismatch() {
+	let wrong_crc = crc32(&target()) ^ 1;
+	let expected = Err(PatchError::TargetChecksum { expected: wrong_crc, actual: crc32(&target()) });
+	let patch = finish(ups_body(&rom(), &target()), crc32(&rom()), wrong_crc);
+	assert_eq!(apply_patch(&rom(), &patch), expected);
+	let patch = finish(bps_body(&rom(), &target(), 0x80), crc32(&rom()), wrong_crc);
+	assert_eq!(apply_patch(&rom(), &patch), expected);
+}
+
+#[test]
+fn patch_checksum_mismatch() {
+	for patch in [ups(&rom(), &target()), bps(&rom(), &target(), 0x80)].iter() {
+		let mut patch = patch.clone();
+		let byte = patch.len() / 2;
+		patch[byte] ^= 0xFF;
+		match apply_patch(&rom(), &patch) {
+			Err(PatchError::PatchChecksum { .. }) => {},
+			result => panic!("expected a patch checksum error, got {:?}", result)
+		}
+	}
+}
+
+#[test]
+fn truncated() {
+	// cut off in the middle of the hunks/actions, with a valid footer
+	let body = ups_body(&rom(), &target());
+	let patch = finish(body[..body.len() - 1].to_vec(), crc32(&rom()), crc32(&target()));
+	assert_eq!(apply_patch(&rom(), &patch), Err(PatchError::Truncated(PatchFormat::Ups)));
+	let body = bps_body(&rom(), &target(), 0x80);
+	let patch = finish(body[..body.len() - 1].to_vec(), crc32(&rom()), crc32(&target()));
+	assert_eq!(apply_patch(&rom(), &patch), Err(PatchError::Truncated(PatchFormat::Bps)));
+
+	// too short to have a footer
+	assert_eq!(apply_patch(&rom(), b"UPS1"), Err(PatchError::Truncated(PatchFormat::Ups)));
+	assert_eq!(apply_patch(&rom(), b"BPS1"), Err(PatchError::Truncated(PatchFormat::Bps)));
+}
+
+#[test]
+fn corrupt_patches_dont_panic() {
+	let patches = [ups(&rom(), &target()), bps(&rom(), &target(), 0x80)];
+	for patch in patches.iter() {
+		// every prefix, with and without a valid footer
+		for length in 0..patch.len() {
+			let _ = apply_patch(&rom(), &patch[..length]);
+			if length > 4 {
+				let _ = apply_patch(&rom(), &finish(patch[..length].to_vec(), crc32(&rom()), crc32(&target())));
+			}
+		}
+		// every byte of the body changed, with a valid footer
+		for byte in 4..patch.len() - 12 {
+			let mut body = patch[..patch.len() - 12].to_vec();
+			body[byte] ^= 0xFF;
+			let _ = apply_patch(&rom(), &finish(body, crc32(&rom()), crc32(&target())));
+		}
+	}
+
+	// hunks that skip so far that the position overflows
+	let mut body = b"UPS1".to_vec();
+	body.extend(number(rom().len()));
+	body.extend(number(rom().len()));
+	body.extend(number(1 << 62));
+	body.push(0);
+	body.extend(number(usize::max_value() - (1 << 62)));
+	body.push(0);
+	let patch = finish(body, crc32(&rom()), crc32(&rom()));
+	assert_eq!(apply_patch(&rom(), &patch), Err(PatchError::OutOfBounds(PatchFormat::Ups)));
+
+	// a patched rom that's too big to be allocated
+	for (magic, format) in [(b"UPS1", PatchFormat::Ups), (b"BPS1", PatchFormat::Bps)].iter() {
+		let mut body = magic.to_vec();
+		body.extend(number(rom().len()));
+		body.extend(number(usize::max_value() / 2));
+		body.extend(number(0));
+		let patch = finish(body, crc32(&rom()), crc32(&rom()));
+		assert_eq!(apply_patch(&rom(), &patch), Err(PatchError::OutOfBounds(*format)));
+	}
+}
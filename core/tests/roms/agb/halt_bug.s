@@ -0,0 +1,90 @@
+; Tests the HALT bug, and that HALT without a pending interrupt waits for one.
+;
+; When HALT is executed with IME=0 while an interrupt is already pending (IE & IF != 0),
+; the cpu doesn't halt, and pc isn't incremented after the next opcode fetch, so the byte
+; after HALT is read twice.
+;
+; Results are reported the same way as the mooneye-gb test roms: on success the registers
+; are set to the fibonacci numbers B=3, C=5, D=8, E=13, H=21, L=34 and LD B, B is executed,
+; on failure all of them are set to $42.
+;
+; Assembled with rgbds:
+;   rgbasm -o halt_bug.o halt_bug.s
+;   rgblink -o halt_bug.gb halt_bug.o
+;   rgbfix -v -p 0 -t HALT_BUG -l 0x33 halt_bug.gb
+
+SECTION "entry", ROM0[$100]
+	nop
+	jp main
+
+SECTION "header", ROM0[$104]
+	ds $150 - $104
+
+SECTION "main", ROM0[$150]
+main:
+	di
+	ld sp, $FFFE
+
+	; halt bug with a one byte instruction after HALT: inc b runs twice
+	ld a, $04 ; timer interrupt
+	ldh [$FF], a ; IE
+	ldh [$0F], a ; IF
+	ld b, 0
+	halt
+	inc b
+	ld a, b
+	cp 2
+	jr nz, fail
+
+	; halt bug with a two byte instruction after HALT: the opcode of ld a, $14 is read again as its operand,
+	; so a = $3E, and then $14 is executed as inc d
+	ld d, 0
+	halt
+	ld a, $14
+	cp $3E
+	jr nz, fail
+	ld a, d
+	cp 1
+	jr nz, fail
+
+	; no interrupt pending: the cpu halts until the timer overflows, and the next instruction runs once
+	xor a
+	ldh [$0F], a ; IF
+	ldh [$06], a ; TMA
+	ld a, $F0
+	ldh [$05], a ; TIMA
+	ld a, $05 ; timer enabled, 262144 Hz
+	ldh [$07], a ; TAC
+	ld c, 0
+	halt
+	inc c
+	ld a, c
+	cp 1
+	jr nz, fail
+	ldh a, [$0F]
+	and $04
+	jr z, fail
+
+pass:
+	ld b, 3
+	ld c, 5
+	ld d, 8
+	ld e, 13
+	ld h, 21
+	ld l, 34
+	xor a
+	ld b, b
+.loop:
+	jr .loop
+
+fail:
+	ld a, $42
+	ld b, a
+	ld c, a
+	ld d, a
+	ld e, a
+	ld h, a
+	ld l, a
+	ld b, b
+.loop:
+	jr .loop
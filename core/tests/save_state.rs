@@ -0,0 +1,49 @@
+extern crate agb_core;
+
+mod common;
+
+use agb_core::gameboy::Gameboy;
+use agb_core::gameboy::debugger::DebuggerInterface;
+
+use common::{cartridge, run_to_code};
+
+const CART_ROM_ONLY: u8 = 0x00;
+const RAM_SIZE_NONE: u8 = 0x00;
+
+/// A dmg boot rom that runs the code given, and then the rest of the boot rom's nops.
+fn boot_rom(code: &[u8]) -> Box<[u8]> {
+	let mut rom = vec![0; 0x100];
+	rom[..code.len()].copy_from_slice(code);
+	rom.into_boxed_slice()
+}
+
+#[test]
+fn state_saved_during_boot_needs_the_boot_rom() {
+	let boot_rom = boot_rom(&[0x18, 0xFE]); // jr -2
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, Some(boot_rom.clone())).unwrap();
+	for _ in 0..10 {
+		gameboy.debug_step();
+	}
+	let state = gameboy.save_state().unwrap();
+
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, None).unwrap();
+	assert!(gameboy.load_state(&state[..]).is_err());
+
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, Some(boot_rom)).unwrap();
+	gameboy.load_state(&state[..]).unwrap();
+	assert!(gameboy.boot_rom.as_ref().unwrap().mapped());
+	assert_eq!(gameboy.read_memory(0x0000), 0x18);
+}
+
+#[test]
+fn state_saved_after_boot_loads_without_the_boot_rom() {
+	let boot_rom = boot_rom(&[0x3E, 0x01, 0xE0, 0x50]); // ld a, 1; ldh [0x50], a
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, Some(boot_rom)).unwrap();
+	run_to_code(&mut gameboy);
+	let state = gameboy.save_state().unwrap();
+
+	let mut gameboy = Gameboy::new(cartridge(CART_ROM_ONLY, RAM_SIZE_NONE), None, None).unwrap();
+	gameboy.load_state(&state[..]).unwrap();
+	assert!(gameboy.boot_rom.is_none());
+	assert_eq!(gameboy.read_memory(0x0000), 0x00);
+}
@@ -9,19 +9,24 @@ mod manifest {
 	use std::convert::Into;
 	use std::time::Duration;
 
-	use agb_core::gameboy::{Gameboy, Mode};
+	use agb_core::gameboy::{Gameboy, Model};
 	use agb_core::gameboy::debugger::DebuggerInterface;
 
 	#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 	pub enum HardwareType {
-		DMG, CGB
+		DMG0, DMG, MGB, SGB, SGB2, CGB, AGB
 	}
 
-	impl Into<Mode> for HardwareType {
-		fn into(self) -> Mode {
+	impl Into<Model> for HardwareType {
+		fn into(self) -> Model {
 			match self {
-				HardwareType::DMG => Mode::DMG,
-				HardwareType::CGB => Mode::CGB
+				HardwareType::DMG0 => Model::DMG0,
+				HardwareType::DMG => Model::DMG,
+				HardwareType::MGB => Model::MGB,
+				HardwareType::SGB => Model::SGB,
+				HardwareType::SGB2 => Model::SGB2,
+				HardwareType::CGB => Model::CGB,
+				HardwareType::AGB => Model::AGB
 			}
 		}
 	}
@@ -139,34 +144,38 @@ pub mod test_runner {
 			None => None
 		};
 
-		let mut gameboy = Gameboy::new(rom, sram).expect("invalid rom file");
+		// run the test on every hardware model it's supposed to pass on
+		for hardware in manifest.hardware_versions.iter() {
+			println!("running test on {:?}", hardware);
+			let mut gameboy = Gameboy::with_model((*hardware).into(), rom.clone(), sram.clone(), None).expect("invalid rom file");
 
-		match manifest.duration {
-			TestDuration::Time(duration) => gameboy.emulate(duration),
-			TestDuration::Cycles(target_cycles) => {
-				while gameboy.get_cycle_counter() < target_cycles {
-					gameboy.debug_step();
-				}
-			},
-			TestDuration::Opcode(target_opcode) => {
-				loop {
-					let pc = gameboy.get_registers().pc;
-					let next_opcode = gameboy.read_memory(pc);
-					gameboy.debug_step();
-					if next_opcode == target_opcode {
-						break;
+			match manifest.duration {
+				TestDuration::Time(duration) => gameboy.emulate(duration),
+				TestDuration::Cycles(target_cycles) => {
+					while gameboy.get_cycle_counter() < target_cycles {
+						gameboy.debug_step();
+					}
+				},
+				TestDuration::Opcode(target_opcode) => {
+					loop {
+						let pc = gameboy.get_registers().pc;
+						let next_opcode = gameboy.read_memory(pc);
+						gameboy.debug_step();
+						if next_opcode == target_opcode {
+							break;
+						}
 					}
 				}
-			}
-		};
+			};
 
-		//check test assertions
-		manifest.registers.check(&gameboy);
-		manifest.memory.into_iter().for_each(|memory_assertion| {
-			let expected = memory_assertion.value;
-			let actual = gameboy.read_memory(memory_assertion.address);
-			assert_eq!(expected, actual);
-		});
+			//check test assertions
+			manifest.registers.check(&gameboy);
+			manifest.memory.iter().for_each(|memory_assertion| {
+				let expected = memory_assertion.value;
+				let actual = gameboy.read_memory(memory_assertion.address);
+				assert_eq!(expected, actual);
+			});
+		}
 	}
 }
 
@@ -189,6 +198,11 @@ mod mooneye {
 	use ::test_runner::run_test;
 	run_tests!(
 		add_sp_e_timing, "tests/manifests/mooneye-gb/add_sp_e_timing.json",
+		boot_regs_dmg0, "tests/manifests/mooneye-gb/boot_regs-dmg0.json",
+		boot_regs_dmgABC, "tests/manifests/mooneye-gb/boot_regs-dmgABC.json",
+		boot_regs_mgb, "tests/manifests/mooneye-gb/boot_regs-mgb.json",
+		boot_regs_sgb, "tests/manifests/mooneye-gb/boot_regs-sgb.json",
+		boot_regs_sgb2, "tests/manifests/mooneye-gb/boot_regs-sgb2.json",
 		call_timing, "tests/manifests/mooneye-gb/call_timing.json",
 		call_timing2, "tests/manifests/mooneye-gb/call_timing2.json",
 		call_cc_timing, "tests/manifests/mooneye-gb/call_cc_timing.json",
@@ -268,3 +282,11 @@ mod mooneye {
 		);
 	}
 }
+
+/* test roms written for agb (the sources are next to the roms in tests/roms/agb) */
+mod agb {
+	use ::test_runner::run_test;
+	run_tests!(
+		halt_bug, "tests/manifests/agb/halt_bug.json"
+	);
+}